@@ -20,16 +20,19 @@
 // limitations under the License.
 
 use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
 
-use amplify::ByteArray;
-use rgb::bitcoin::{Transaction as Tx, Txid};
-use rgb::commit_verify::{mpc, CommitEncode, CommitEngine};
+use amplify::{ByteArray, Bytes32};
+use rgb::bitcoin::hashes::Hash;
+use rgb::bitcoin::{Transaction as Tx, TxMerkleNode, Txid};
+use rgb::commit_verify::{mpc, CommitEncode, CommitEngine, CommitId};
+use rgb::dbc::anchor::VerifyError;
 use rgb::dbc::{self, Anchor};
-use rgb::validation::{DbcProof, EAnchor};
+use rgb::validation::{DbcError, DbcProof, EAnchor};
 use rgb::{BundleId, DiscloseHash, TransitionBundle};
 #[cfg(feature = "serde")]
 use serde_crate::{Deserialize, Serialize};
-use strict_encoding::StrictDumb;
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
 
 use crate::{MergeReveal, MergeRevealError, LIB_NAME_RGB_OPS};
 
@@ -180,6 +183,28 @@ pub enum SealWitnessMergeError {
     DbcMismatch,
 }
 
+/// Error verifying that the anchor of a [`WitnessBundle`] commits to its bundle.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum AnchorError {
+    /// the anchor MPC proof doesn't match the contract of the bundle. Details: {0}
+    #[from]
+    Mpc(mpc::InvalidProof),
+
+    /// the witness transaction doesn't commit to the bundle with the anchor. Details: {0}
+    #[from]
+    Dbc(DbcError),
+}
+
+impl From<VerifyError<DbcError>> for AnchorError {
+    fn from(err: VerifyError<DbcError>) -> Self {
+        match err {
+            VerifyError::Dbc(err) => AnchorError::Dbc(err),
+            VerifyError::Mpc(err) => AnchorError::Mpc(err),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB_OPS)]
@@ -211,6 +236,13 @@ impl SealWitness {
             return Err(SealWitnessMergeError::DbcMismatch);
         }
         self.public.merge_reveal(&other.public)?;
+        // If the other merkle block commits to the same MPC root and doesn't reveal any new
+        // message there is nothing to merge.
+        if self.merkle_block.commit_id() == other.merkle_block.commit_id()
+            && self.merkle_block.to_known_message_map() == other.merkle_block.to_known_message_map()
+        {
+            return Ok(());
+        }
         self.merkle_block.merge_reveal(&other.merkle_block)?;
         Ok(())
     }
@@ -232,14 +264,12 @@ impl ToWitnessId for PubWitness {
 
 impl MergeReveal for PubWitness {
     fn merge_reveal(&mut self, other: &Self) -> Result<(), MergeRevealError> {
-        if self == other {
-            return Ok(());
-        }
+        // NB: `PartialEq` compares txids only, so it can't be used to skip merging a revealed TX
         if self.txid() != other.txid() {
             return Err(MergeRevealError::TxidMismatch(self.txid(), other.txid()));
         }
-        if let Self::Tx(tx2) = other {
-            if let Self::Tx(tx1) = self {
+        if let Some(tx2) = other.tx() {
+            if let Some(tx1) = self.tx_mut() {
                 // Replace each input in tx1 with the one from tx2 if it has more witness or
                 // sig_script data
                 for (input1, input2) in tx1.input.iter_mut().zip(tx2.input.iter()) {
@@ -259,6 +289,22 @@ impl MergeReveal for PubWitness {
                 *self = other.clone();
             }
         }
+        // A witness with an SPV proof is preferred over a witness with the TX only
+        if let (
+            Self::Tx(tx),
+            Self::SpvProof {
+                merkle_proof,
+                block_height,
+                ..
+            },
+        ) = (&*self, other)
+        {
+            *self = Self::SpvProof {
+                tx: tx.clone(),
+                merkle_proof: *merkle_proof,
+                block_height: *block_height,
+            };
+        }
         Ok(())
     }
 }
@@ -277,8 +323,20 @@ pub enum PubWitness {
     #[strict_type(tag = 0x01)]
     #[cfg_attr(feature = "serde", serde(with = "tx_compat_serde"))]
     Tx(Tx),
+    /// Witness TX provided together with the SPV data proving its inclusion in a block.
+    #[strict_type(tag = 0x02)]
+    SpvProof {
+        #[cfg_attr(feature = "serde", serde(with = "tx_compat_serde"))]
+        tx: Tx,
+        /// Merkle node ([`TxMerkleNode`]) of the SPV proof, see [`PubWitness::merkle_proof`].
+        merkle_proof: Bytes32,
+        block_height: u32,
+    },
 }
 
+impl StrictSerialize for PubWitness {}
+impl StrictDeserialize for PubWitness {}
+
 impl PartialEq for PubWitness {
     fn eq(&self, other: &Self) -> bool { self.txid() == other.txid() }
 }
@@ -291,22 +349,69 @@ impl PartialOrd for PubWitness {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
 }
 
+/// Displays the witness as its txid.
+impl Display for PubWitness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(&self.txid(), f) }
+}
+
+impl From<Txid> for PubWitness {
+    fn from(txid: Txid) -> Self { Self::new(txid) }
+}
+
+impl From<Tx> for PubWitness {
+    fn from(tx: Tx) -> Self { Self::with(tx) }
+}
+
 impl PubWitness {
     pub fn new(txid: Txid) -> Self { Self::Txid(txid) }
 
     pub fn with(tx: Tx) -> Self { Self::Tx(tx) }
 
+    pub fn with_merkle_proof(tx: Tx, merkle_proof: TxMerkleNode, block_height: u32) -> Self {
+        Self::SpvProof {
+            tx,
+            merkle_proof: Bytes32::from_byte_array(merkle_proof.to_byte_array()),
+            block_height,
+        }
+    }
+
     pub fn txid(&self) -> Txid {
         match self {
             PubWitness::Txid(txid) => *txid,
-            PubWitness::Tx(tx) => tx.compute_txid(),
+            PubWitness::Tx(tx) | PubWitness::SpvProof { tx, .. } => tx.compute_txid(),
         }
     }
 
     pub fn tx(&self) -> Option<&Tx> {
         match self {
             PubWitness::Txid(_) => None,
-            PubWitness::Tx(tx) => Some(tx),
+            PubWitness::Tx(tx) | PubWitness::SpvProof { tx, .. } => Some(tx),
+        }
+    }
+
+    pub fn into_tx(self) -> Option<Tx> {
+        match self {
+            PubWitness::Txid(_) => None,
+            PubWitness::Tx(tx) | PubWitness::SpvProof { tx, .. } => Some(tx),
+        }
+    }
+
+    fn tx_mut(&mut self) -> Option<&mut Tx> {
+        match self {
+            PubWitness::Txid(_) => None,
+            PubWitness::Tx(tx) | PubWitness::SpvProof { tx, .. } => Some(tx),
+        }
+    }
+
+    /// Returns the merkle node and the block height of the SPV proof, if the witness has one.
+    pub fn merkle_proof(&self) -> Option<(TxMerkleNode, u32)> {
+        match self {
+            PubWitness::SpvProof {
+                merkle_proof,
+                block_height,
+                ..
+            } => Some((TxMerkleNode::from_byte_array(merkle_proof.to_byte_array()), *block_height)),
+            _ => None,
         }
     }
 }
@@ -364,4 +469,309 @@ where DbcProof: From<D>
     pub fn eanchor(&self) -> EAnchor {
         EAnchor::new(self.anchor.mpc_proof.clone(), self.anchor.dbc_proof.clone().into())
     }
+
+    /// Verifies that the anchor commits to the bundle id under the protocol id of the bundle
+    /// contract.
+    ///
+    /// If the witness transaction is known, the check is complete: the MPC commitment computed
+    /// from the anchor and the bundle id must match the one committed in the transaction. Otherwise
+    /// only the MPC proof can be checked against the contract, since the commitment it must lead
+    /// to is not known.
+    pub fn verify_anchor(&self) -> Result<(), AnchorError> {
+        let contract_id = self.bundle.known_transitions[0].transition.contract_id;
+        let protocol_id = mpc::ProtocolId::from_byte_array(contract_id.to_byte_array());
+        let message = mpc::Message::from_byte_array(self.bundle.bundle_id().to_byte_array());
+        let eanchor = self.eanchor();
+        match self.pub_witness.tx() {
+            Some(tx) => eanchor.verify(protocol_id, message, tx).map(|_| ())?,
+            None => eanchor.convolve(protocol_id, message).map(|_| ())?,
+        }
+        Ok(())
+    }
+
+    /// Number of the transitions known in the bundle.
+    pub fn transition_count(&self) -> usize { self.bundle.known_transitions.len() }
+
+    /// Number of the inputs spent by the transitions known in the bundle.
+    pub fn total_inputs(&self) -> usize {
+        self.bundle
+            .known_transitions
+            .iter()
+            .map(|kt| kt.transition.inputs.len())
+            .sum()
+    }
+
+    /// Number of the assignments, of any type, created by the transitions known in the bundle.
+    pub fn total_outputs(&self) -> usize {
+        self.bundle
+            .known_transitions
+            .iter()
+            .flat_map(|kt| kt.transition.assignments.values())
+            .map(|assigns| assigns.len_u16() as usize)
+            .sum()
+    }
+}
+
+impl<D: dbc::Proof> MergeReveal for WitnessBundle<D> {
+    fn merge_reveal(&mut self, other: &Self) -> Result<(), MergeRevealError> {
+        let bundle_id = self.bundle.bundle_id();
+        let other_bundle_id = other.bundle.bundle_id();
+        if bundle_id != other_bundle_id {
+            return Err(MergeRevealError::BundleMismatch(bundle_id, other_bundle_id));
+        }
+        if self.anchor != other.anchor {
+            return Err(MergeRevealError::AnchorsNonEqual(bundle_id));
+        }
+        self.pub_witness.merge_reveal(&other.pub_witness)?;
+        self.bundle.merge_reveal(&other.bundle)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::confinement::{
+        Confined, NonEmptyOrdMap, NonEmptyOrdSet, NonEmptyVec, SmallOrdMap, U24,
+    };
+    use rgb::bitcoin::{Amount, ScriptBuf, TxOut};
+    use rgb::commit_verify::mpc::{MerkleBlock, MerkleTree, Message, MultiSource, ProtocolId};
+    use rgb::commit_verify::TryCommitVerify;
+    use rgb::dbc::opret::OpretProof;
+    use rgb::{
+        Assign, AssignmentType, GraphSeal, KnownTransition, OpId, Operation, Opout, RevealedValue,
+        Transition, TypedAssigns,
+    };
+
+    use super::*;
+
+    fn seal_witness(messages: u8) -> SealWitness { seal_witness_with_entropy(messages, 1) }
+
+    fn seal_witness_with_entropy(messages: u8, entropy: u64) -> SealWitness {
+        let source = MultiSource {
+            messages: Confined::from_iter_checked(
+                (1..=messages).map(|no| (ProtocolId::from([no; 32]), Message::from([no; 32]))),
+            ),
+            static_entropy: Some(entropy),
+            ..default!()
+        };
+        let merkle_block = MerkleBlock::from(MerkleTree::try_commit(&source).unwrap());
+        let txid = Txid::from_str(&"01".repeat(32)).unwrap();
+        SealWitness::new(
+            PubWitness::Txid(txid),
+            merkle_block,
+            DbcProof::Opret(OpretProof::default()),
+        )
+    }
+
+    #[test]
+    fn merge_reveal_same_witness() {
+        let mut witness = seal_witness(16);
+        let other = witness.clone();
+        let known = witness.merkle_block.to_known_message_map();
+        for _ in 0..2 {
+            witness.merge_reveal(&other).unwrap();
+            assert_eq!(witness.merkle_block.to_known_message_map(), known);
+            assert_eq!(witness.merkle_block, other.merkle_block);
+        }
+    }
+
+    #[test]
+    fn merge_reveal_unrelated_mpc() {
+        let protocol_id = ProtocolId::from([1; 32]);
+        let mut witness = seal_witness_with_entropy(4, 1);
+        let mut other = seal_witness_with_entropy(4, 2);
+        witness.merkle_block.conceal_other(protocol_id).unwrap();
+        other.merkle_block.conceal_other(protocol_id).unwrap();
+        assert_eq!(
+            witness.merkle_block.to_known_message_map(),
+            other.merkle_block.to_known_message_map()
+        );
+        assert!(matches!(
+            witness.merge_reveal(&other),
+            Err(SealWitnessMergeError::MpcMismatch(mpc::MergeError::UnrelatedBlocks { .. }))
+        ));
+    }
+
+    #[test]
+    fn witness_bundle_merge_reveal() {
+        let tx = Tx {
+            version: rgb::bitcoin::transaction::Version::TWO,
+            lock_time: rgb::bitcoin::absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+        let mut bundle: WitnessBundle = strict_dumb!();
+        bundle.pub_witness = PubWitness::Txid(tx.compute_txid());
+        let mut other = bundle.clone();
+        other.pub_witness = PubWitness::Tx(tx.clone());
+
+        bundle.merge_reveal(&other).unwrap();
+        assert_eq!(bundle.pub_witness.tx(), Some(&tx));
+        // merging back a txid-only witness keeps the revealed TX
+        let txid_only = WitnessBundle {
+            pub_witness: PubWitness::Txid(tx.compute_txid()),
+            ..other.clone()
+        };
+        bundle.merge_reveal(&txid_only).unwrap();
+        assert_eq!(bundle.pub_witness.tx(), Some(&tx));
+
+        other.anchor.dbc_proof = DbcProof::Opret(OpretProof::default());
+        assert_eq!(
+            bundle.merge_reveal(&other),
+            Err(MergeRevealError::AnchorsNonEqual(bundle.bundle.bundle_id()))
+        );
+    }
+
+    #[test]
+    fn witness_bundle_anchor() {
+        let mut witness_bundle: WitnessBundle = strict_dumb!();
+        let contract_id = witness_bundle.bundle.known_transitions[0]
+            .transition
+            .contract_id;
+        let protocol_id = ProtocolId::from_byte_array(contract_id.to_byte_array());
+        let source = MultiSource {
+            messages: Confined::from_iter_checked([
+                (
+                    protocol_id,
+                    Message::from_byte_array(witness_bundle.bundle.bundle_id().to_byte_array()),
+                ),
+                (ProtocolId::from([0xAA; 32]), Message::from([0xAA; 32])),
+            ]),
+            static_entropy: Some(1),
+            ..default!()
+        };
+        let tree = MerkleTree::try_commit(&source).unwrap();
+        let commitment = tree.commit_id();
+        let mpc_proof = MerkleBlock::from(tree)
+            .to_merkle_proof(protocol_id)
+            .unwrap();
+        let tx = Tx {
+            version: rgb::bitcoin::transaction::Version::TWO,
+            lock_time: rgb::bitcoin::absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![TxOut {
+                value: Amount::ZERO,
+                script_pubkey: ScriptBuf::new_op_return(commitment.to_byte_array()),
+            }],
+        };
+        witness_bundle.pub_witness = PubWitness::with(tx);
+        witness_bundle.anchor = Anchor::new(mpc_proof, DbcProof::Opret(OpretProof::default()));
+        witness_bundle.verify_anchor().unwrap();
+
+        // the bundle id commits to the input map only
+        let mut mismatched = witness_bundle.clone();
+        let opid = mismatched.bundle.known_transitions[0].opid;
+        mismatched.bundle.input_map = NonEmptyOrdMap::with_key_value(
+            Opout::new(OpId::from_byte_array([0xBB; 32]), AssignmentType::with(0), 0),
+            opid,
+        );
+        assert_ne!(mismatched.bundle.bundle_id(), witness_bundle.bundle.bundle_id());
+        assert_eq!(mismatched.verify_anchor(), Err(AnchorError::Dbc(DbcError::CommitmentMismatch)));
+    }
+
+    #[test]
+    fn bundle_counts() {
+        let transition = |nonce: u64, inputs: u16, outputs: u32| {
+            let mut transition: Transition = strict_dumb!();
+            transition.nonce = nonce;
+            transition.inputs = NonEmptyOrdSet::from_iter_checked((0..inputs).map(|no| {
+                Opout::new(OpId::from_byte_array([nonce as u8; 32]), AssignmentType::with(0), no)
+            }))
+            .into();
+            let assigns = (0..outputs).map(|vout| {
+                Assign::revealed(GraphSeal::with_blinded_vout(vout, nonce), RevealedValue::new(100))
+            });
+            let mut typed = TypedAssigns::Fungible(strict_dumb!());
+            *typed.as_fungible_mut().unwrap() = NonEmptyVec::from_iter_checked(assigns);
+            transition.assignments =
+                SmallOrdMap::from_iter_checked([(AssignmentType::with(0), typed)]).into();
+            KnownTransition::new(transition.id(), transition)
+        };
+        let known_transitions =
+            NonEmptyVec::from_iter_checked([transition(1, 2, 3), transition(2, 1, 1)]);
+        let mut bundle: WitnessBundle = strict_dumb!();
+        bundle.bundle = TransitionBundle {
+            input_map: NonEmptyOrdMap::from_iter_checked(
+                known_transitions
+                    .iter()
+                    .flat_map(|kt| kt.transition.inputs.iter().map(|opout| (*opout, kt.opid))),
+            ),
+            known_transitions,
+        };
+        assert_eq!(bundle.transition_count(), 2);
+        assert_eq!(bundle.total_inputs(), 3);
+        assert_eq!(bundle.total_outputs(), 4);
+
+        // transitions without assignments don't contribute to the outputs
+        let dumb: WitnessBundle = strict_dumb!();
+        assert_eq!(dumb.total_outputs(), 0);
+    }
+
+    fn spv_witness() -> (Tx, PubWitness) {
+        let tx = Tx {
+            version: rgb::bitcoin::transaction::Version::TWO,
+            lock_time: rgb::bitcoin::absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+        let node = TxMerkleNode::from_byte_array([7; 32]);
+        (tx.clone(), PubWitness::with_merkle_proof(tx, node, 800_000))
+    }
+
+    #[test]
+    fn pub_witness_encoding() {
+        let (tx, spv) = spv_witness();
+        for witness in [PubWitness::new(tx.compute_txid()), PubWitness::with(tx.clone()), spv] {
+            let data = witness.to_strict_serialized::<U24>().unwrap();
+            let decoded = PubWitness::from_strict_serialized::<U24>(data.clone()).unwrap();
+            assert_eq!(decoded.txid(), witness.txid());
+            assert_eq!(decoded.tx(), witness.tx());
+            assert_eq!(decoded.merkle_proof(), witness.merkle_proof());
+            assert_eq!(decoded.to_strict_serialized::<U24>().unwrap(), data);
+        }
+        // existing tags are preserved
+        let data = PubWitness::with(tx.clone())
+            .to_strict_serialized::<U24>()
+            .unwrap();
+        assert_eq!(data[0], 0x01);
+        let (_, spv) = spv_witness();
+        assert_eq!(spv.to_strict_serialized::<U24>().unwrap()[0], 0x02);
+        assert_eq!(spv.merkle_proof(), Some((TxMerkleNode::from_byte_array([7; 32]), 800_000)));
+        assert_eq!(spv.txid(), tx.compute_txid());
+    }
+
+    #[test]
+    fn pub_witness_prefers_spv_proof() {
+        let (tx, spv) = spv_witness();
+        let mut witness = PubWitness::with(tx.clone());
+        witness.merge_reveal(&spv).unwrap();
+        assert!(witness.merkle_proof().is_some());
+        witness.merge_reveal(&PubWitness::with(tx.clone())).unwrap();
+        assert!(witness.merkle_proof().is_some());
+
+        let mut witness = PubWitness::new(tx.compute_txid());
+        witness.merge_reveal(&spv).unwrap();
+        assert_eq!(witness.tx(), Some(&tx));
+        assert!(witness.merkle_proof().is_some());
+    }
+
+    #[test]
+    fn pub_witness_from_display() {
+        let (tx, _) = spv_witness();
+        let txid = tx.compute_txid();
+        assert_eq!(PubWitness::from(txid).to_string(), txid.to_string());
+        assert_eq!(PubWitness::from(txid), PubWitness::Txid(txid));
+        let witness = PubWitness::from(tx.clone());
+        assert_eq!(witness.tx(), Some(&tx));
+        assert_eq!(witness.to_string(), txid.to_string());
+    }
+
+    #[test]
+    fn merge_reveal_dbc_mismatch() {
+        let mut witness = seal_witness(1);
+        let mut other = witness.clone();
+        other.dbc_proof = DbcProof::Tapret(strict_dumb!());
+        assert!(matches!(witness.merge_reveal(&other), Err(SealWitnessMergeError::DbcMismatch)));
+    }
 }
@@ -0,0 +1,139 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compressed serialization of consignments.
+//!
+//! Compressed data start with [`COMPRESSED_MAGIC`] followed by a single
+//! version byte, allowing the decompressor to detect the format.
+
+use std::io::{self, Read};
+
+use amplify::confinement::U32 as MAX_LEN;
+use strict_encoding::{StreamReader, StreamWriter, StrictDecode, StrictEncode};
+
+use crate::containers::Consignment;
+
+/// Magic bytes prefixing compressed consignment data.
+pub const COMPRESSED_MAGIC: [u8; 4] = *b"RGBZ";
+/// Version of the compressed consignment format.
+pub const COMPRESSED_VERSION: u8 = 1;
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DecompressError {
+    /// data are not a compressed consignment.
+    InvalidMagic,
+
+    /// unsupported compressed consignment format version {0}.
+    UnsupportedVersion(u8),
+
+    #[display(inner)]
+    #[from]
+    #[from(io::Error)]
+    Decode(strict_encoding::DecodeError),
+}
+
+impl<const TRANSFER: bool> Consignment<TRANSFER> {
+    /// Serializes the consignment with strict encoding and compresses it with
+    /// zstd, prefixing the result with the compressed format header.
+    pub fn strict_serialize_compressed(&self) -> Result<Vec<u8>, io::Error> {
+        let mut data = COMPRESSED_MAGIC.to_vec();
+        data.push(COMPRESSED_VERSION);
+
+        let mut encoder = zstd::Encoder::new(data, COMPRESSION_LEVEL)?;
+        self.strict_write(StreamWriter::new::<MAX_LEN>(&mut encoder))?;
+        encoder.finish()
+    }
+
+    /// Decompresses and deserializes the consignment produced by
+    /// [`Self::strict_serialize_compressed`].
+    pub fn strict_deserialize_compressed(mut data: impl Read) -> Result<Self, DecompressError> {
+        let mut magic = [0u8; 4];
+        let mut version = [0u8; 1];
+        data.read_exact(&mut magic)?;
+        if magic != COMPRESSED_MAGIC {
+            return Err(DecompressError::InvalidMagic);
+        }
+        data.read_exact(&mut version)?;
+        if version[0] != COMPRESSED_VERSION {
+            return Err(DecompressError::UnsupportedVersion(version[0]));
+        }
+
+        let decoder = zstd::Decoder::new(data)?;
+        let me = Self::strict_read(StreamReader::new::<MAX_LEN>(decoder))?;
+        Ok(me)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::confinement::{SmallBlob, U16};
+    use rgb::{GlobalStateType, RevealedData};
+    use strict_encoding::{StrictSerialize, StrictWriter};
+
+    use super::*;
+    use crate::containers::Contract;
+    use crate::stl::{EmbeddedMedia, MediaType};
+
+    #[test]
+    fn compressed_round_trip() {
+        let mut contract =
+            Contract::from_str(include_str!("../../asset/armored_contract.default")).unwrap();
+        let media = EmbeddedMedia {
+            ty: MediaType::with("image/png"),
+            data: SmallBlob::try_from(vec![0xAB; 50_000]).unwrap(),
+        };
+        let media = media
+            .strict_encode(StrictWriter::in_memory::<U16>())
+            .unwrap()
+            .unbox()
+            .unconfine();
+        contract
+            .genesis
+            .globals
+            .add_state(
+                GlobalStateType::with(2000),
+                RevealedData::new(SmallBlob::try_from(media).unwrap()),
+            )
+            .unwrap();
+
+        let plain = contract.to_strict_serialized::<MAX_LEN>().unwrap();
+        let compressed = contract.strict_serialize_compressed().unwrap();
+        assert_eq!(&compressed[..4], &COMPRESSED_MAGIC);
+        assert_eq!(compressed[4], COMPRESSED_VERSION);
+        assert!(compressed.len() < plain.len());
+
+        let decompressed = Contract::strict_deserialize_compressed(compressed.as_slice()).unwrap();
+        assert_eq!(decompressed.to_strict_serialized::<MAX_LEN>().unwrap(), plain);
+
+        let mut unknown = compressed.clone();
+        unknown[4] = 2;
+        assert!(matches!(
+            Contract::strict_deserialize_compressed(unknown.as_slice()),
+            Err(DecompressError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            Contract::strict_deserialize_compressed(plain.as_slice()),
+            Err(DecompressError::InvalidMagic)
+        ));
+    }
+}
@@ -25,7 +25,7 @@ use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 use std::str::FromStr;
 
-use aluvm::library::Lib;
+use aluvm::library::{Lib, LibId};
 use amplify::confinement::{Confined, LargeVec, SmallOrdMap, SmallOrdSet};
 use amplify::{ByteArray, Bytes32};
 use armor::{ArmorHeader, AsciiArmor, StrictArmor, StrictArmorError};
@@ -37,12 +37,12 @@ use rgb::validation::{
 };
 use rgb::vm::OrdOpRef;
 use rgb::{
-    impl_serde_baid64, validation, BundleId, ContractId, Genesis, GraphSeal, Operation, Schema,
-    SchemaId, TransitionBundle, Txid,
+    impl_serde_baid64, validation, BundleId, ContractId, Genesis, GraphSeal, KnownTransition, OpId,
+    Operation, Schema, SchemaId, TransitionBundle, Txid,
 };
 use rgbcore::validation::ConsignmentApi;
-use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
-use strict_types::TypeSystem;
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize, StrictWriter};
+use strict_types::{SemId, TypeSystem};
 
 use super::{
     ContainerVer, SecretSeals, WitnessBundle, ASCII_ARMOR_CONSIGNMENT_TYPE, ASCII_ARMOR_CONTRACT,
@@ -50,7 +50,7 @@ use super::{
 };
 use crate::contract::ContractData;
 use crate::info::ContractInfo;
-use crate::persistence::{MemContract, MemContractState};
+use crate::persistence::{MemContract, MemContractState, MemError};
 use crate::{SecretSeal, LIB_NAME_RGB_OPS};
 
 pub type Transfer = Consignment<true>;
@@ -156,10 +156,12 @@ impl<const TRANSFER: bool> ValidConsignment<TRANSFER> {
     }
 
     /// Return the [`ContractData`] from the consignment.
-    pub fn contract_data(&self) -> ContractData<MemContract> {
+    ///
+    /// Fails with [`MemError::StateOverflow`] if the consignment state can't be held in memory.
+    pub fn contract_data(&self) -> Result<ContractData<MemContract>, MemError> {
         let mut unfiltered =
             MemContractState::new(&self.consignment.schema, self.consignment.contract_id());
-        unfiltered.add_operation(OrdOpRef::Genesis(&self.consignment.genesis));
+        unfiltered.add_operation(OrdOpRef::Genesis(&self.consignment.genesis))?;
 
         let filter = if TRANSFER {
             let mut filter = HashMap::new();
@@ -179,7 +181,7 @@ impl<const TRANSFER: bool> ValidConsignment<TRANSFER> {
                 let ord = self.validation_status.tx_ord_map.get(&witness_id).unwrap();
                 filter.insert(witness_id, *ord);
                 unfiltered
-                    .add_operation(OrdOpRef::Transition(transition, witness_id, *ord, bundle_id));
+                    .add_operation(OrdOpRef::Transition(transition, witness_id, *ord, bundle_id))?;
             }
             filter
         } else {
@@ -188,12 +190,12 @@ impl<const TRANSFER: bool> ValidConsignment<TRANSFER> {
 
         let state = MemContract::new(filter, BTreeSet::new(), unfiltered);
         let info = ContractInfo::with(&self.consignment.genesis);
-        ContractData {
+        Ok(ContractData {
             state,
             schema: self.consignment.schema.clone(),
             types: self.consignment.types.clone(),
             info,
-        }
+        })
     }
 }
 
@@ -316,6 +318,39 @@ impl<const TRANSFER: bool> Consignment<TRANSFER> {
     #[inline]
     pub fn schema_id(&self) -> SchemaId { self.schema.schema_id() }
 
+    /// Estimates the length of the strict-serialized consignment by summing up the encoded
+    /// sizes of its components, without serializing them into a memory buffer.
+    ///
+    /// Useful for transport layers which need to pick a framing or chunking strategy before
+    /// sending the consignment.
+    pub fn estimated_size(&self) -> usize {
+        fn encoded_len(val: &impl StrictEncode) -> usize {
+            val.strict_encode(StrictWriter::counter::<{ usize::MAX }>())
+                .expect("counting writer doesn't fail")
+                .unbox()
+                .unconfine()
+                .count
+        }
+
+        encoded_len(&self.version)
+            + encoded_len(&self.transfer)
+            + encoded_len(&self.terminals)
+            + encoded_len(&self.genesis)
+            + encoded_len(&self.bundles)
+            + encoded_len(&self.schema)
+            + encoded_len(&self.types)
+            + encoded_len(&self.scripts)
+    }
+
+    /// Returns the secret seals of all the history terminals, i.e. the seals the recipient of
+    /// the consignment is expected to control.
+    ///
+    /// The seals are read from the consignment data, so they are available before it is
+    /// validated or accepted.
+    pub fn terminal_seals(&self) -> BTreeSet<SecretSeal> {
+        self.terminals.values().flatten().collect()
+    }
+
     pub fn reveal_terminal_seals<E>(
         mut self,
         f: impl Fn(SecretSeal) -> Result<Option<GraphSeal>, E>,
@@ -414,6 +449,107 @@ impl<const TRANSFER: bool> Consignment<TRANSFER> {
     }
 }
 
+impl Transfer {
+    /// Checks that the transfer contains all the data required by its recipient to validate it,
+    /// reporting everything which is missing.
+    pub fn is_self_contained(&self) -> Result<(), MissingData> {
+        let mut missing = MissingData::default();
+
+        let mut known_ops = bset![self.genesis.id()];
+        for witness_bundle in &self.bundles {
+            known_ops.extend(
+                witness_bundle
+                    .bundle
+                    .known_transitions
+                    .iter()
+                    .map(|kt| kt.opid),
+            );
+            if witness_bundle.pub_witness.tx().is_none() {
+                missing.witness_txes.insert(witness_bundle.witness_id());
+            }
+        }
+        for witness_bundle in &self.bundles {
+            for KnownTransition { transition, .. } in &witness_bundle.bundle.known_transitions {
+                for input in &transition.inputs {
+                    if !known_ops.contains(&input.op) {
+                        missing.operations.insert(input.op);
+                    }
+                }
+            }
+        }
+        for bundle_id in self.terminals.keys() {
+            if !self.bundle_ids().any(|id| id == *bundle_id) {
+                missing.bundles.insert(*bundle_id);
+            }
+        }
+        for sem_id in self.schema.types() {
+            if self.types.extract([sem_id]).is_err() {
+                missing.types.insert(sem_id);
+            }
+        }
+        for lib_id in self.schema.libs() {
+            if !self.scripts.iter().any(|lib| lib.id() == lib_id) {
+                missing.libs.insert(lib_id);
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(missing)
+        }
+    }
+}
+
+/// Data which is missing from a transfer for it to be validated by its recipient.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Error)]
+pub struct MissingData {
+    /// Terminal bundles not present in the transfer.
+    pub bundles: BTreeSet<BundleId>,
+    /// Operations spent by transfer transitions which are neither the genesis nor a transition
+    /// present in the transfer.
+    pub operations: BTreeSet<OpId>,
+    /// Witnesses for which the transfer doesn't contain the full transaction.
+    pub witness_txes: BTreeSet<Txid>,
+    /// Types used by the schema which are not present in the type system.
+    pub types: BTreeSet<SemId>,
+    /// Libraries used by the schema which are not present in the scripts.
+    pub libs: BTreeSet<LibId>,
+}
+
+impl MissingData {
+    /// Checks whether nothing is missing.
+    pub fn is_empty(&self) -> bool {
+        self.bundles.is_empty()
+            && self.operations.is_empty()
+            && self.witness_txes.is_empty()
+            && self.types.is_empty()
+            && self.libs.is_empty()
+    }
+}
+
+impl Display for MissingData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("transfer is not self-contained")?;
+        if !self.bundles.is_empty() {
+            write!(f, "; missing bundles: {:?}", self.bundles)?;
+        }
+        if !self.operations.is_empty() {
+            write!(f, "; missing operations: {:?}", self.operations)?;
+        }
+        if !self.witness_txes.is_empty() {
+            write!(f, "; witnesses without transaction: {:?}", self.witness_txes)?;
+        }
+        if !self.types.is_empty() {
+            write!(f, "; missing types: {:?}", self.types)?;
+        }
+        if !self.libs.is_empty() {
+            write!(f, "; missing libraries: {:?}", self.libs)?;
+        }
+        Ok(())
+    }
+}
+
 impl<const TRANSFER: bool> StrictArmor for Consignment<TRANSFER> {
     type Id = ConsignmentId;
     const PLATE_TITLE: &'static str = "RGB CONSIGNMENT";
@@ -478,6 +614,9 @@ impl<const TRANSFER: bool> FromStr for Consignment<TRANSFER> {
 
 #[cfg(test)]
 mod test {
+    use amplify::confinement::{NonEmptyOrdSet, U32};
+    use rgb::commit_verify::Conceal;
+
     use super::*;
 
     #[test]
@@ -489,6 +628,33 @@ mod test {
         eprintln!("{contract}");
     }
 
+    #[test]
+    fn terminal_seals() {
+        let seals = (0..3)
+            .map(|nonce| GraphSeal::with_blinded_vout(nonce, nonce as u64).conceal())
+            .collect::<Vec<_>>();
+        let mut transfer: Transfer = strict_dumb!();
+        assert!(transfer.terminal_seals().is_empty());
+
+        transfer.terminals = SmallOrdMap::from_iter_checked([
+            (
+                BundleId::from_byte_array([1; 32]),
+                SecretSeals::from(NonEmptyOrdSet::from_iter_checked(seals[..2].iter().copied())),
+            ),
+            (BundleId::from_byte_array([2; 32]), SecretSeals::from(NonEmptyOrdSet::with(seals[2]))),
+        ]);
+        assert_eq!(transfer.terminal_seals(), seals.into_iter().collect());
+    }
+
+    #[test]
+    fn estimated_size() {
+        let contract =
+            Contract::from_str(include_str!("../../asset/armored_contract.default")).unwrap();
+        let actual = contract.to_strict_serialized::<U32>().unwrap().len();
+        let estimated = contract.estimated_size();
+        assert!(estimated.abs_diff(actual) * 20 <= actual, "{estimated} vs {actual}");
+    }
+
     #[test]
     fn error_contract_strs() {
         Contract::from_str(include_str!("../../asset/armored_contract.default")).unwrap();
@@ -28,8 +28,8 @@ use strict_encoding::{StreamReader, StreamWriter, StrictDecode, StrictEncode};
 
 use crate::containers::{Contract, Kit, Transfer};
 
-const RGB_PREFIX: [u8; 4] = *b"RGB\x00";
-const MAGIC_LEN: usize = 3;
+pub(super) const RGB_PREFIX: [u8; 4] = *b"RGB\x00";
+pub(super) const MAGIC_LEN: usize = 3;
 
 #[derive(Debug, Display, Error, From)]
 #[display(doc_comments)]
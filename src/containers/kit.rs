@@ -25,7 +25,7 @@ use std::ops::Deref;
 use std::str::FromStr;
 
 use aluvm::library::Lib;
-use amplify::confinement::{SmallOrdSet, TinyOrdSet};
+use amplify::confinement::{self, SmallOrdSet, TinyOrdSet};
 use amplify::{ByteArray, Bytes32};
 use armor::{ArmorHeader, AsciiArmor, StrictArmor};
 use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
@@ -150,6 +150,20 @@ impl CommitEncode for Kit {
 #[derive(Debug)]
 pub enum KitValidationError {}
 
+/// Errors merging kits.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum KitError {
+    /// the merged kit would contain too many schemata.
+    TooManySchemata,
+
+    /// the merged kit would contain too many scripts.
+    TooManyScripts,
+
+    /// the type systems of the kits can't be merged: {0}
+    Types(confinement::Error),
+}
+
 impl Kit {
     #[inline]
     pub fn kit_id(&self) -> KitId { self.commit_id() }
@@ -161,6 +175,39 @@ impl Kit {
             kit: self,
         })
     }
+
+    /// Merges the other kit into this one, adding its schemata and scripts which are not present
+    /// yet (by their ids) and extending the type system with its types.
+    ///
+    /// The operation is atomic: if the merged kit would exceed the kit limits, this kit is left
+    /// untouched.
+    pub fn merge(&mut self, other: Kit) -> Result<(), KitError> {
+        let mut schemata = self.schemata.clone();
+        for schema in other.schemata {
+            let schema_id = schema.schema_id();
+            if !schemata.iter().any(|known| known.schema_id() == schema_id) {
+                schemata
+                    .push(schema)
+                    .map_err(|_| KitError::TooManySchemata)?;
+            }
+        }
+
+        let mut scripts = self.scripts.clone();
+        for lib in other.scripts {
+            let lib_id = lib.id();
+            if !scripts.iter().any(|known| known.id() == lib_id) {
+                scripts.push(lib).map_err(|_| KitError::TooManyScripts)?;
+            }
+        }
+
+        let mut types = self.types.clone();
+        types.extend(other.types).map_err(KitError::Types)?;
+
+        self.schemata = schemata;
+        self.scripts = scripts;
+        self.types = types;
+        Ok(())
+    }
 }
 
 impl StrictArmor for Kit {
@@ -192,7 +239,83 @@ impl FromStr for Kit {
 
 #[cfg(test)]
 mod test {
+    use strict_types::stl::std_stl;
+    use strict_types::typesys::SystemBuilder;
+
     use super::*;
+
+    fn lib(code: u8) -> Lib { Lib::with("ALU", vec![code], vec![], default!()).unwrap() }
+
+    fn named_schema(name: &str) -> Schema {
+        Schema {
+            name: strict_encoding::TypeName::from_str(name).unwrap(),
+            ..strict_dumb!()
+        }
+    }
+
+    #[test]
+    fn kit_merge() {
+        let mut kit = Kit {
+            schemata: TinyOrdSet::from_iter_checked([named_schema("First")]),
+            scripts: SmallOrdSet::from_iter_checked([lib(0), lib(1)]),
+            ..Kit::default()
+        };
+        let other = Kit {
+            schemata: TinyOrdSet::from_iter_checked([
+                named_schema("First"),
+                named_schema("Second"),
+            ]),
+            scripts: SmallOrdSet::from_iter_checked([lib(1), lib(2)]),
+            ..Kit::default()
+        };
+        kit.merge(other.clone()).unwrap();
+
+        assert_eq!(kit.schemata.len(), 2);
+        assert_eq!(kit.scripts.len(), 3);
+        let shared = lib(1).id();
+        assert_eq!(kit.scripts.iter().filter(|lib| lib.id() == shared).count(), 1);
+        for schema in &other.schemata {
+            assert!(kit.schemata.contains(schema));
+        }
+
+        // merging again doesn't change the kit
+        let merged = kit.clone();
+        kit.merge(other).unwrap();
+        assert_eq!(kit, merged);
+    }
+
+    fn std_types(names: &[&'static str]) -> TypeSystem {
+        let sys = SystemBuilder::new()
+            .import(std_stl())
+            .unwrap()
+            .finalize()
+            .unwrap();
+        sys.as_types()
+            .extract(names.iter().map(|name| *sys.resolve(*name).unwrap()))
+            .unwrap()
+    }
+
+    #[test]
+    fn kit_merge_types() {
+        let mut kit = Kit {
+            types: std_types(&["Std.Bool", "Std.Alpha"]),
+            ..Kit::default()
+        };
+        let other = Kit {
+            types: std_types(&["Std.Alpha", "Std.Dec"]),
+            ..Kit::default()
+        };
+        kit.merge(other).unwrap();
+
+        // the type shared by both kits is kept once
+        assert_eq!(kit.types.count_types().to_u32(), 3);
+        assert_eq!(kit.types, std_types(&["Std.Bool", "Std.Alpha", "Std.Dec"]));
+
+        let merged = kit.clone();
+        kit.merge(Kit::default()).unwrap();
+        assert_eq!(kit, merged);
+    }
+
     #[test]
     fn kit_str_round_trip() {
         let kit = Kit::from_str(include_str!("../../asset/armored_kit.default"))
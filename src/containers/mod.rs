@@ -29,16 +29,24 @@ mod consignment;
 mod util;
 mod partials;
 mod file;
+mod reader;
 mod kit;
+#[cfg(feature = "compression")]
+mod compression;
 
-pub use anchors::{PubWitness, SealWitness, SealWitnessMergeError, ToWitnessId, WitnessBundle};
+pub use anchors::{
+    AnchorError, PubWitness, SealWitness, SealWitnessMergeError, ToWitnessId, WitnessBundle,
+};
+#[cfg(feature = "compression")]
+pub use compression::{DecompressError, COMPRESSED_MAGIC, COMPRESSED_VERSION};
 pub use consignment::{
-    Consignment, ConsignmentExt, ConsignmentId, ConsignmentParseError, Contract, Transfer,
-    ValidConsignment, ValidContract, ValidTransfer,
+    Consignment, ConsignmentExt, ConsignmentId, ConsignmentParseError, Contract, MissingData,
+    Transfer, ValidConsignment, ValidContract, ValidTransfer,
 };
 pub use file::{FileContent, LoadError, UniversalFile};
-pub use kit::{Kit, KitId, ValidKit};
+pub use kit::{Kit, KitError, KitId, ValidKit};
 pub use partials::{Batch, Fascia};
+pub use reader::{ConsignmentReader, ConsignmentTrailer, ContractReader, TransferReader};
 pub use seal::{BuilderSeal, VoutSeal};
 pub use util::{ContainerVer, SecretSeals};
 
@@ -0,0 +1,296 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Incremental decoding of consignments, allowing to process the bundles of large consignments
+//! one by one without keeping all of them in memory.
+
+use std::collections::BTreeSet;
+use std::io::Read;
+
+use aluvm::library::Lib;
+use amplify::confinement::{Confined, SmallOrdMap, U32 as FILE_MAX_LEN};
+use rgb::validation::CONSIGNMENT_MAX_LIBS;
+use rgb::{BundleId, ContractId, Genesis, Operation, Schema};
+use strict_encoding::{DecodeError, ReadRaw, StreamReader, StrictDecode};
+use strict_types::TypeSystem;
+
+use super::file::{MAGIC_LEN, RGB_PREFIX};
+use super::{ContainerVer, Contract, FileContent, LoadError, SecretSeals, Transfer, WitnessBundle};
+
+pub type ContractReader<R> = ConsignmentReader<R, false>;
+pub type TransferReader<R> = ConsignmentReader<R, true>;
+
+/// Consignment data following its bundles.
+#[derive(Clone, Debug)]
+pub struct ConsignmentTrailer {
+    /// Schema under which contract is issued.
+    pub schema: Schema,
+    /// Type system covering all types used in schema.
+    pub types: TypeSystem,
+    /// Collection of scripts used across consignment.
+    pub scripts: Confined<BTreeSet<Lib>, 0, CONSIGNMENT_MAX_LIBS>,
+}
+
+/// Reader decoding a strict-encoded [`super::Consignment`] incrementally.
+///
+/// The consignment header (version, transfer flag, terminals and genesis) is decoded on
+/// construction, failing if the transfer flag doesn't match `TRANSFER`; then the witness bundles
+/// are decoded one at a time, either with [`Self::next_bundle`] or by iterating over the reader.
+/// Each bundle is checked to contain only transitions of the consignment contract. Since the
+/// schema, the type system and the scripts follow the bundles in the consignment data, they are
+/// decoded last by [`Self::finish`].
+///
+/// # Validation
+///
+/// The reader doesn't validate the bundles: this requires the schema, the type system and the
+/// scripts, which are known only once all the bundles are read. Thus, the bundles returned by the
+/// reader must not be trusted (e.g. stored into a stash) until the whole consignment is validated.
+#[derive(Debug)]
+pub struct ConsignmentReader<R: Read, const TRANSFER: bool> {
+    reader: StreamReader<R>,
+    version: ContainerVer,
+    terminals: SmallOrdMap<BundleId, SecretSeals>,
+    genesis: Genesis,
+    contract_id: ContractId,
+    remaining: usize,
+}
+
+impl<R: Read, const TRANSFER: bool> ConsignmentReader<R, TRANSFER> {
+    /// Starts reading the strict-encoded consignment data, decoding its header.
+    ///
+    /// Fails if the consignment transfer flag doesn't match `TRANSFER`.
+    pub fn new(data: R) -> Result<Self, LoadError> {
+        let mut reader = StreamReader::new::<FILE_MAX_LEN>(data);
+        let version = ContainerVer::strict_read(&mut reader)?;
+        let transfer = bool::strict_read(&mut reader)?;
+        if transfer != TRANSFER {
+            return Err(DecodeError::DataIntegrityError(format!(
+                "consignment transfer flag is {transfer} while {TRANSFER} was expected"
+            ))
+            .into());
+        }
+        let terminals = SmallOrdMap::strict_read(&mut reader)?;
+        let genesis = Genesis::strict_read(&mut reader)?;
+        let remaining = reader.read_raw_len::<FILE_MAX_LEN>()?;
+        Ok(Self {
+            reader,
+            version,
+            terminals,
+            contract_id: genesis.contract_id(),
+            genesis,
+            remaining,
+        })
+    }
+
+    /// Starts reading the consignment saved with [`FileContent::save`], checking its magic bytes
+    /// and decoding its header.
+    pub fn load(mut data: R) -> Result<Self, LoadError> {
+        let mut rgb = [0u8; 4];
+        let mut magic = [0u8; MAGIC_LEN];
+        data.read_exact(&mut rgb)?;
+        data.read_exact(&mut magic)?;
+        let expected = if TRANSFER { Transfer::MAGIC } else { Contract::MAGIC };
+        if rgb != RGB_PREFIX || magic != expected {
+            return Err(LoadError::InvalidMagic);
+        }
+        Self::new(data)
+    }
+
+    pub fn version(&self) -> ContainerVer { self.version }
+
+    pub fn transfer(&self) -> bool { TRANSFER }
+
+    pub fn terminals(&self) -> &SmallOrdMap<BundleId, SecretSeals> { &self.terminals }
+
+    pub fn genesis(&self) -> &Genesis { &self.genesis }
+
+    pub fn contract_id(&self) -> ContractId { self.contract_id }
+
+    /// Number of the bundles which are not read yet.
+    pub fn remaining_bundles(&self) -> usize { self.remaining }
+
+    /// Decodes the next witness bundle, returning `None` once all of them are read.
+    ///
+    /// After an error the data can't be read any further.
+    pub fn next_bundle(&mut self) -> Result<Option<WitnessBundle>, LoadError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let res = self.read_bundle();
+        self.remaining = if res.is_ok() { self.remaining - 1 } else { 0 };
+        res.map(Some)
+    }
+
+    fn read_bundle(&mut self) -> Result<WitnessBundle, LoadError> {
+        let witness_bundle = WitnessBundle::strict_read(&mut self.reader)?;
+        if let Some(kt) = witness_bundle
+            .bundle
+            .known_transitions
+            .iter()
+            .find(|kt| kt.transition.contract_id != self.contract_id)
+        {
+            return Err(DecodeError::DataIntegrityError(format!(
+                "bundle {} contains transition {} of contract {} while the consignment is for \
+                 contract {}",
+                witness_bundle.bundle.bundle_id(),
+                kt.opid,
+                kt.transition.contract_id,
+                self.contract_id
+            ))
+            .into());
+        }
+        Ok(witness_bundle)
+    }
+
+    /// Decodes the data following the bundles, skipping the bundles which were not read yet.
+    pub fn finish(mut self) -> Result<ConsignmentTrailer, LoadError> {
+        while self.next_bundle()?.is_some() {}
+        Ok(ConsignmentTrailer {
+            schema: Schema::strict_read(&mut self.reader)?,
+            types: TypeSystem::strict_read(&mut self.reader)?,
+            scripts: Confined::strict_read(&mut self.reader)?,
+        })
+    }
+}
+
+impl<R: Read, const TRANSFER: bool> Iterator for ConsignmentReader<R, TRANSFER> {
+    type Item = Result<WitnessBundle, LoadError>;
+
+    fn next(&mut self) -> Option<Self::Item> { self.next_bundle().transpose() }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { (0, Some(self.remaining)) }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use std::str::FromStr;
+
+    use amplify::confinement::NonEmptyVec;
+    use amplify::ByteArray;
+    use rgb::{KnownTransition, Transition, TransitionBundle, Txid};
+    use strict_encoding::StrictSerialize;
+
+    use super::*;
+    use crate::containers::{ConsignmentExt, PubWitness};
+
+    fn witness_bundle(contract_id: ContractId, nonce: u64) -> WitnessBundle {
+        let transition = Transition {
+            contract_id,
+            nonce,
+            ..strict_dumb!()
+        };
+        let dumb: TransitionBundle = strict_dumb!();
+        WitnessBundle {
+            pub_witness: PubWitness::new(
+                Txid::from_str(&format!("{nonce:02x}").repeat(32)).unwrap(),
+            ),
+            anchor: strict_dumb!(),
+            bundle: TransitionBundle {
+                input_map: dumb.input_map,
+                known_transitions: NonEmptyVec::with(KnownTransition::new(
+                    transition.id(),
+                    transition,
+                )),
+            },
+        }
+    }
+
+    fn transfer() -> Transfer {
+        let mut transfer: Transfer = strict_dumb!();
+        transfer.transfer = true;
+        let contract_id = transfer.contract_id();
+        for nonce in 1..=3 {
+            transfer
+                .bundles
+                .push(witness_bundle(contract_id, nonce))
+                .unwrap();
+        }
+        transfer
+    }
+
+    #[test]
+    fn stream_consignment() {
+        let transfer = transfer();
+        let data = transfer.to_strict_serialized::<FILE_MAX_LEN>().unwrap();
+
+        let mut reader = TransferReader::new(Cursor::new(data.as_slice())).unwrap();
+        assert_eq!(reader.version(), transfer.version);
+        assert!(reader.transfer());
+        assert_eq!(reader.terminals(), &transfer.terminals);
+        assert_eq!(reader.genesis(), &transfer.genesis);
+        assert_eq!(reader.remaining_bundles(), 3);
+        let bundles = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(bundles.len(), transfer.bundles.len());
+        for (streamed, decoded) in bundles.iter().zip(&transfer.bundles) {
+            assert_eq!(streamed.pub_witness, decoded.pub_witness);
+            assert_eq!(streamed.bundle, decoded.bundle);
+        }
+        let trailer = reader.finish().unwrap();
+        assert_eq!(trailer.schema, transfer.schema);
+        assert_eq!(trailer.types, transfer.types);
+        assert_eq!(trailer.scripts, transfer.scripts);
+
+        // unread bundles are skipped
+        let mut reader = TransferReader::new(Cursor::new(data.as_slice())).unwrap();
+        assert!(reader.next_bundle().unwrap().is_some());
+        assert_eq!(reader.finish().unwrap().schema, transfer.schema);
+
+        let mut file = vec![];
+        transfer.save(&mut file).unwrap();
+        assert_eq!(TransferReader::load(file.as_slice()).unwrap().count(), 3);
+        assert!(matches!(ContractReader::load(file.as_slice()), Err(LoadError::InvalidMagic)));
+    }
+
+    #[test]
+    fn stream_foreign_bundle() {
+        let mut transfer = transfer();
+        let other = ContractId::from_byte_array([0xFF; 32]);
+        transfer.bundles.push(witness_bundle(other, 4)).unwrap();
+        let data = transfer.to_strict_serialized::<FILE_MAX_LEN>().unwrap();
+
+        let mut reader = TransferReader::new(Cursor::new(data)).unwrap();
+        for _ in 0..3 {
+            reader.next_bundle().unwrap().unwrap();
+        }
+        assert!(matches!(
+            reader.next_bundle(),
+            Err(LoadError::Decode(DecodeError::DataIntegrityError(_)))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn stream_transfer_flag_mismatch() {
+        let transfer = transfer();
+        let data = transfer.to_strict_serialized::<FILE_MAX_LEN>().unwrap();
+        assert!(matches!(
+            ContractReader::new(Cursor::new(data.as_slice())),
+            Err(LoadError::Decode(DecodeError::DataIntegrityError(_)))
+        ));
+
+        let mut contract: Contract = strict_dumb!();
+        contract.transfer = false;
+        let data = contract.to_strict_serialized::<FILE_MAX_LEN>().unwrap();
+        assert!(ContractReader::new(Cursor::new(data.as_slice())).is_ok());
+        assert!(matches!(
+            TransferReader::new(Cursor::new(data.as_slice())),
+            Err(LoadError::Decode(DecodeError::DataIntegrityError(_)))
+        ));
+    }
+}
@@ -0,0 +1,136 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use invoice::Amount;
+use rgb::{Assign, ExposedSeal, RevealedValue};
+
+use crate::contract::OutputAssignment;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ChangeError {
+    /// the sum of the selected allocations overflows.
+    Overflow,
+
+    /// the selected allocations amount to {available}, which is less than the {required} to be
+    /// sent.
+    Insufficient { available: Amount, required: Amount },
+}
+
+/// Computes the amount which has to be sent back as change when spending the `selected`
+/// allocations to send `amount_to_send`.
+pub fn compute_change(
+    selected: &[OutputAssignment<RevealedValue>],
+    amount_to_send: Amount,
+) -> Result<Amount, ChangeError> {
+    let available = selected.iter().try_fold(0u64, |sum, assignment| {
+        sum.checked_add(Amount::from(assignment.state).value())
+            .ok_or(ChangeError::Overflow)
+    })?;
+    available
+        .checked_sub(amount_to_send.value())
+        .map(Amount::from)
+        .ok_or_else(|| ChangeError::Insufficient {
+            available: Amount::from(available),
+            required: amount_to_send,
+        })
+}
+
+/// Constructs the assignment of the `change` amount to the provided seal.
+pub fn change_assignment<Seal: ExposedSeal>(
+    seal: Seal,
+    change: Amount,
+) -> Assign<RevealedValue, Seal> {
+    Assign::revealed(seal, RevealedValue::new(change))
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::ByteArray;
+    use rgb::{AssignmentType, GenesisSeal, OpId, Txid};
+
+    use super::*;
+
+    fn allocations(amounts: &[u64]) -> Vec<OutputAssignment<RevealedValue>> {
+        let txid = Txid::from_str(&"01".repeat(32)).unwrap();
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(no, amount)| {
+                OutputAssignment::with_no_witness(
+                    GenesisSeal::with_blinding(txid, no as u32, no as u64),
+                    RevealedValue::new(*amount),
+                    None,
+                    OpId::from_byte_array([1; 32]),
+                    AssignmentType::with(0),
+                    no as u16,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn exact_amount() {
+        let selected = allocations(&[60, 40]);
+        assert_eq!(compute_change(&selected, Amount::from(100u64)), Ok(Amount::ZERO));
+    }
+
+    #[test]
+    fn change_amount() {
+        let selected = allocations(&[60, 40, 25]);
+        assert_eq!(compute_change(&selected, Amount::from(100u64)), Ok(Amount::from(25u64)));
+        assert_eq!(compute_change(&selected, Amount::ZERO), Ok(Amount::from(125u64)));
+    }
+
+    #[test]
+    fn insufficient_amount() {
+        let selected = allocations(&[60, 40]);
+        assert_eq!(
+            compute_change(&selected, Amount::from(101u64)),
+            Err(ChangeError::Insufficient {
+                available: Amount::from(100u64),
+                required: Amount::from(101u64),
+            })
+        );
+        assert_eq!(
+            compute_change(&[], Amount::from(1u64)),
+            Err(ChangeError::Insufficient {
+                available: Amount::ZERO,
+                required: Amount::from(1u64),
+            })
+        );
+    }
+
+    #[test]
+    fn overflowing_inputs() {
+        let selected = allocations(&[u64::MAX, 1]);
+        assert_eq!(compute_change(&selected, Amount::from(1u64)), Err(ChangeError::Overflow));
+        let selected = allocations(&[u64::MAX - 1, 1]);
+        assert_eq!(compute_change(&selected, Amount::from(u64::MAX)), Ok(Amount::ZERO));
+    }
+
+    #[test]
+    fn change_seal() {
+        let txid = Txid::from_str(&"02".repeat(32)).unwrap();
+        let seal = GenesisSeal::with_blinding(txid, 1u32, 7);
+        let assign = change_assignment(seal, Amount::from(25u64));
+        assert_eq!(assign.to_revealed(), Some((seal, RevealedValue::new(25u64))));
+    }
+}
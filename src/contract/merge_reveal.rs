@@ -52,6 +52,9 @@ pub enum MergeRevealError {
     /// anchors in anchored bundle are not equal for bundle {0}.
     AnchorsNonEqual(BundleId),
 
+    /// bundles {0} and {1} are different and can't be merge-revealed.
+    BundleMismatch(BundleId, BundleId),
+
     /// assignments have different keys.
     AssignmentsDifferentKeys,
 
@@ -136,7 +139,11 @@ impl<Seal: ExposedSeal> MergeReveal for Assignments<Seal> {
 
 impl MergeReveal for TransitionBundle {
     fn merge_reveal(&mut self, other: &Self) -> Result<(), MergeRevealError> {
-        debug_assert_eq!(self.bundle_id(), other.bundle_id());
+        let self_id = self.bundle_id();
+        let other_id = other.bundle_id();
+        if self_id != other_id {
+            return Err(MergeRevealError::BundleMismatch(self_id, other_id));
+        }
 
         let mut self_transitions = self.known_transitions.to_unconfined();
         for other in &other.known_transitions {
@@ -192,3 +199,99 @@ impl MergeReveal for Transition {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{NonEmptyOrdMap, NonEmptyOrdSet, NonEmptyVec, SmallOrdMap};
+    use amplify::ByteArray;
+    use rgb::{
+        AssignmentType, ContractId, GraphSeal, KnownTransition, Opout, RevealedValue,
+        TransitionType,
+    };
+
+    use super::*;
+
+    fn transition(nonce: u64) -> Transition {
+        let ty = AssignmentType::with(0);
+        let assign =
+            Assign::revealed(GraphSeal::with_blinded_vout(0, nonce), RevealedValue::new(1));
+        let mut assigns = TypedAssigns::Fungible(strict_dumb!());
+        *assigns.as_fungible_mut().unwrap() = NonEmptyVec::with(assign);
+        let assignments = SmallOrdMap::from_iter_checked([(ty, assigns)]);
+        Transition {
+            ffv: none!(),
+            contract_id: ContractId::from_byte_array([1; 32]),
+            nonce,
+            transition_type: TransitionType::with(0),
+            metadata: none!(),
+            globals: none!(),
+            inputs: NonEmptyOrdSet::with(Opout::new(OpId::from([2; 32]), ty, nonce as u16)).into(),
+            assignments: assignments.into(),
+            signature: none!(),
+        }
+    }
+
+    fn concealed(transition: &Transition) -> Transition {
+        let mut transition = transition.clone();
+        let assigns = transition
+            .assignments
+            .get_mut(&AssignmentType::with(0))
+            .and_then(TypedAssigns::as_fungible_mut)
+            .unwrap();
+        for assign in assigns.iter_mut() {
+            if let Assign::Revealed { seal, state } = assign.clone() {
+                *assign = Assign::ConfidentialSeal {
+                    seal: seal.conceal(),
+                    state,
+                };
+            }
+        }
+        transition
+    }
+
+    fn bundle(transitions: &[&Transition], known: Vec<Transition>) -> TransitionBundle {
+        TransitionBundle {
+            input_map: NonEmptyOrdMap::from_iter_checked(transitions.iter().flat_map(|t| {
+                let opid = t.id();
+                let inputs = t.inputs.iter().copied().collect::<Vec<_>>();
+                inputs.into_iter().map(move |opout| (opout, opid))
+            })),
+            known_transitions: NonEmptyVec::from_iter_checked(
+                known.into_iter().map(|t| KnownTransition::new(t.id(), t)),
+            ),
+        }
+    }
+
+    fn is_revealed(transition: &Transition) -> bool {
+        transition
+            .assignments
+            .get(&AssignmentType::with(0))
+            .unwrap()
+            .as_fungible()
+            .iter()
+            .all(|a| matches!(a, Assign::Revealed { .. }))
+    }
+
+    #[test]
+    fn bundle_merge_reveal() {
+        let t1 = transition(1);
+        let t2 = transition(2);
+        assert_eq!(concealed(&t2).id(), t2.id());
+
+        let mut first = bundle(&[&t1, &t2], vec![t1.clone(), concealed(&t2)]);
+        let second = bundle(&[&t1, &t2], vec![t2.clone()]);
+        first.merge_reveal(&second).unwrap();
+        assert_eq!(first.known_transitions.len(), 2);
+        assert!(first
+            .known_transitions
+            .iter()
+            .all(|kt| is_revealed(&kt.transition)));
+        assert_eq!(first.known_transitions_opids(), bset![t1.id(), t2.id()]);
+
+        let other = bundle(&[&t1], vec![t1.clone()]);
+        assert_eq!(
+            first.merge_reveal(&other),
+            Err(MergeRevealError::BundleMismatch(first.bundle_id(), other.bundle_id()))
+        );
+    }
+}
@@ -21,6 +21,7 @@
 
 mod assignments;
 mod builder;
+mod change;
 mod data;
 mod filter;
 mod linking;
@@ -30,6 +31,7 @@ pub(crate) mod resolver;
 
 pub use assignments::{KnownState, OutputAssignment, WitnessInfo};
 pub use builder::{BuilderError, ContractBuilder, TransitionBuilder};
+pub use change::{change_assignment, compute_change, ChangeError};
 pub use data::{
     AllocatedState, ContractData, ContractError, ContractOp, DataAllocation, FungibleAllocation,
     OpDirection, OwnedAllocation, RightsAllocation,
@@ -39,7 +41,7 @@ pub use linking::{LinkError, LinkableIssuerWrapper, LinkableSchemaWrapper};
 pub use merge_reveal::{MergeReveal, MergeRevealError};
 use rgb::vm::OrdOpRef;
 use rgb::{OpId, TransitionType, Txid};
-pub use schema::{IssuerWrapper, SchemaWrapper};
+pub use schema::{assignment_type_by_name, schema_assignment_name, IssuerWrapper, SchemaWrapper};
 
 use crate::LIB_NAME_RGB_OPS;
 
@@ -96,4 +98,12 @@ pub struct GlobalOut {
 impl GlobalOut {
     #[inline]
     pub fn witness_id(&self) -> Option<Txid> { self.op_witness.witness_id() }
+
+    /// Id of the operation which defined the global state.
+    #[inline]
+    pub fn opid(&self) -> OpId { self.opid }
+
+    /// Detects whether the global state was defined by the contract genesis.
+    #[inline]
+    pub fn is_genesis(&self) -> bool { matches!(self.op_witness, OpWitness::Genesis) }
 }
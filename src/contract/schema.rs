@@ -26,7 +26,26 @@ use crate::containers::{ContainerVer, Kit, ValidKit};
 use crate::contract::ContractData;
 use crate::persistence::ContractStateRead;
 use crate::validation::Scripts;
-use crate::Schema;
+use crate::{AssignmentType, Schema};
+
+/// Returns the name given by the schema to the owned state of the provided assignment type, or
+/// `None` if the schema doesn't define such assignment type.
+pub fn schema_assignment_name(schema: &Schema, ty: AssignmentType) -> Option<&str> {
+    schema
+        .owned_types
+        .get(&ty)
+        .map(|details| details.name.as_str())
+}
+
+/// Returns the assignment type which the schema defines with the provided name, or `None` if the
+/// schema doesn't have an owned state with such name.
+pub fn assignment_type_by_name(schema: &Schema, name: &str) -> Option<AssignmentType> {
+    schema
+        .owned_types
+        .iter()
+        .find(|(_, details)| details.name.as_str() == name)
+        .map(|(ty, _)| *ty)
+}
 
 /// The instances implementing this trait are used as wrappers around [`ContractData`] object,
 /// allowing a simple API matching the schema requirements.
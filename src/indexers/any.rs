@@ -23,47 +23,51 @@
 
 use std::collections::HashMap;
 
-use rgb::bitcoin::{Transaction as Tx, Txid};
+use rgb::bitcoin::{OutPoint as Outpoint, Transaction as Tx, Txid};
 use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
 use rgbcore::vm::WitnessOrd;
 use rgbcore::ChainNet;
 
+use super::{ChainNetCache, ResolveWitnessAtTip, SpentStatus};
 use crate::containers::Consignment;
 
-/// Generic struct wrapping any implementation of the [`ResolveWitness`] trait.
+/// Generic struct wrapping any implementation of the [`ResolveWitnessAtTip`] trait.
 /// It also contains a map of the [`Consignment`] TXs, non-empty if `add_consignment_txes` has been
 /// called.
 #[derive(From)]
 #[non_exhaustive]
 pub struct AnyResolver {
-    inner: Box<dyn ResolveWitness + Send>,
+    inner: Box<dyn ResolveWitnessAtTip + Send>,
     consignment_txes: HashMap<Txid, Tx>,
+    chain_net_cache: ChainNetCache,
 }
 
 impl AnyResolver {
-    /// Return an [`AnyResolver`] wrapping an [`super::electrum_blocking::ElectrumClient`].
+    /// Return an [`AnyResolver`] wrapping an [`super::electrum_blocking::ElectrumClient`], see
+    /// [`super::electrum_blocking::ElectrumClient::connect`] for the default `config`.
     #[cfg(feature = "electrum_blocking")]
     pub fn electrum_blocking(
         url: &str,
         config: Option<electrum_client::Config>,
     ) -> Result<Self, String> {
         Ok(AnyResolver {
-            inner: Box::new(super::electrum_blocking::ElectrumClient {
-                inner: electrum_client::Client::from_config(url, config.unwrap_or_default())
+            inner: Box::new(
+                super::electrum_blocking::ElectrumClient::connect(url, config)
                     .map_err(|e| e.to_string())?,
-            }),
+            ),
             consignment_txes: Default::default(),
+            chain_net_cache: default!(),
         })
     }
 
-    /// Return an [`AnyResolver`] wrapping an [`super::esplora_blocking::EsploraClient`].
+    /// Return an [`AnyResolver`] wrapping an [`super::esplora_blocking::EsploraClient`], applying
+    /// the [`super::ESPLORA_DEFAULT_TIMEOUT`] if the `builder` has no timeout set.
     #[cfg(feature = "esplora_blocking")]
     pub fn esplora_blocking(builder: esplora_client::Builder) -> Result<Self, String> {
         Ok(AnyResolver {
-            inner: Box::new(super::esplora_blocking::EsploraClient {
-                inner: esplora_client::BlockingClient::from_builder(builder),
-            }),
+            inner: Box::new(super::EsploraBuilder::from_builder(builder).build_blocking()),
             consignment_txes: Default::default(),
+            chain_net_cache: default!(),
         })
     }
 
@@ -76,22 +80,117 @@ impl AnyResolver {
         Ok(AnyResolver {
             inner: Box::new(super::mempool_blocking::MemPoolClient::new(builder.unwrap())),
             consignment_txes: Default::default(),
+            chain_net_cache: default!(),
         })
     }
 
+    /// Return an [`AnyResolver`] wrapping a [`super::bitcoincore::BitcoinCoreClient`].
+    #[cfg(feature = "bitcoincore")]
+    pub fn bitcoincore(url: &str, auth: bitcoincore_rpc::Auth) -> Result<Self, String> {
+        Ok(AnyResolver {
+            inner: Box::new(super::bitcoincore::BitcoinCoreClient::new(
+                bitcoincore_rpc::Client::new(url, auth).map_err(|e| e.to_string())?,
+            )),
+            consignment_txes: Default::default(),
+            chain_net_cache: default!(),
+        })
+    }
+
+    /// Return an [`AnyResolver`] wrapping an asynchronous resolver, whose futures are driven to
+    /// completion on the runtime of the provided `handle`.
+    ///
+    /// The consignment TXs added to the returned resolver are handled as for any other wrapped
+    /// resolver.
+    ///
+    /// # Panics and deadlocks
+    ///
+    /// Every call to the returned resolver blocks the calling thread until the future completes,
+    /// so it must not be used from within an asynchronous context: calling it from a task of the
+    /// runtime (including one driven by `handle` itself) panics, while calling it from a thread the
+    /// runtime depends on to make progress (e.g. the only worker of a current-thread runtime)
+    /// deadlocks. Use it from a dedicated thread or wrap the call into
+    /// [`tokio::task::spawn_blocking`].
+    #[cfg(feature = "async_bridge")]
+    pub fn from_async(
+        resolver: impl super::ResolveWitnessAsync + Send + 'static,
+        handle: tokio::runtime::Handle,
+    ) -> Self {
+        AnyResolver {
+            inner: Box::new(AsyncBridge {
+                inner: resolver,
+                handle,
+            }),
+            consignment_txes: Default::default(),
+            chain_net_cache: default!(),
+        }
+    }
+
     /// Add to the resolver the TXs found in the consignment bundles. Those TXs
     /// will not be resolved by an indexer and will be considered tentative.
-    /// Use with caution, this could allow accepting a consignment containing TXs that have not
-    /// been broadcasted.
-    pub fn add_consignment_txes<const TYPE: bool>(&mut self, consignment: &Consignment<TYPE>) {
-        self.consignment_txes.extend(
-            consignment
-                .bundles
-                .iter()
-                .filter_map(|bw| bw.pub_witness.tx().cloned())
-                .map(|tx| (tx.compute_txid(), tx)),
-        );
+    ///
+    /// # Security
+    ///
+    /// The injected TXs are trusted blindly: they may have never been broadcasted, or belong to
+    /// a different chain, since a raw TX carries no information about the chain it was made
+    /// for. Thus, the TXs are injected only if `allow_unverified` is explicitly set, and also the
+    /// consignment must be issued on the [`ChainNet`] the resolver was detected to work with (if
+    /// it was already detected). Returns the number of the injected TXs.
+    pub fn add_consignment_txes<const TYPE: bool>(
+        &mut self,
+        consignment: &Consignment<TYPE>,
+        allow_unverified: bool,
+    ) -> usize {
+        if !self.accepts_consignment_txes(consignment, allow_unverified) {
+            return 0;
+        }
+        let txes = consignment
+            .bundles
+            .iter()
+            .filter_map(|bw| bw.pub_witness.tx().cloned())
+            .map(|tx| (tx.compute_txid(), tx))
+            .collect::<Vec<_>>();
+        let count = txes.len();
+        self.consignment_txes.extend(txes);
+        count
+    }
+
+    /// Same as [`Self::add_consignment_txes`], but consumes the consignment, moving its TXs into
+    /// the resolver instead of cloning them.
+    pub fn add_consignment_txes_owned<const TYPE: bool>(
+        &mut self,
+        consignment: Consignment<TYPE>,
+        allow_unverified: bool,
+    ) -> usize {
+        if !self.accepts_consignment_txes(&consignment, allow_unverified) {
+            return 0;
+        }
+        let txes = consignment
+            .bundles
+            .into_iter()
+            .filter_map(|bw| bw.pub_witness.into_tx())
+            .map(|tx| (tx.compute_txid(), tx))
+            .collect::<Vec<_>>();
+        let count = txes.len();
+        self.consignment_txes.extend(txes);
+        count
     }
+
+    fn accepts_consignment_txes<const TYPE: bool>(
+        &self,
+        consignment: &Consignment<TYPE>,
+        allow_unverified: bool,
+    ) -> bool {
+        allow_unverified
+            && self
+                .chain_net()
+                .is_none_or(|chain_net| chain_net == consignment.genesis.chain_net)
+    }
+
+    /// Remove from the resolver all the TXs added from consignments, releasing their memory.
+    pub fn clear_consignment_txes(&mut self) { self.consignment_txes = empty!(); }
+
+    /// Return the number of consignment TXs known to the resolver.
+    pub fn consignment_tx_count(&self) -> usize { self.consignment_txes.len() }
 }
 
 impl ResolveWitness for AnyResolver {
@@ -104,6 +203,198 @@ impl ResolveWitness for AnyResolver {
     }
 
     fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
-        self.inner.check_chain_net(chain_net)
+        self.chain_net_cache
+            .check(chain_net, || self.inner.check_chain_net(chain_net))
+    }
+}
+
+impl ResolveWitnessAtTip for AnyResolver {
+    fn resolve_witness_at_tip(
+        &self,
+        witness_id: Txid,
+        tip_height: u32,
+    ) -> Result<WitnessStatus, WitnessResolverError> {
+        if let Some(tx) = self.consignment_txes.get(&witness_id) {
+            Ok(WitnessStatus::Resolved(tx.clone(), WitnessOrd::Tentative))
+        } else {
+            self.inner.resolve_witness_at_tip(witness_id, tip_height)
+        }
+    }
+
+    fn chain_net(&self) -> Option<ChainNet> { self.chain_net_cache.detected() }
+
+    fn resolve_spent(&self, outpoint: Outpoint) -> Result<SpentStatus, WitnessResolverError> {
+        self.inner.resolve_spent(outpoint)
+    }
+}
+
+/// Blocking adapter of an asynchronous resolver, see [`AnyResolver::from_async`].
+#[cfg(feature = "async_bridge")]
+struct AsyncBridge<R: super::ResolveWitnessAsync> {
+    inner: R,
+    handle: tokio::runtime::Handle,
+}
+
+#[cfg(feature = "async_bridge")]
+impl<R: super::ResolveWitnessAsync> ResolveWitness for AsyncBridge<R> {
+    fn resolve_witness(&self, witness_id: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        self.handle.block_on(self.inner.resolve_witness(witness_id))
+    }
+
+    fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        self.handle.block_on(self.inner.check_chain_net(chain_net))
+    }
+}
+
+#[cfg(feature = "async_bridge")]
+impl<R: super::ResolveWitnessAsync> ResolveWitnessAtTip for AsyncBridge<R> {}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::LargeVec;
+    use rgb::bitcoin::absolute::LockTime;
+    use rgb::bitcoin::transaction::Version;
+
+    use super::*;
+    use crate::containers::{PubWitness, WitnessBundle};
+    use crate::indexers::mock::{txid, MockResolver};
+    use crate::indexers::OfflineResolver;
+    #[cfg(feature = "async_bridge")]
+    use crate::indexers::ResolveWitnessAsync;
+
+    #[test]
+    fn detected_chain_net() {
+        let resolver = AnyResolver {
+            inner: Box::new(OfflineResolver::new(ChainNet::BitcoinSignet)),
+            consignment_txes: empty!(),
+            chain_net_cache: default!(),
+        };
+        assert_eq!(resolver.chain_net(), None);
+        assert!(resolver.check_chain_net(ChainNet::BitcoinMainnet).is_err());
+        assert_eq!(resolver.chain_net(), None);
+        assert!(resolver.check_chain_net(ChainNet::BitcoinSignet).is_ok());
+        assert_eq!(resolver.chain_net(), Some(ChainNet::BitcoinSignet));
+    }
+
+    #[test]
+    fn spent_status_forwarding() {
+        let mut inner = MockResolver::unresolved();
+        inner.outputs.insert(Outpoint::new(txid(1), 0), None);
+        inner
+            .outputs
+            .insert(Outpoint::new(txid(1), 1), Some(txid(2)));
+        let resolver = AnyResolver {
+            inner: Box::new(inner),
+            consignment_txes: empty!(),
+            chain_net_cache: default!(),
+        };
+        assert_eq!(resolver.resolve_spent(Outpoint::new(txid(1), 0)), Ok(SpentStatus::Unspent));
+        assert_eq!(
+            resolver.resolve_spent(Outpoint::new(txid(1), 1)),
+            Ok(SpentStatus::Spent { by: txid(2) })
+        );
+        assert_eq!(resolver.resolve_spent(Outpoint::new(txid(1), 2)), Ok(SpentStatus::Unknown));
+    }
+
+    #[test]
+    fn consignment_txes_injection() {
+        let tx = Tx {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+        let mut witness_bundle: WitnessBundle = strict_dumb!();
+        witness_bundle.pub_witness = PubWitness::with(tx.clone());
+        let mut consignment: Consignment<true> = strict_dumb!();
+        consignment.genesis.chain_net = ChainNet::BitcoinSignet;
+        consignment.bundles = LargeVec::from_iter_checked([witness_bundle]);
+
+        let mut resolver = AnyResolver {
+            inner: Box::new(OfflineResolver::new(ChainNet::BitcoinSignet)),
+            consignment_txes: empty!(),
+            chain_net_cache: default!(),
+        };
+        assert_eq!(resolver.add_consignment_txes(&consignment, false), 0);
+        assert_eq!(resolver.consignment_tx_count(), 0);
+        assert!(matches!(
+            resolver.resolve_witness(tx.compute_txid()),
+            Ok(WitnessStatus::Unresolved)
+        ));
+
+        // consignment from a chain other than the detected one is skipped
+        resolver
+            .check_chain_net(ChainNet::BitcoinMainnet)
+            .unwrap_err();
+        resolver.check_chain_net(ChainNet::BitcoinSignet).unwrap();
+        consignment.genesis.chain_net = ChainNet::BitcoinMainnet;
+        assert_eq!(resolver.add_consignment_txes(&consignment, true), 0);
+
+        consignment.genesis.chain_net = ChainNet::BitcoinSignet;
+        assert_eq!(resolver.add_consignment_txes_owned(consignment, true), 1);
+        assert!(matches!(
+            resolver.resolve_witness(tx.compute_txid()),
+            Ok(WitnessStatus::Resolved(_, WitnessOrd::Tentative))
+        ));
+    }
+
+    #[cfg(feature = "async_bridge")]
+    struct MockAsyncResolver {
+        tx: Tx,
+    }
+
+    #[cfg(feature = "async_bridge")]
+    #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+    impl ResolveWitnessAsync for MockAsyncResolver {
+        async fn resolve_witness(
+            &self,
+            witness_id: Txid,
+        ) -> Result<WitnessStatus, WitnessResolverError> {
+            if witness_id == self.tx.compute_txid() {
+                Ok(WitnessStatus::Resolved(self.tx.clone(), WitnessOrd::Ignored))
+            } else {
+                Ok(WitnessStatus::Unresolved)
+            }
+        }
+
+        async fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+            if chain_net == ChainNet::BitcoinRegtest {
+                Ok(())
+            } else {
+                Err(WitnessResolverError::WrongChainNet)
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "async_bridge")]
+    fn async_bridge() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let tx = Tx {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+        let txid = tx.compute_txid();
+        let mut resolver =
+            AnyResolver::from_async(MockAsyncResolver { tx: tx.clone() }, runtime.handle().clone());
+
+        assert!(resolver.check_chain_net(ChainNet::BitcoinRegtest).is_ok());
+        assert!(resolver.check_chain_net(ChainNet::BitcoinMainnet).is_err());
+        assert!(matches!(
+            resolver.resolve_witness(txid).unwrap(),
+            WitnessStatus::Resolved(_, WitnessOrd::Ignored)
+        ));
+
+        // consignment TXs take precedence over the wrapped async resolver
+        resolver.consignment_txes.insert(txid, tx);
+        assert!(matches!(
+            resolver.resolve_witness(txid).unwrap(),
+            WitnessStatus::Resolved(_, WitnessOrd::Tentative)
+        ));
     }
 }
@@ -0,0 +1,283 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::num::NonZeroU32;
+
+pub use bitcoincore_rpc;
+use bitcoincore_rpc::jsonrpc::error::RpcError;
+use bitcoincore_rpc::{jsonrpc, Client, RpcApi};
+use rgb::bitcoin::constants::ChainHash;
+use rgb::bitcoin::Txid;
+use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgbcore::vm::WitnessOrd;
+use rgbcore::ChainNet;
+
+use super::ChainNetCache;
+
+/// RPC error code returned by Bitcoin Core when the requested transaction is unknown.
+const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+
+/// Wrapper of a Bitcoin Core RPC client, necessary to implement the foreign `ResolveWitness`
+/// trait.
+///
+/// NB: in order to resolve transactions which are neither in the mempool nor in the node wallet,
+/// the node must be run with `txindex=1`.
+///
+/// The outcome of [`ResolveWitness::check_chain_net`] is cached in `chain_net_cache`, so the
+/// node is queried only on the first check for each [`ChainNet`].
+pub struct BitcoinCoreClient {
+    pub inner: Client,
+    pub chain_net_cache: ChainNetCache,
+}
+
+impl BitcoinCoreClient {
+    /// Return a [`BitcoinCoreClient`] wrapping the `inner` client.
+    pub fn new(inner: Client) -> Self {
+        Self {
+            inner,
+            chain_net_cache: default!(),
+        }
+    }
+}
+
+impl ResolveWitness for BitcoinCoreClient {
+    fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        self.chain_net_cache.check(chain_net, || {
+            // check the node is for the correct network
+            let block_hash = self
+                .inner
+                .get_block_hash(0)
+                .map_err(|e| WitnessResolverError::ResolverIssue(None, e.to_string()))?;
+            let chain_hash = ChainHash::from_genesis_block_hash(block_hash);
+            if chain_net.chain_hash() != chain_hash {
+                return Err(WitnessResolverError::WrongChainNet);
+            }
+            Ok(())
+        })
+    }
+
+    fn resolve_witness(&self, txid: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        let tx_info = match self.inner.get_raw_transaction_info(&txid, None) {
+            Err(bitcoincore_rpc::Error::JsonRpc(jsonrpc::Error::Rpc(RpcError {
+                code, ..
+            }))) if code == RPC_INVALID_ADDRESS_OR_KEY => {
+                return Ok(WitnessStatus::Unresolved);
+            }
+            Err(e) => return Err(WitnessResolverError::ResolverIssue(Some(txid), e.to_string())),
+            Ok(info) => info,
+        };
+        let tx = tx_info
+            .transaction()
+            .map_err(|_| WitnessResolverError::InvalidResolverData)?;
+
+        let Some(block_hash) = tx_info
+            .blockhash
+            .filter(|_| tx_info.confirmations > Some(0))
+        else {
+            return Ok(WitnessStatus::Resolved(tx, WitnessOrd::Tentative));
+        };
+        let header = self
+            .inner
+            .get_block_header_info(&block_hash)
+            .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
+        // a negative number of confirmations means the block is not in the active chain
+        if header.confirmations < 1 {
+            return Ok(WitnessStatus::Resolved(tx, WitnessOrd::Tentative));
+        }
+        let height = u32::try_from(header.height)
+            .ok()
+            .and_then(NonZeroU32::new)
+            .ok_or(WitnessResolverError::InvalidResolverData)?;
+        let pos = self
+            .chain_net_cache
+            .witness_pos(height, header.time as i64)
+            .ok_or(WitnessResolverError::InvalidResolverData)?;
+
+        Ok(WitnessStatus::Resolved(tx, WitnessOrd::Mined(pos)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+
+    use bitcoincore_rpc::jsonrpc::{Request, Response, Transport};
+    use rgb::bitcoin::constants::genesis_block;
+    use rgb::bitcoin::{consensus, Network, OutPoint as Outpoint, Transaction as Tx};
+    use rgbcore::vm::WitnessPos;
+    use serde_json::json;
+    use serde_json::value::RawValue;
+
+    use super::*;
+    use crate::indexers::mock::{tx, txid};
+
+    const BLOCK_TIME: i64 = 1_700_000_000;
+
+    /// JSON-RPC transport answering with the configured result or error for each method and
+    /// counting the calls.
+    struct MockRpc {
+        responses: HashMap<&'static str, Result<serde_json::Value, i32>>,
+        calls: Arc<Mutex<HashMap<String, usize>>>,
+    }
+
+    impl Transport for MockRpc {
+        fn send_request(&self, req: Request) -> Result<Response, jsonrpc::Error> {
+            *self
+                .calls
+                .lock()
+                .unwrap()
+                .entry(req.method.to_owned())
+                .or_default() += 1;
+            let (result, error) = match self.responses.get(req.method) {
+                Some(Ok(value)) => (Some(RawValue::from_string(value.to_string()).unwrap()), None),
+                Some(Err(code)) => (
+                    None,
+                    Some(RpcError {
+                        code: *code,
+                        message: s!("mock error"),
+                        data: None,
+                    }),
+                ),
+                None => panic!("unexpected call of {}", req.method),
+            };
+            Ok(Response {
+                result,
+                error,
+                id: req.id,
+                jsonrpc: Some(s!("2.0")),
+            })
+        }
+
+        fn send_batch(&self, _: &[Request]) -> Result<Vec<Response>, jsonrpc::Error> {
+            unimplemented!()
+        }
+
+        fn fmt_target(&self, f: &mut fmt::Formatter) -> fmt::Result { f.write_str("mock") }
+    }
+
+    impl MockRpc {
+        /// Node knowing the `tx` as mined in a block at height 100, which has the given number of
+        /// `confirmations`.
+        fn with_tx(tx: &Tx, confirmations: i32) -> Self {
+            let block_hash = genesis_block(Network::Bitcoin).block_hash();
+            let tx_info = json!({
+                "hex": consensus::encode::serialize_hex(tx),
+                "txid": tx.compute_txid(),
+                "hash": tx.compute_wtxid(),
+                "size": 0,
+                "vsize": 0,
+                "version": 2,
+                "locktime": 0,
+                "vin": [],
+                "vout": [],
+                "blockhash": block_hash,
+                "confirmations": 1,
+            });
+            let header = json!({
+                "hash": block_hash,
+                "confirmations": confirmations,
+                "height": 100,
+                "version": 1,
+                "merkleroot": "00".repeat(32),
+                "time": BLOCK_TIME,
+                "nonce": 0,
+                "bits": "1d00ffff",
+                "difficulty": 1.0,
+                "chainwork": "00",
+                "nTx": 1,
+            });
+            Self {
+                responses: HashMap::from([
+                    ("getrawtransaction", Ok(tx_info)),
+                    ("getblockheader", Ok(header)),
+                    ("getblockhash", Ok(json!(block_hash))),
+                ]),
+                calls: default!(),
+            }
+        }
+
+        /// Returns a client using this transport, together with the number of calls of each
+        /// method.
+        fn into_client(self) -> (BitcoinCoreClient, Arc<Mutex<HashMap<String, usize>>>) {
+            let calls = self.calls.clone();
+            let client = Client::from_jsonrpc(jsonrpc::Client::with_transport(self));
+            (BitcoinCoreClient::new(client), calls)
+        }
+    }
+
+    #[test]
+    fn unknown_tx() {
+        let rpc = MockRpc {
+            responses: HashMap::from([("getrawtransaction", Err(RPC_INVALID_ADDRESS_OR_KEY))]),
+            calls: default!(),
+        };
+        let (client, _) = rpc.into_client();
+        assert!(matches!(client.resolve_witness(txid(1)), Ok(WitnessStatus::Unresolved)));
+
+        let rpc = MockRpc {
+            responses: HashMap::from([("getrawtransaction", Err(-1))]),
+            calls: default!(),
+        };
+        let (client, _) = rpc.into_client();
+        assert!(matches!(
+            client.resolve_witness(txid(1)),
+            Err(WitnessResolverError::ResolverIssue(Some(_), _))
+        ));
+    }
+
+    #[test]
+    fn stale_block_tx() {
+        let tx = tx(&[Outpoint::new(txid(1), 0)], &[1000]);
+        let (client, _) = MockRpc::with_tx(&tx, -1).into_client();
+        assert!(matches!(
+            client.resolve_witness(tx.compute_txid()),
+            Ok(WitnessStatus::Resolved(_, WitnessOrd::Tentative))
+        ));
+    }
+
+    #[test]
+    fn mined_tx() {
+        let tx = tx(&[Outpoint::new(txid(1), 0)], &[1000]);
+        let (client, _) = MockRpc::with_tx(&tx, 3).into_client();
+        let height = NonZeroU32::new(100).unwrap();
+        assert_eq!(
+            client
+                .resolve_witness(tx.compute_txid())
+                .unwrap()
+                .witness_ord(),
+            WitnessOrd::Mined(WitnessPos::bitcoin(height, BLOCK_TIME).unwrap())
+        );
+    }
+
+    #[test]
+    fn cached_chain_net() {
+        let tx = tx(&[Outpoint::new(txid(1), 0)], &[1000]);
+        let (client, calls) = MockRpc::with_tx(&tx, 1).into_client();
+        for _ in 0..2 {
+            client.check_chain_net(ChainNet::BitcoinMainnet).unwrap();
+            assert!(matches!(
+                client.check_chain_net(ChainNet::BitcoinSignet),
+                Err(WitnessResolverError::WrongChainNet)
+            ));
+        }
+        assert_eq!(calls.lock().unwrap().get("getblockhash"), Some(&2));
+        assert_eq!(client.chain_net_cache.detected(), Some(ChainNet::BitcoinMainnet));
+    }
+}
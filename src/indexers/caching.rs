@@ -0,0 +1,174 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, PoisonError};
+
+use rgb::bitcoin::{Transaction as Tx, Txid};
+use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgbcore::vm::WitnessOrd;
+use rgbcore::ChainNet;
+
+/// Resolver wrapping any implementation of the [`ResolveWitness`] trait which memoizes the
+/// witnesses found to be mined, so that they are not requested from the inner resolver again.
+///
+/// Tentative, archived, ignored and unresolved witnesses are never cached, since their status may
+/// change at any time. A resolver created with [`CachingResolver::with_capacity`] keeps at most
+/// the given number of witnesses, evicting the least recently used ones.
+pub struct CachingResolver<R: ResolveWitness> {
+    inner: R,
+    cache: Mutex<WitnessCache>,
+}
+
+impl<R: ResolveWitness> CachingResolver<R> {
+    /// Return a [`CachingResolver`] wrapping the `inner` resolver with an unbounded cache.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(WitnessCache::new(None)),
+        }
+    }
+
+    /// Return a [`CachingResolver`] wrapping the `inner` resolver with a cache holding up to
+    /// `cap` witnesses.
+    pub fn with_capacity(inner: R, cap: usize) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(WitnessCache::new(Some(cap))),
+        }
+    }
+
+    /// Return the wrapped resolver, dropping the cache.
+    pub fn into_inner(self) -> R { self.inner }
+}
+
+impl<R: ResolveWitness> ResolveWitness for CachingResolver<R> {
+    fn resolve_witness(&self, witness_id: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        if let Some(status) = self
+            .cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(witness_id)
+        {
+            return Ok(status);
+        }
+        let status = self.inner.resolve_witness(witness_id)?;
+        if let WitnessStatus::Resolved(tx, ord @ WitnessOrd::Mined(_)) = &status {
+            self.cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(witness_id, tx.clone(), *ord);
+        }
+        Ok(status)
+    }
+
+    fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        self.inner.check_chain_net(chain_net)
+    }
+}
+
+struct WitnessCache {
+    capacity: Option<usize>,
+    entries: HashMap<Txid, (Tx, WitnessOrd, u64)>,
+    recency: BTreeMap<u64, Txid>,
+    tick: u64,
+}
+
+impl WitnessCache {
+    fn new(capacity: Option<usize>) -> Self {
+        Self {
+            capacity,
+            entries: empty!(),
+            recency: empty!(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, witness_id: Txid) -> Option<WitnessStatus> {
+        self.tick += 1;
+        let (tx, ord, used) = self.entries.get_mut(&witness_id)?;
+        self.recency.remove(used);
+        self.recency.insert(self.tick, witness_id);
+        *used = self.tick;
+        Some(WitnessStatus::Resolved(tx.clone(), *ord))
+    }
+
+    fn insert(&mut self, witness_id: Txid, tx: Tx, ord: WitnessOrd) {
+        if self.capacity == Some(0) {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, _, used)) = self.entries.insert(witness_id, (tx, ord, self.tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.tick, witness_id);
+        if let Some(capacity) = self.capacity {
+            while self.entries.len() > capacity {
+                let Some((_, evicted)) = self.recency.pop_first() else {
+                    break;
+                };
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU32;
+
+    use rgbcore::vm::WitnessPos;
+
+    use super::*;
+    use crate::indexers::mock::{txid, MockResolver};
+
+    fn mined() -> WitnessOrd {
+        WitnessOrd::Mined(WitnessPos::bitcoin(NonZeroU32::new(100).unwrap(), 1231006505).unwrap())
+    }
+
+    #[test]
+    fn mined_witness_is_cached() {
+        let resolver = CachingResolver::new(MockResolver::resolved(mined()));
+        let first = resolver.resolve_witness(txid(1)).unwrap();
+        let second = resolver.resolve_witness(txid(1)).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(resolver.into_inner().calls(), 1);
+    }
+
+    #[test]
+    fn tentative_witness_is_not_cached() {
+        let resolver = CachingResolver::new(MockResolver::resolved(WitnessOrd::Tentative));
+        resolver.resolve_witness(txid(1)).unwrap();
+        resolver.resolve_witness(txid(1)).unwrap();
+        assert_eq!(resolver.into_inner().calls(), 2);
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let resolver = CachingResolver::with_capacity(MockResolver::resolved(mined()), 2);
+        resolver.resolve_witness(txid(1)).unwrap();
+        resolver.resolve_witness(txid(2)).unwrap();
+        resolver.resolve_witness(txid(1)).unwrap();
+        resolver.resolve_witness(txid(3)).unwrap();
+        assert_eq!(resolver.inner.calls(), 3);
+        resolver.resolve_witness(txid(1)).unwrap();
+        assert_eq!(resolver.inner.calls(), 3);
+        resolver.resolve_witness(txid(2)).unwrap();
+        assert_eq!(resolver.inner.calls(), 4);
+    }
+}
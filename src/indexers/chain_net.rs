@@ -0,0 +1,207 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+
+use rgbcore::validation::WitnessResolverError;
+use rgbcore::vm::WitnessPos;
+use rgbcore::{ChainNet, Layer1};
+
+/// Constructs the [`WitnessPos`] of a block at `height` with the given `timestamp` on the
+/// `layer1` blockchain.
+pub fn witness_pos(layer1: Layer1, height: NonZeroU32, timestamp: i64) -> Option<WitnessPos> {
+    match layer1 {
+        Layer1::Bitcoin => WitnessPos::bitcoin(height, timestamp),
+        Layer1::Liquid => WitnessPos::liquid(height, timestamp),
+    }
+}
+
+/// Cache of the outcomes of [`ResolveWitness::check_chain_net`] for resolvers whose chain can't
+/// change during their lifetime.
+///
+/// Both successful checks and checks failing with [`WitnessResolverError::WrongChainNet`] are
+/// cached, so that a misconfigured endpoint is not queried again. Other errors are transient and
+/// are never cached.
+///
+/// [`ResolveWitness::check_chain_net`]: rgbcore::validation::ResolveWitness::check_chain_net
+#[derive(Debug, Default)]
+pub struct ChainNetCache(Mutex<HashMap<ChainNet, bool>>);
+
+impl ChainNetCache {
+    /// Returns the cached outcome of the check for `chain_net`, performing it with `check` only
+    /// if it is not known yet.
+    pub fn check(
+        &self,
+        chain_net: ChainNet,
+        check: impl FnOnce() -> Result<(), WitnessResolverError>,
+    ) -> Result<(), WitnessResolverError> {
+        if let Some(res) = self.cached_res(chain_net) {
+            return res;
+        }
+        self.record(chain_net, check())
+    }
+
+    /// Asynchronous version of [`Self::check`], for resolvers performing the check with a
+    /// [`Future`].
+    pub async fn check_async<F: Future<Output = Result<(), WitnessResolverError>>>(
+        &self,
+        chain_net: ChainNet,
+        check: impl FnOnce() -> F,
+    ) -> Result<(), WitnessResolverError> {
+        if let Some(res) = self.cached_res(chain_net) {
+            return res;
+        }
+        self.record(chain_net, check().await)
+    }
+
+    fn cached_res(&self, chain_net: ChainNet) -> Option<Result<(), WitnessResolverError>> {
+        self.cached(chain_net).map(|matches| {
+            if matches {
+                Ok(())
+            } else {
+                Err(WitnessResolverError::WrongChainNet)
+            }
+        })
+    }
+
+    fn record(
+        &self,
+        chain_net: ChainNet,
+        res: Result<(), WitnessResolverError>,
+    ) -> Result<(), WitnessResolverError> {
+        let matches = match res {
+            Ok(()) => true,
+            Err(WitnessResolverError::WrongChainNet) => false,
+            Err(_) => return res,
+        };
+        self.0
+            .lock()
+            .expect("poisoned chain-net cache")
+            .insert(chain_net, matches);
+        res
+    }
+
+    /// Returns whether the resolver is known to match (`true`) or not to match (`false`) the
+    /// given `chain_net`, or `None` if it was not checked yet.
+    pub fn cached(&self, chain_net: ChainNet) -> Option<bool> {
+        self.0
+            .lock()
+            .expect("poisoned chain-net cache")
+            .get(&chain_net)
+            .copied()
+    }
+
+    /// Returns the [`ChainNet`] the resolver was successfully checked against, if any.
+    pub fn detected(&self) -> Option<ChainNet> {
+        self.0
+            .lock()
+            .expect("poisoned chain-net cache")
+            .iter()
+            .find(|(_, matches)| **matches)
+            .map(|(chain_net, _)| *chain_net)
+    }
+
+    /// Returns the [`Layer1`] of the detected [`ChainNet`], falling back to bitcoin if no chain
+    /// was detected yet.
+    pub fn layer1(&self) -> Layer1 {
+        self.detected()
+            .map(|chain_net| chain_net.layer1())
+            .unwrap_or(Layer1::Bitcoin)
+    }
+
+    /// Constructs the [`WitnessPos`] of a block on the [`Self::layer1`] blockchain, see
+    /// [`witness_pos`].
+    pub fn witness_pos(&self, height: NonZeroU32, timestamp: i64) -> Option<WitnessPos> {
+        witness_pos(self.layer1(), height, timestamp)
+    }
+
+    /// Forgets all the cached outcomes, e.g. after the resolver endpoint is reconfigured.
+    pub fn clear(&self) { self.0.lock().expect("poisoned chain-net cache").clear(); }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn cached_check() {
+        let cache = ChainNetCache::default();
+        let calls = Cell::new(0);
+        let check = |res: Result<(), WitnessResolverError>| {
+            let calls = &calls;
+            move || {
+                calls.set(calls.get() + 1);
+                res
+            }
+        };
+
+        assert!(cache.check(ChainNet::BitcoinMainnet, check(Ok(()))).is_ok());
+        assert!(cache.check(ChainNet::BitcoinMainnet, check(Ok(()))).is_ok());
+        assert_eq!(calls.get(), 1);
+        assert_eq!(cache.detected(), Some(ChainNet::BitcoinMainnet));
+
+        let wrong = Err(WitnessResolverError::WrongChainNet);
+        assert!(cache
+            .check(ChainNet::BitcoinTestnet4, check(wrong))
+            .is_err());
+        assert!(matches!(
+            cache.check(ChainNet::BitcoinTestnet4, check(Ok(()))),
+            Err(WitnessResolverError::WrongChainNet)
+        ));
+        assert_eq!(calls.get(), 2);
+        assert_eq!(cache.cached(ChainNet::BitcoinTestnet4), Some(false));
+
+        let issue = Err(WitnessResolverError::ResolverIssue(None, s!("offline")));
+        assert!(cache.check(ChainNet::BitcoinSignet, check(issue)).is_err());
+        assert_eq!(cache.cached(ChainNet::BitcoinSignet), None);
+        assert!(cache.check(ChainNet::BitcoinSignet, check(Ok(()))).is_ok());
+        assert_eq!(calls.get(), 4);
+
+        cache.clear();
+        assert_eq!(cache.cached(ChainNet::BitcoinMainnet), None);
+        assert_eq!(cache.detected(), None);
+    }
+
+    #[test]
+    fn witness_pos_layer1() {
+        let height = NonZeroU32::new(100).unwrap();
+        let timestamp = 1_700_000_000;
+        let cache = ChainNetCache::default();
+        assert_eq!(cache.layer1(), Layer1::Bitcoin);
+        assert_eq!(cache.witness_pos(height, timestamp).unwrap().layer1(), Layer1::Bitcoin);
+
+        for (chain_net, layer1) in [
+            (ChainNet::BitcoinMainnet, Layer1::Bitcoin),
+            (ChainNet::BitcoinRegtest, Layer1::Bitcoin),
+            (ChainNet::LiquidMainnet, Layer1::Liquid),
+            (ChainNet::LiquidTestnet, Layer1::Liquid),
+        ] {
+            let cache = ChainNetCache::default();
+            cache.check(chain_net, || Ok(())).unwrap();
+            assert_eq!(cache.layer1(), layer1);
+            let pos = cache.witness_pos(height, timestamp).unwrap();
+            assert_eq!(pos, witness_pos(layer1, height, timestamp).unwrap());
+            assert_eq!(pos.layer1(), layer1);
+        }
+    }
+}
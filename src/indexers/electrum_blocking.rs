@@ -21,25 +21,134 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::iter;
 use std::num::NonZeroU32;
+use std::time::Duration;
+use std::{iter, thread};
 
 use amplify::hex::FromHex;
 pub use electrum_client;
-use electrum_client::{Client, ElectrumApi, Param};
+use electrum_client::{Client, Config, ConfigBuilder, ElectrumApi, Param, Socks5Config};
 use rgb::bitcoin::constants::ChainHash;
-use rgb::bitcoin::{consensus, Transaction as Tx, Txid};
+use rgb::bitcoin::{consensus, OutPoint as Outpoint, Transaction as Tx, Txid};
+use rgb::secp256k1::rand::{thread_rng, RngCore};
 use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
-use rgbcore::vm::{WitnessOrd, WitnessPos};
+use rgbcore::vm::WitnessOrd;
 use rgbcore::ChainNet;
 
+use super::{require_confirmations, ChainNetCache, ResolveWitnessAtTip, SpentStatus};
+
 /// Wrapper of an electrum client, necessary to implement the foreign `ResolveWitness` trait.
+///
+/// The outcome of [`ResolveWitness::check_chain_net`] is cached in `chain_net_cache`, so the
+/// server is queried only on the first check for each [`ChainNet`].
+///
+/// A TX included in a block is reported as mined only once it has at least `min_confirmations`
+/// confirmations (1 by default), otherwise it is reported as tentative.
+///
+/// The height of a mined TX is derived from its confirmations and then confirmed by fetching its
+/// merkle proof. Since the index of the server may lag behind, the proof is looked for in the
+/// `max_forward_search` blocks starting at the expected height (extended by the blocks mined
+/// while resolving), and then in `safety_margin` blocks before and after them (1 by default).
+///
+/// The wrapped [`Client`] keeps the URL and the [`Config`] it was created from, and when a
+/// request fails due to a connection error (e.g. a broken pipe after the server dropped the
+/// connection) it transparently reconnects and repeats the request, up to [`Config::retry`]
+/// times. Errors returned by the server, like an unknown TX, don't cause a reconnection.
 pub struct ElectrumClient {
     pub inner: Client,
+    pub retry_config: RetryConfig,
+    pub chain_net_cache: ChainNetCache,
+    pub min_confirmations: u32,
+    pub safety_margin: u32,
+    pub max_forward_search: u32,
+}
+
+/// Number of reconnections to the server attempted by clients created with
+/// [`ElectrumClient::connect`] without an explicit [`Config`].
+pub const DEFAULT_RECONNECT_ATTEMPTS: u8 = 3;
+
+/// Configuration of the retries performed by [`ElectrumClient`] when resolving witnesses.
+///
+/// Only transient failures ([`WitnessResolverError::ResolverIssue`]) are retried. The delay
+/// before each retry starts from `base_delay`, doubles at each attempt up to `max_delay` and is
+/// increased by a random amount up to `jitter`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RetryConfig {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: Duration,
+}
+
+impl Default for RetryConfig {
+    /// Configuration performing no retries.
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Delay to wait before the retry following the given (zero-based) failed attempt.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let jitter = match self.jitter.as_nanos() as u64 {
+            0 => 0,
+            max => thread_rng().next_u64() % max.saturating_add(1),
+        };
+        backoff + Duration::from_nanos(jitter)
+    }
 }
 
 impl ResolveWitness for ElectrumClient {
     fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        self.chain_net_cache
+            .check(chain_net, || self.fetch_chain_net(chain_net))
+    }
+
+    fn resolve_witness(&self, txid: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        self.retrying(|| {
+            // We get the height of the tip of blockchain
+            let header = self
+                .inner
+                .block_headers_subscribe()
+                .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
+            let tip_height = u32::try_from(header.height)
+                .map_err(|_| WitnessResolverError::InvalidResolverData)?;
+
+            self.resolve_witness_from(txid, tip_height, true)
+        })
+    }
+}
+
+impl ResolveWitnessAtTip for ElectrumClient {
+    fn resolve_witness_at_tip(
+        &self,
+        txid: Txid,
+        tip_height: u32,
+    ) -> Result<WitnessStatus, WitnessResolverError> {
+        self.retrying(|| self.resolve_witness_from(txid, tip_height, false))
+    }
+
+    fn chain_net(&self) -> Option<ChainNet> { self.chain_net_cache.detected() }
+
+    fn resolve_spent(&self, outpoint: Outpoint) -> Result<SpentStatus, WitnessResolverError> {
+        self.retrying(|| self.fetch_spent(outpoint))
+    }
+}
+
+impl ElectrumClient {
+    /// Checks the server is for the provided `chain_net` and supports the required
+    /// functionality, without using the cache.
+    fn fetch_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
         // check the electrum server is for the correct network
         let block_hash = self
             .inner
@@ -115,13 +224,132 @@ impl ResolveWitness for ElectrumClient {
         Ok(())
     }
 
-    fn resolve_witness(&self, txid: Txid) -> Result<WitnessStatus, WitnessResolverError> {
-        // We get the height of the tip of blockchain
-        let header = self
+    /// Return an [`ElectrumClient`] connected to the server at `url`.
+    ///
+    /// Without a `config`, the client reconnects to the server up to
+    /// [`DEFAULT_RECONNECT_ATTEMPTS`] times when the connection is lost.
+    pub fn connect(url: &str, config: Option<Config>) -> Result<Self, electrum_client::Error> {
+        let config = config.unwrap_or_else(|| {
+            ConfigBuilder::new()
+                .retry(DEFAULT_RECONNECT_ATTEMPTS)
+                .build()
+        });
+        Ok(Self {
+            inner: Client::from_config(url, config)?,
+            retry_config: default!(),
+            chain_net_cache: default!(),
+            min_confirmations: 1,
+            safety_margin: 1,
+            max_forward_search: 1,
+        })
+    }
+
+    /// Return an [`ElectrumClient`] retrying transient failures according to `retry_config`.
+    pub fn with_retry(inner: Client, retry_config: RetryConfig) -> Self {
+        Self {
+            inner,
+            retry_config,
+            chain_net_cache: default!(),
+            min_confirmations: 1,
+            safety_margin: 1,
+            max_forward_search: 1,
+        }
+    }
+
+    /// Return an [`ElectrumClient`] connecting to the server at `url` through the given SOCKS5
+    /// proxy, optionally authenticated (see [`Socks5Config::with_credentials`]).
+    ///
+    /// Further options can be set by building the [`Client`] from a custom
+    /// [`electrum_client::Config`].
+    pub fn with_proxy(url: &str, proxy: Socks5Config) -> Result<Self, electrum_client::Error> {
+        let config = ConfigBuilder::new().socks5(Some(proxy)).build();
+        Ok(Self {
+            inner: Client::from_config(url, config)?,
+            retry_config: default!(),
+            chain_net_cache: default!(),
+            min_confirmations: 1,
+            safety_margin: 1,
+            max_forward_search: 1,
+        })
+    }
+
+    /// Set the number of confirmations required to report a TX as mined.
+    pub fn with_min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+
+    /// Set the number of blocks before and after the searched range in which the merkle proof of
+    /// a mined TX is looked for.
+    pub fn with_safety_margin(mut self, safety_margin: u32) -> Self {
+        self.safety_margin = safety_margin;
+        self
+    }
+
+    /// Set the number of blocks, starting at the height expected from the confirmations of a
+    /// mined TX, in which its merkle proof is looked for (at least 1).
+    pub fn with_max_forward_search(mut self, max_forward_search: u32) -> Self {
+        self.max_forward_search = max_forward_search.max(1);
+        self
+    }
+
+    /// Runs `f` until it succeeds, fails with a non-transient error or the maximum number of
+    /// attempts is reached.
+    fn retrying<T>(
+        &self,
+        mut f: impl FnMut() -> Result<T, WitnessResolverError>,
+    ) -> Result<T, WitnessResolverError> {
+        let mut attempt = 0;
+        loop {
+            match f() {
+                Err(WitnessResolverError::ResolverIssue(..))
+                    if attempt + 1 < self.retry_config.max_attempts =>
+                {
+                    thread::sleep(self.retry_config.delay(attempt));
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Looks for a transaction spending the output at `outpoint` among the history of the
+    /// script of the output, since the electrum protocol can't query outputs directly.
+    fn fetch_spent(&self, outpoint: Outpoint) -> Result<SpentStatus, WitnessResolverError> {
+        let txid = outpoint.txid;
+        let tx = match self.inner.transaction_get(&txid) {
+            Err(e)
+                if !is_connection_error(&e)
+                    && e.to_string()
+                        .contains("No such mempool or blockchain transaction") =>
+            {
+                return Ok(SpentStatus::Unknown);
+            }
+            Err(e) => return Err(WitnessResolverError::ResolverIssue(Some(txid), e.to_string())),
+            Ok(tx) => tx,
+        };
+        let Some(output) = tx.output.get(outpoint.vout as usize) else {
+            return Ok(SpentStatus::Unknown);
+        };
+        let history = self
             .inner
-            .block_headers_subscribe()
+            .script_get_history(&output.script_pubkey)
             .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
+        find_spender(outpoint, history.into_iter().map(|entry| entry.tx_hash), |candidate| {
+            self.inner
+                .transaction_get(&candidate)
+                .map_err(|e| WitnessResolverError::ResolverIssue(Some(candidate), e.to_string()))
+        })
+    }
 
+    /// Resolves the witness using the provided tip height. When `subscribed` is true the header
+    /// notifications received after the tip subscription are used to account for new blocks.
+    fn resolve_witness_from(
+        &self,
+        txid: Txid,
+        tip_height: u32,
+        subscribed: bool,
+    ) -> Result<WitnessStatus, WitnessResolverError> {
         // Now we get and parse transaction information to get the number of
         // confirmations
         let tx_details = match self.inner.raw_call("blockchain.transaction.get", vec![
@@ -129,16 +357,20 @@ impl ResolveWitness for ElectrumClient {
             Param::Bool(true),
         ]) {
             Err(e)
-                if e.to_string()
-                    .contains("No such mempool or blockchain transaction") =>
+                if !is_connection_error(&e)
+                    && e.to_string()
+                        .contains("No such mempool or blockchain transaction") =>
             {
                 return Ok(WitnessStatus::Unresolved);
             }
             Err(e) => return Err(WitnessResolverError::ResolverIssue(Some(txid), e.to_string())),
             Ok(v) => v,
         };
-        let forward =
-            iter::from_fn(|| self.inner.block_headers_pop().ok().flatten()).count() as isize;
+        let forward = if subscribed {
+            iter::from_fn(|| self.inner.block_headers_pop().ok().flatten()).count() as isize
+        } else {
+            0
+        };
 
         let Some(tx_hex) = tx_details
             .get("hex")
@@ -165,25 +397,171 @@ impl ResolveWitness for ElectrumClient {
             .and_then(|v| v.as_i64())
             .ok_or(WitnessResolverError::InvalidResolverData)?;
 
-        let tip_height =
-            u32::try_from(header.height).map_err(|_| WitnessResolverError::InvalidResolverData)?;
-        let height: isize = (tip_height - confirmations) as isize;
-        const SAFETY_MARGIN: isize = 1;
-        // first check from expected min to max height
-        let get_merkle_res = (1..=forward + 1)
-            // we need this under assumption that electrum was lying due to "DB desynchronization"
-            // since this have a very low probability we do that after everything else
-            .chain((1..=SAFETY_MARGIN).flat_map(|i| [i + forward + 1, 1 - i]))
-            .find_map(|offset| self.inner.transaction_get_merkle(&txid, (height + offset) as usize).ok())
-            .ok_or_else(|| WitnessResolverError::ResolverIssue(Some(txid), s!("transaction can't be located in the blockchain")))?;
+        let height = tip_height.saturating_sub(confirmations) as isize + 1;
+        let window = forward + self.max_forward_search.max(1) as isize;
+        let get_merkle_res = merkle_search_heights(height, window, self.safety_margin as isize)
+            .find_map(|height| self.inner.transaction_get_merkle(&txid, height).ok())
+            .ok_or_else(|| {
+                WitnessResolverError::ResolverIssue(
+                    Some(txid),
+                    s!("transaction can't be located in the blockchain"),
+                )
+            })?;
 
         let tx_height = u32::try_from(get_merkle_res.block_height)
             .map_err(|_| WitnessResolverError::InvalidResolverData)?;
 
         let height = NonZeroU32::new(tx_height).ok_or(WitnessResolverError::InvalidResolverData)?;
-        let pos = WitnessPos::bitcoin(height, block_time)
+        let pos = self
+            .chain_net_cache
+            .witness_pos(height, block_time)
             .ok_or(WitnessResolverError::InvalidResolverData)?;
 
-        Ok(WitnessStatus::Resolved(tx, WitnessOrd::Mined(pos)))
+        let ord = require_confirmations(WitnessOrd::Mined(pos), tip_height, self.min_confirmations);
+        Ok(WitnessStatus::Resolved(tx, ord))
+    }
+}
+
+/// Returns the heights in which the merkle proof of a TX is looked for: first the `window`
+/// blocks starting at the `expected` height, then `margin` blocks before and after them.
+fn merkle_search_heights(
+    expected: isize,
+    window: isize,
+    margin: isize,
+) -> impl Iterator<Item = usize> {
+    // first check from expected min to max height
+    (0..window)
+        // we need this under assumption that electrum was lying due to "DB desynchronization"
+        // since this have a very low probability we do that after everything else
+        .chain((1..=margin).flat_map(move |i| [window - 1 + i, -i]))
+        .filter_map(move |offset| usize::try_from(expected + offset).ok())
+}
+
+/// Looks for a transaction spending the output at `outpoint` among the `history` of the script of
+/// the output, retrieving the transactions with `get_tx`.
+fn find_spender(
+    outpoint: Outpoint,
+    history: impl IntoIterator<Item = Txid>,
+    mut get_tx: impl FnMut(Txid) -> Result<Tx, WitnessResolverError>,
+) -> Result<SpentStatus, WitnessResolverError> {
+    for candidate in history.into_iter().filter(|id| *id != outpoint.txid) {
+        if get_tx(candidate)?
+            .input
+            .iter()
+            .any(|input| input.previous_output == outpoint)
+        {
+            return Ok(SpentStatus::Spent { by: candidate });
+        }
+    }
+    Ok(SpentStatus::Unspent)
+}
+
+/// Detects errors caused by the connection to the server, as opposed to the errors returned by
+/// the server itself.
+fn is_connection_error(err: &electrum_client::Error) -> bool {
+    use electrum_client::Error;
+    match err {
+        Error::IOError(_) | Error::SharedIOError(_) | Error::CouldntLockReader | Error::Mpsc => {
+            true
+        }
+        Error::AllAttemptsErrored(errors) => errors.iter().any(is_connection_error),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::io;
+
+    use super::*;
+    use crate::indexers::mock::{tx, txid};
+
+    #[test]
+    fn connection_errors() {
+        let broken_pipe = || io::Error::from(io::ErrorKind::BrokenPipe);
+        assert!(is_connection_error(&electrum_client::Error::IOError(broken_pipe())));
+        assert!(is_connection_error(&electrum_client::Error::AllAttemptsErrored(vec![
+            electrum_client::Error::IOError(broken_pipe()),
+            electrum_client::Error::IOError(broken_pipe()),
+        ])));
+        let not_found = electrum_client::Error::Message(s!("No such mempool or blockchain \
+                                                            transaction. Use gettransaction for \
+                                                            wallet transactions."));
+        assert!(!is_connection_error(&not_found));
+        assert!(!is_connection_error(&electrum_client::Error::AllAttemptsErrored(vec![not_found])));
+    }
+
+    #[test]
+    fn spender_in_history() {
+        let funding = tx(&[Outpoint::new(txid(1), 0)], &[1000, 2000]);
+        let outpoint = Outpoint::new(funding.compute_txid(), 1);
+        // spends the other output of the same script
+        let sibling = tx(&[Outpoint::new(funding.compute_txid(), 0)], &[900]);
+        let spender = tx(&[Outpoint::new(txid(2), 0), outpoint], &[2900]);
+        let txes =
+            HashMap::from([&funding, &sibling, &spender].map(|tx| (tx.compute_txid(), tx.clone())));
+        let get_tx = |txid: Txid| {
+            txes.get(&txid)
+                .cloned()
+                .ok_or(WitnessResolverError::ResolverIssue(Some(txid), s!("unknown")))
+        };
+
+        // the funding TX itself is skipped
+        let history = [funding.compute_txid(), sibling.compute_txid()];
+        assert_eq!(find_spender(outpoint, history, get_tx), Ok(SpentStatus::Unspent));
+        let history = [funding.compute_txid(), sibling.compute_txid(), spender.compute_txid()];
+        assert_eq!(
+            find_spender(outpoint, history, get_tx),
+            Ok(SpentStatus::Spent {
+                by: spender.compute_txid()
+            })
+        );
+        // failures to retrieve a candidate are reported
+        assert!(find_spender(outpoint, [txid(3)], get_tx).is_err());
+    }
+
+    #[test]
+    fn merkle_search_window() {
+        // the server index lags behind, so the proof is found 3 blocks after the expected height
+        let expected = 800_000;
+        let get_merkle = |height: usize| (height == 800_003).then_some(height);
+        let find = |window, margin| {
+            merkle_search_heights(expected as isize, window, margin).find_map(get_merkle)
+        };
+
+        assert_eq!(merkle_search_heights(expected as isize, 1, 1).collect::<Vec<_>>(), vec![
+            800_000, 800_001, 799_999
+        ]);
+        // default window and margin
+        assert_eq!(find(1, 1), None);
+        // wider margin
+        assert_eq!(find(1, 3), Some(800_003));
+        // wider forward search
+        assert_eq!(find(4, 0), Some(800_003));
+        // heights below zero are skipped
+        assert_eq!(merkle_search_heights(0, 1, 2).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn retry_delay_backoff() {
+        let config = RetryConfig {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            jitter: Duration::ZERO,
+        };
+        assert_eq!(config.delay(0), Duration::from_millis(100));
+        assert_eq!(config.delay(1), Duration::from_millis(200));
+        assert_eq!(config.delay(2), Duration::from_millis(400));
+        assert_eq!(config.delay(3), Duration::from_millis(500));
+        assert_eq!(config.delay(40), Duration::from_millis(500));
+
+        let config = RetryConfig {
+            jitter: Duration::from_millis(50),
+            ..config
+        };
+        let delay = config.delay(0);
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(150));
     }
 }
@@ -0,0 +1,212 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Logic shared by the blocking and async esplora resolvers.
+
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use esplora_client::{Builder, OutputStatus, TxStatus};
+use rgbcore::validation::WitnessResolverError;
+use rgbcore::vm::WitnessOrd;
+use rgbcore::Layer1;
+
+use super::chain_net::witness_pos;
+use super::SpentStatus;
+
+/// Converts the status of a transaction reported by esplora into its [`WitnessOrd`], with the
+/// position of mined transactions on the `layer1` blockchain.
+pub(super) fn witness_ord(
+    status: &TxStatus,
+    layer1: Layer1,
+) -> Result<WitnessOrd, WitnessResolverError> {
+    Ok(
+        match status
+            .block_height
+            .and_then(|h| status.block_time.map(|t| (h, t)))
+        {
+            Some((h, t)) => {
+                let height = NonZeroU32::new(h).ok_or(WitnessResolverError::InvalidResolverData)?;
+                WitnessOrd::Mined(
+                    witness_pos(layer1, height, t as i64)
+                        .ok_or(WitnessResolverError::InvalidResolverData)?,
+                )
+            }
+            None => WitnessOrd::Tentative,
+        },
+    )
+}
+
+/// Converts the status of an output reported by esplora into its [`SpentStatus`], `None` standing
+/// for an output unknown to the server.
+pub(super) fn spent_status(
+    status: Option<OutputStatus>,
+) -> Result<SpentStatus, WitnessResolverError> {
+    let Some(status) = status else {
+        return Ok(SpentStatus::Unknown);
+    };
+    Ok(match (status.spent, status.txid) {
+        (false, _) => SpentStatus::Unspent,
+        (true, Some(by)) => SpentStatus::Spent { by },
+        (true, None) => return Err(WitnessResolverError::InvalidResolverData),
+    })
+}
+
+/// Default timeout of the requests performed by clients built with [`EsploraBuilder`], in seconds.
+pub const ESPLORA_DEFAULT_TIMEOUT: u64 = 30;
+
+/// Builder of esplora clients, supporting the options required by authenticated endpoints.
+///
+/// All the options are carried by the wrapped [`esplora_client::Builder`] and thus are applied to
+/// every request performed by the resulting client, both blocking and async:
+/// - [`EsploraBuilder::with_header`] feeds into [`Builder::header`];
+/// - [`EsploraBuilder::with_proxy`] feeds into [`Builder::proxy`];
+/// - [`EsploraBuilder::with_timeout`] feeds into [`Builder::timeout`];
+/// - [`EsploraBuilder::with_max_retries`] feeds into [`Builder::max_retries`].
+///
+/// No option is set on the blocking or async client directly, so an already configured
+/// [`esplora_client::Builder`] can be wrapped with [`From`] and further customized.
+///
+/// Clients started with [`EsploraBuilder::new`] or [`EsploraBuilder::from_builder`] time out
+/// requests after [`ESPLORA_DEFAULT_TIMEOUT`] seconds, unless configured otherwise, so that a slow
+/// or unreachable server can't block the validation indefinitely; a request which times out is
+/// reported as [`WitnessResolverError::ResolverIssue`].
+#[derive(Clone, Debug, From)]
+pub struct EsploraBuilder(Builder);
+
+impl EsploraBuilder {
+    /// Start building a client for the esplora server at `url`, with the
+    /// [`ESPLORA_DEFAULT_TIMEOUT`].
+    pub fn new(url: &str) -> Self { Self(Builder::new(url).timeout(ESPLORA_DEFAULT_TIMEOUT)) }
+
+    /// Wrap an already configured `builder`, applying the [`ESPLORA_DEFAULT_TIMEOUT`] if it has no
+    /// timeout set.
+    pub fn from_builder(builder: Builder) -> Self {
+        match builder.timeout {
+            Some(_) => Self(builder),
+            None => Self(builder.timeout(ESPLORA_DEFAULT_TIMEOUT)),
+        }
+    }
+
+    /// Add an HTTP header (e.g. an API key or an `Authorization` token) to every request.
+    pub fn with_header(self, name: &str, value: &str) -> Self { Self(self.0.header(name, value)) }
+
+    /// Route the requests through the given proxy (e.g. `socks5://127.0.0.1:9050`).
+    pub fn with_proxy(self, proxy: &str) -> Self { Self(self.0.proxy(proxy)) }
+
+    /// Set the timeout of every request, in seconds.
+    pub fn with_timeout(self, timeout: u64) -> Self { Self(self.0.timeout(timeout)) }
+
+    /// Set the timeout of every request, rounded up to whole seconds.
+    pub fn with_timeout_duration(self, timeout: Duration) -> Self {
+        let secs = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+        self.with_timeout(secs)
+    }
+
+    /// Set how many times a request is retried by the esplora client on transient HTTP errors.
+    pub fn with_max_retries(self, max_retries: usize) -> Self {
+        Self(self.0.max_retries(max_retries))
+    }
+
+    /// Return the wrapped [`esplora_client::Builder`].
+    pub fn into_inner(self) -> Builder { self.0 }
+
+    /// Build a blocking [`EsploraClient`](super::esplora_blocking::EsploraClient).
+    #[cfg(feature = "esplora_blocking")]
+    pub fn build_blocking(self) -> super::esplora_blocking::EsploraClient {
+        super::esplora_blocking::EsploraClient::new(self.0.build_blocking())
+    }
+
+    /// Build an async [`EsploraAsyncClient`](super::esplora_async::EsploraAsyncClient).
+    #[cfg(feature = "esplora_async")]
+    pub fn build_async(
+        self,
+    ) -> Result<super::esplora_async::EsploraAsyncClient, esplora_client::Error> {
+        Ok(super::esplora_async::EsploraAsyncClient::new(self.0.build_async()?))
+    }
+}
+
+impl From<EsploraBuilder> for Builder {
+    fn from(builder: EsploraBuilder) -> Self { builder.0 }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::indexers::mock::txid;
+
+    fn output_status(spent: bool, txid: Option<rgb::bitcoin::Txid>) -> OutputStatus {
+        OutputStatus {
+            spent,
+            txid,
+            vin: None,
+            status: None,
+        }
+    }
+
+    #[test]
+    fn output_spent_status() {
+        assert_eq!(spent_status(None), Ok(SpentStatus::Unknown));
+        assert_eq!(spent_status(Some(output_status(false, None))), Ok(SpentStatus::Unspent));
+        assert_eq!(
+            spent_status(Some(output_status(false, Some(txid(1))))),
+            Ok(SpentStatus::Unspent)
+        );
+        assert_eq!(
+            spent_status(Some(output_status(true, Some(txid(1))))),
+            Ok(SpentStatus::Spent { by: txid(1) })
+        );
+        assert_eq!(
+            spent_status(Some(output_status(true, None))),
+            Err(WitnessResolverError::InvalidResolverData)
+        );
+    }
+
+    #[test]
+    fn builder_options() {
+        let builder = EsploraBuilder::new("https://esplora.example.com/api")
+            .with_header("X-Api-Key", "secret")
+            .with_proxy("socks5://127.0.0.1:9050")
+            .with_timeout(60)
+            .into_inner();
+        assert_eq!(builder.headers.get("X-Api-Key").map(String::as_str), Some("secret"));
+        assert_eq!(builder.proxy.as_deref(), Some("socks5://127.0.0.1:9050"));
+        assert_eq!(builder.timeout, Some(60));
+    }
+
+    #[test]
+    fn builder_timeout() {
+        let builder = EsploraBuilder::new("https://esplora.example.com/api").into_inner();
+        assert_eq!(builder.timeout, Some(ESPLORA_DEFAULT_TIMEOUT));
+
+        let builder = EsploraBuilder::new("https://esplora.example.com/api")
+            .with_timeout_duration(Duration::from_millis(1500))
+            .into_inner();
+        assert_eq!(builder.timeout, Some(2));
+
+        let builder = EsploraBuilder::from_builder(Builder::new("https://esplora.example.com/api"))
+            .into_inner();
+        assert_eq!(builder.timeout, Some(ESPLORA_DEFAULT_TIMEOUT));
+
+        let builder = EsploraBuilder::from_builder(
+            Builder::new("https://esplora.example.com/api").timeout(5),
+        )
+        .into_inner();
+        assert_eq!(builder.timeout, Some(5));
+    }
+}
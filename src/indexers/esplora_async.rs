@@ -0,0 +1,246 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use esplora_client;
+use esplora_client::AsyncClient;
+use rgb::bitcoin::constants::ChainHash;
+use rgb::bitcoin::{OutPoint as Outpoint, Txid};
+use rgbcore::validation::{WitnessResolverError, WitnessStatus};
+use rgbcore::vm::WitnessOrd;
+use rgbcore::ChainNet;
+
+use super::esplora::{spent_status, witness_ord};
+use super::{require_confirmations, ChainNetCache, ResolveWitnessAsync, SpentStatus};
+
+/// Wrapper of an async esplora client, implementing the [`ResolveWitnessAsync`] trait.
+///
+/// A TX which is unknown to the esplora server, including one evicted from its mempool, resolves
+/// to [`WitnessStatus::Unresolved`] rather than an error.
+///
+/// The outcome of [`ResolveWitnessAsync::check_chain_net`] is cached in `chain_net_cache`, so the
+/// server is queried only on the first check for each [`ChainNet`].
+///
+/// A TX included in a block is reported as mined only once it has at least `min_confirmations`
+/// confirmations (1 by default), otherwise it is reported as tentative.
+///
+/// ```no_run
+/// use std::str::FromStr;
+///
+/// use rgbstd::indexers::esplora_async::{esplora_client, EsploraAsyncClient};
+/// use rgbstd::indexers::ResolveWitnessAsync;
+/// use rgbstd::rgbcore::validation::WitnessStatus;
+/// use rgbstd::Txid;
+///
+/// # async fn resolve() {
+/// let builder = esplora_client::Builder::new("https://blockstream.info/api");
+/// let client =
+///     EsploraAsyncClient::new(esplora_client::AsyncClient::from_builder(builder).unwrap());
+/// let txid =
+///     Txid::from_str("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b").unwrap();
+/// match client.resolve_witness(txid).await.unwrap() {
+///     WitnessStatus::Resolved(_tx, ord) => println!("witness {txid} is {ord}"),
+///     WitnessStatus::Unresolved => println!("witness {txid} is unknown"),
+/// }
+/// # }
+/// ```
+pub struct EsploraAsyncClient {
+    pub inner: AsyncClient,
+    pub chain_net_cache: ChainNetCache,
+    pub min_confirmations: u32,
+}
+
+impl EsploraAsyncClient {
+    /// Return an [`EsploraAsyncClient`] wrapping the `inner` client, with default options.
+    pub fn new(inner: AsyncClient) -> Self {
+        Self {
+            inner,
+            chain_net_cache: default!(),
+            min_confirmations: 1,
+        }
+    }
+
+    /// Set the number of confirmations required to report a TX as mined.
+    pub fn with_min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+
+    /// Provide the [`SpentStatus`] of the transaction output at `outpoint`, see
+    /// [`ResolveWitnessAtTip::resolve_spent`](super::ResolveWitnessAtTip::resolve_spent).
+    pub async fn resolve_spent(
+        &self,
+        outpoint: Outpoint,
+    ) -> Result<SpentStatus, WitnessResolverError> {
+        let txid = outpoint.txid;
+        let status = self
+            .inner
+            .get_output_status(&txid, outpoint.vout as u64)
+            .await
+            .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
+        spent_status(status)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl ResolveWitnessAsync for EsploraAsyncClient {
+    async fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        self.chain_net_cache
+            .check_async(chain_net, || async {
+                // check the esplora server is for the correct network
+                let block_hash = self
+                    .inner
+                    .get_block_hash(0)
+                    .await
+                    .map_err(|e| WitnessResolverError::ResolverIssue(None, e.to_string()))?;
+                let chain_hash = ChainHash::from_genesis_block_hash(block_hash);
+                if chain_net.chain_hash() != chain_hash {
+                    return Err(WitnessResolverError::WrongChainNet);
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn resolve_witness(&self, txid: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        let Some(tx) = self
+            .inner
+            .get_tx(&txid)
+            .await
+            .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?
+        else {
+            return Ok(WitnessStatus::Unresolved);
+        };
+        let status = self
+            .inner
+            .get_tx_status(&txid)
+            .await
+            .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
+        let mut ord = witness_ord(&status, self.chain_net_cache.layer1())?;
+        if self.min_confirmations > 1 && matches!(ord, WitnessOrd::Mined(_)) {
+            let tip_height = self
+                .inner
+                .get_height()
+                .await
+                .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
+            ord = require_confirmations(ord, tip_height, self.min_confirmations);
+        }
+        Ok(WitnessStatus::Resolved(tx, ord))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use rgb::bitcoin::constants::genesis_block;
+    use rgb::bitcoin::Network;
+
+    use super::*;
+
+    /// Esplora server answering each request with the body returned by `respond` for the
+    /// requested path and counting the requests.
+    fn mock_server(respond: fn(&str) -> String) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let body = respond(line.split(' ').nth(1).unwrap_or_default());
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn client(runtime: &tokio::runtime::Runtime, url: &str) -> EsploraAsyncClient {
+        runtime.block_on(async {
+            EsploraAsyncClient::new(
+                AsyncClient::from_builder(esplora_client::Builder::new(url).max_retries(0))
+                    .unwrap(),
+            )
+        })
+    }
+
+    #[test]
+    fn cached_chain_net() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (url, requests) =
+            mock_server(|_| genesis_block(Network::Bitcoin).block_hash().to_string());
+        let client = client(&runtime, &url);
+
+        for _ in 0..2 {
+            runtime
+                .block_on(client.check_chain_net(ChainNet::BitcoinMainnet))
+                .unwrap();
+            assert!(matches!(
+                runtime.block_on(client.check_chain_net(ChainNet::BitcoinSignet)),
+                Err(WitnessResolverError::WrongChainNet)
+            ));
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(client.chain_net_cache.detected(), Some(ChainNet::BitcoinMainnet));
+    }
+
+    #[test]
+    fn spent_output() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (url, _) = mock_server(|path| match path.rsplit('/').next() {
+            Some("0") => s!(r#"{"spent":false}"#),
+            _ => format!(r#"{{"spent":true,"txid":"{}","vin":0}}"#, "02".repeat(32)),
+        });
+        let client = client(&runtime, &url);
+        let txid = Txid::from_str(&"01".repeat(32)).unwrap();
+
+        assert_eq!(
+            runtime.block_on(client.resolve_spent(Outpoint::new(txid, 0))),
+            Ok(SpentStatus::Unspent)
+        );
+        assert_eq!(
+            runtime.block_on(client.resolve_spent(Outpoint::new(txid, 1))),
+            Ok(SpentStatus::Spent {
+                by: Txid::from_str(&"02".repeat(32)).unwrap()
+            })
+        );
+    }
+}
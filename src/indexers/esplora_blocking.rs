@@ -19,33 +19,80 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::num::NonZeroU32;
-
 pub use esplora_client;
 use esplora_client::BlockingClient;
 use rgb::bitcoin::constants::ChainHash;
-use rgb::bitcoin::Txid;
+use rgb::bitcoin::{OutPoint as Outpoint, Txid};
 use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
-use rgbcore::vm::{WitnessOrd, WitnessPos};
+use rgbcore::vm::WitnessOrd;
 use rgbcore::ChainNet;
 
+use super::esplora::{spent_status, witness_ord};
+use super::{require_confirmations, ChainNetCache, ResolveReplacement, SpentStatus};
+
 /// Wrapper of an esplora client, necessary to implement the foreign `ResolveWitness` trait.
+///
+/// A TX which is unknown to the esplora server, including one evicted from its mempool, resolves
+/// to [`WitnessStatus::Unresolved`] rather than an error. Wrap the client into a
+/// [`TentativeTracker`](super::TentativeTracker) to tell apart witnesses dropped from the mempool,
+/// or use [`ResolveReplacement::replacement_of`] to learn the TX which replaced it via RBF.
+///
+/// The outcome of [`ResolveWitness::check_chain_net`] is cached in `chain_net_cache`, so the
+/// server is queried only on the first check for each [`ChainNet`].
+///
+/// A TX included in a block is reported as mined only once it has at least `min_confirmations`
+/// confirmations (1 by default), otherwise it is reported as tentative.
 pub struct EsploraClient {
     pub inner: BlockingClient,
+    pub chain_net_cache: ChainNetCache,
+    pub min_confirmations: u32,
+}
+
+impl EsploraClient {
+    /// Return an [`EsploraClient`] wrapping the `inner` client, with default options.
+    pub fn new(inner: BlockingClient) -> Self {
+        Self {
+            inner,
+            chain_net_cache: default!(),
+            min_confirmations: 1,
+        }
+    }
+
+    /// Set the number of confirmations required to report a TX as mined.
+    pub fn with_min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+
+    /// Fetches the [`SpentStatus`] of the output at `outpoint` from the `/tx/:txid/outspend/:vout`
+    /// endpoint.
+    pub(super) fn fetch_spent(
+        &self,
+        outpoint: Outpoint,
+    ) -> Result<SpentStatus, WitnessResolverError> {
+        let txid = outpoint.txid;
+        let status = self
+            .inner
+            .get_output_status(&txid, outpoint.vout as u64)
+            .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
+        spent_status(status)
+    }
 }
 
 impl ResolveWitness for EsploraClient {
     fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
-        // check the esplora server is for the correct network
-        let block_hash = self
-            .inner
-            .get_block_hash(0)
-            .map_err(|e| WitnessResolverError::ResolverIssue(None, e.to_string()))?;
-        let chain_hash = ChainHash::from_genesis_block_hash(block_hash);
-        if chain_net.chain_hash() != chain_hash {
-            return Err(WitnessResolverError::WrongChainNet);
-        }
-        Ok(())
+        self.chain_net_cache.check(chain_net, || {
+            // check the esplora server is for the correct network
+            let block_hash = self
+                .inner
+                .get_block_hash(0)
+                .map_err(|e| WitnessResolverError::ResolverIssue(None, e.to_string()))?;
+            let chain_hash = ChainHash::from_genesis_block_hash(block_hash);
+            if chain_net.chain_hash() != chain_hash {
+                return Err(WitnessResolverError::WrongChainNet);
+            }
+            Ok(())
+        })
     }
 
     fn resolve_witness(&self, txid: Txid) -> Result<WitnessStatus, WitnessResolverError> {
@@ -60,19 +107,63 @@ impl ResolveWitness for EsploraClient {
             .inner
             .get_tx_status(&txid)
             .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
-        let ord = match status
-            .block_height
-            .and_then(|h| status.block_time.map(|t| (h, t)))
-        {
-            Some((h, t)) => {
-                let height = NonZeroU32::new(h).ok_or(WitnessResolverError::InvalidResolverData)?;
-                WitnessOrd::Mined(
-                    WitnessPos::bitcoin(height, t as i64)
-                        .ok_or(WitnessResolverError::InvalidResolverData)?,
-                )
-            }
-            None => WitnessOrd::Tentative,
-        };
+        let mut ord = witness_ord(&status, self.chain_net_cache.layer1())?;
+        if self.min_confirmations > 1 && matches!(ord, WitnessOrd::Mined(_)) {
+            let tip_height = self
+                .inner
+                .get_height()
+                .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?;
+            ord = require_confirmations(ord, tip_height, self.min_confirmations);
+        }
         Ok(WitnessStatus::Resolved(tx, ord))
     }
 }
+
+impl ResolveReplacement for EsploraClient {
+    fn output_spender(
+        &self,
+        outpoint: Outpoint,
+    ) -> Result<Option<(Txid, WitnessOrd)>, WitnessResolverError> {
+        let txid = outpoint.txid;
+        let Some(status) = self
+            .inner
+            .get_output_status(&txid, outpoint.vout as u64)
+            .map_err(|e| WitnessResolverError::ResolverIssue(Some(txid), e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        match (status.spent, status.txid, status.status) {
+            (true, Some(spender), Some(tx_status)) => {
+                Ok(Some((spender, witness_ord(&tx_status, self.chain_net_cache.layer1())?)))
+            }
+            (true, Some(spender), None) => Ok(Some((spender, WitnessOrd::Tentative))),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpListener;
+    use std::str::FromStr;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::indexers::esplora::EsploraBuilder;
+
+    #[test]
+    fn unresponsive_server_timeout() {
+        // the connection is queued by the OS but the server never accepts nor answers it
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = EsploraBuilder::new(&format!("http://{}", listener.local_addr().unwrap()))
+            .with_timeout(2)
+            .with_max_retries(0)
+            .build_blocking();
+        let start = Instant::now();
+        let err = client
+            .resolve_witness(Txid::from_str(&"01".repeat(32)).unwrap())
+            .unwrap_err();
+        assert!(matches!(err, WitnessResolverError::ResolverIssue(Some(_), _)));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
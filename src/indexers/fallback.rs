@@ -0,0 +1,119 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rgb::bitcoin::Txid;
+use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgbcore::ChainNet;
+
+/// Resolver trying a list of [`ResolveWitness`] implementations in order, falling back to the
+/// next one when a resolver fails or doesn't find the witness.
+///
+/// The first resolved witness status is returned. A witness is reported as unresolved if no
+/// resolver found it and at least one of them succeeded, while an error is returned only if all
+/// the resolvers fail.
+pub struct FallbackResolver {
+    resolvers: Vec<Box<dyn ResolveWitness + Send>>,
+}
+
+impl FallbackResolver {
+    /// Return a [`FallbackResolver`] trying the provided `resolvers` in order.
+    pub fn new(resolvers: Vec<Box<dyn ResolveWitness + Send>>) -> Self { Self { resolvers } }
+
+    fn no_resolvers() -> WitnessResolverError {
+        WitnessResolverError::ResolverIssue(None, s!("no resolvers were provided"))
+    }
+}
+
+impl ResolveWitness for FallbackResolver {
+    fn resolve_witness(&self, witness_id: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        let mut res = Err(Self::no_resolvers());
+        for resolver in &self.resolvers {
+            match resolver.resolve_witness(witness_id) {
+                Ok(status @ WitnessStatus::Resolved(..)) => return Ok(status),
+                Ok(WitnessStatus::Unresolved) => res = Ok(WitnessStatus::Unresolved),
+                Err(err) if res.is_err() => res = Err(err),
+                Err(_) => {}
+            }
+        }
+        res
+    }
+
+    fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        let mut res = Err(Self::no_resolvers());
+        for resolver in &self.resolvers {
+            match resolver.check_chain_net(chain_net) {
+                Err(WitnessResolverError::WrongChainNet) => {
+                    return Err(WitnessResolverError::WrongChainNet);
+                }
+                Ok(()) => res = Ok(()),
+                Err(err) if res.is_err() => res = Err(err),
+                Err(_) => {}
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rgbcore::vm::WitnessOrd;
+
+    use super::*;
+    use crate::indexers::mock::{txid, MockResolver};
+
+    struct FailingResolver;
+
+    impl ResolveWitness for FailingResolver {
+        fn resolve_witness(&self, txid: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+            Err(WitnessResolverError::ResolverIssue(Some(txid), s!("connection refused")))
+        }
+
+        fn check_chain_net(&self, _: ChainNet) -> Result<(), WitnessResolverError> {
+            Err(WitnessResolverError::ResolverIssue(None, s!("connection refused")))
+        }
+    }
+
+    #[test]
+    fn falls_back_to_working_resolver() {
+        let txid = txid(1);
+        let mut working = MockResolver::resolved(WitnessOrd::Tentative);
+        working.chain_net = Some(ChainNet::BitcoinRegtest);
+        let resolver = FallbackResolver::new(vec![Box::new(FailingResolver), Box::new(working)]);
+        assert!(matches!(
+            resolver.resolve_witness(txid),
+            Ok(WitnessStatus::Resolved(_, WitnessOrd::Tentative))
+        ));
+        assert_eq!(resolver.check_chain_net(ChainNet::BitcoinRegtest), Ok(()));
+        assert_eq!(
+            resolver.check_chain_net(ChainNet::BitcoinMainnet),
+            Err(WitnessResolverError::WrongChainNet)
+        );
+    }
+
+    #[test]
+    fn fails_when_all_resolvers_fail() {
+        let txid = txid(1);
+        let resolver = FallbackResolver::new(vec![Box::new(FailingResolver)]);
+        assert!(matches!(
+            resolver.resolve_witness(txid),
+            Err(WitnessResolverError::ResolverIssue(Some(id), _)) if id == txid
+        ));
+        assert!(resolver.check_chain_net(ChainNet::BitcoinRegtest).is_err());
+        assert!(FallbackResolver::new(vec![]).resolve_witness(txid).is_err());
+    }
+}
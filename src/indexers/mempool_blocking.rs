@@ -19,13 +19,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use esplora_client::BlockingClient;
 use rgb::bitcoin::Txid;
 use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
 use rgbcore::ChainNet;
 
 use crate::indexers::esplora_blocking::esplora_client::Builder;
 use crate::indexers::esplora_blocking::EsploraClient;
+use crate::indexers::EsploraBuilder;
 
 /// Wrapper of an esplora client, necessary to implement the foreign `ResolveWitness` trait.
 /// It assumes that mempool.space exposes the same APIs as esplora.
@@ -33,7 +33,7 @@ use crate::indexers::esplora_blocking::EsploraClient;
 // instance. If the mempool service changes in the future and is not compatible with
 // esplora::BlockingClient, only the internal implementation needs to be modified.
 pub struct MemPoolClient {
-    inner: EsploraClient,
+    pub(super) inner: EsploraClient,
 }
 
 impl MemPoolClient {
@@ -41,16 +41,16 @@ impl MemPoolClient {
     ///
     /// # Arguments
     ///
-    /// * `builder` - The builder for the mempool client.
+    /// * `builder` - The builder for the mempool client. The
+    ///   [`ESPLORA_DEFAULT_TIMEOUT`](crate::indexers::ESPLORA_DEFAULT_TIMEOUT) is applied if it has
+    ///   no timeout set.
     ///
     /// # Returns
     ///
     /// Returns the `MemPoolClient` instance.
     #[allow(clippy::result_large_err)]
     pub fn new(builder: Builder) -> Self {
-        let inner = EsploraClient {
-            inner: BlockingClient::from_builder(builder),
-        };
+        let inner = EsploraBuilder::from_builder(builder).build_blocking();
         MemPoolClient { inner }
     }
 }
@@ -0,0 +1,140 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resolver and transaction fixtures shared by the tests.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use rgb::bitcoin::absolute::LockTime;
+use rgb::bitcoin::transaction::Version;
+use rgb::bitcoin::{
+    Amount, OutPoint as Outpoint, ScriptBuf, Sequence, Transaction as Tx, TxIn, TxOut, Txid,
+    Witness,
+};
+use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgbcore::vm::WitnessOrd;
+use rgbcore::ChainNet;
+
+use super::{ResolveReplacement, ResolveWitnessAtTip, SpentStatus};
+
+/// Transaction spending the `prevouts` (signaling RBF) into outputs of the given `values`.
+pub(crate) fn tx(prevouts: &[Outpoint], values: &[u64]) -> Tx {
+    Tx {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: prevouts
+            .iter()
+            .map(|prevout| TxIn {
+                previous_output: *prevout,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output: values
+            .iter()
+            .map(|value| TxOut {
+                value: Amount::from_sat(*value),
+                script_pubkey: ScriptBuf::new(),
+            })
+            .collect(),
+    }
+}
+
+/// Transaction without inputs and outputs.
+pub(crate) fn dummy_tx() -> Tx { tx(&[], &[]) }
+
+/// Txid made of the `no` byte repeated.
+pub(crate) fn txid(no: u8) -> Txid { Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap() }
+
+/// Resolver returning the same configurable status for any witness and counting the calls.
+pub(crate) struct MockResolver {
+    status: RefCell<WitnessStatus>,
+    calls: Cell<usize>,
+    /// The only chain accepted by [`ResolveWitness::check_chain_net`], or any if `None`.
+    pub(crate) chain_net: Option<ChainNet>,
+    /// Outputs known to [`ResolveWitnessAtTip::resolve_spent`], with their spending TX if any.
+    pub(crate) outputs: HashMap<Outpoint, Option<Txid>>,
+    /// Spenders returned by [`ResolveReplacement::output_spender`].
+    pub(crate) spenders: HashMap<Outpoint, (Txid, WitnessOrd)>,
+}
+
+impl MockResolver {
+    pub(crate) fn new(status: WitnessStatus) -> Self {
+        Self {
+            status: RefCell::new(status),
+            calls: Cell::new(0),
+            chain_net: None,
+            outputs: empty!(),
+            spenders: empty!(),
+        }
+    }
+
+    pub(crate) fn unresolved() -> Self { Self::new(WitnessStatus::Unresolved) }
+
+    /// Resolver returning the [`dummy_tx`] with the given ordinal.
+    pub(crate) fn resolved(ord: WitnessOrd) -> Self {
+        Self::new(WitnessStatus::Resolved(dummy_tx(), ord))
+    }
+
+    /// Makes the witnesses resolve to the [`dummy_tx`] with the given ordinal, or unresolved.
+    pub(crate) fn set(&self, ord: Option<WitnessOrd>) {
+        *self.status.borrow_mut() = match ord {
+            Some(ord) => WitnessStatus::Resolved(dummy_tx(), ord),
+            None => WitnessStatus::Unresolved,
+        };
+    }
+
+    /// Number of the witnesses resolved so far.
+    pub(crate) fn calls(&self) -> usize { self.calls.get() }
+}
+
+impl ResolveWitness for MockResolver {
+    fn resolve_witness(&self, _: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        self.calls.set(self.calls.get() + 1);
+        Ok(self.status.borrow().clone())
+    }
+
+    fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        match self.chain_net {
+            Some(expected) if expected != chain_net => Err(WitnessResolverError::WrongChainNet),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ResolveWitnessAtTip for MockResolver {
+    fn resolve_spent(&self, outpoint: Outpoint) -> Result<SpentStatus, WitnessResolverError> {
+        Ok(match self.outputs.get(&outpoint) {
+            None => SpentStatus::Unknown,
+            Some(None) => SpentStatus::Unspent,
+            Some(Some(by)) => SpentStatus::Spent { by: *by },
+        })
+    }
+}
+
+impl ResolveReplacement for MockResolver {
+    fn output_spender(
+        &self,
+        outpoint: Outpoint,
+    ) -> Result<Option<(Txid, WitnessOrd)>, WitnessResolverError> {
+        Ok(self.spenders.get(&outpoint).copied())
+    }
+}
@@ -20,12 +20,155 @@
 // limitations under the License.
 
 mod any;
+mod caching;
+mod chain_net;
+mod fallback;
+mod offline;
+mod parallel;
+mod rbf;
+mod status;
+mod tentative;
+#[cfg(any(feature = "esplora_blocking", feature = "esplora_async"))]
+mod esplora;
 #[cfg(feature = "esplora_blocking")]
 pub mod esplora_blocking;
+#[cfg(feature = "esplora_async")]
+pub mod esplora_async;
 #[cfg(feature = "electrum_blocking")]
 pub mod electrum_blocking;
+#[cfg(feature = "bitcoincore")]
+pub mod bitcoincore;
 
 #[cfg(feature = "mempool_blocking")]
 pub mod mempool_blocking;
+#[cfg(test)]
+pub(crate) mod mock;
 
 pub use any::AnyResolver;
+pub use caching::CachingResolver;
+pub use chain_net::{witness_pos, ChainNetCache};
+#[cfg(any(feature = "esplora_blocking", feature = "esplora_async"))]
+pub use esplora::{EsploraBuilder, ESPLORA_DEFAULT_TIMEOUT};
+pub use fallback::FallbackResolver;
+pub use offline::OfflineResolver;
+pub use parallel::ParallelResolver;
+pub use rbf::ResolveReplacement;
+use rgb::bitcoin::OutPoint as Outpoint;
+use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgbcore::{ChainNet, Txid};
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+pub use status::{require_confirmations, WitnessStatusExt, WitnessStatusRecord};
+pub use tentative::TentativeTracker;
+
+/// Extension of the foreign [`ResolveWitness`] trait for resolvers able to take advantage of an
+/// already known blockchain tip.
+pub trait ResolveWitnessAtTip: ResolveWitness {
+    /// Provide the [`WitnessStatus`] for a TX with the given `witness_id`, using the caller
+    /// provided `tip_height` instead of fetching the blockchain tip.
+    ///
+    /// Default implementation ignores the tip and delegates to
+    /// [`ResolveWitness::resolve_witness`].
+    fn resolve_witness_at_tip(
+        &self,
+        witness_id: Txid,
+        _tip_height: u32,
+    ) -> Result<WitnessStatus, WitnessResolverError> {
+        self.resolve_witness(witness_id)
+    }
+
+    /// Return the [`ChainNet`] the resolver is connected to, as detected by the first successful
+    /// [`ResolveWitness::check_chain_net`], or `None` if it is not known.
+    ///
+    /// Default implementation returns `None`.
+    fn chain_net(&self) -> Option<ChainNet> { None }
+
+    /// Provide the [`SpentStatus`] of the transaction output at `outpoint`, allowing to detect
+    /// seals closed by a transaction unknown to RGB.
+    ///
+    /// Default implementation returns [`SpentStatus::Unknown`].
+    fn resolve_spent(&self, _outpoint: Outpoint) -> Result<SpentStatus, WitnessResolverError> {
+        Ok(SpentStatus::Unknown)
+    }
+}
+
+/// Spending status of a transaction output, see [`ResolveWitnessAtTip::resolve_spent`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum SpentStatus {
+    /// The output exists and is not spent by any known transaction, including the ones in the
+    /// mempool.
+    Unspent,
+    /// The output is spent by the transaction `by`, which may be not mined yet.
+    Spent { by: Txid },
+    /// The resolver is unable to tell the status of the output, e.g. because it doesn't support
+    /// the query or the transaction is unknown to it.
+    Unknown,
+}
+
+/// Asynchronous version of the [`ResolveWitness`] trait, to be implemented by resolvers built on
+/// non-blocking clients.
+#[cfg(feature = "async")]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+pub trait ResolveWitnessAsync {
+    /// Provide the [`WitnessStatus`] for a TX with the given `witness_id`.
+    async fn resolve_witness(
+        &self,
+        witness_id: Txid,
+    ) -> Result<WitnessStatus, WitnessResolverError>;
+
+    /// Check that the resolver works with the expected [`ChainNet`].
+    async fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError>;
+}
+
+#[cfg(feature = "esplora_blocking")]
+impl ResolveWitnessAtTip for esplora_blocking::EsploraClient {
+    fn chain_net(&self) -> Option<ChainNet> { self.chain_net_cache.detected() }
+
+    fn resolve_spent(&self, outpoint: Outpoint) -> Result<SpentStatus, WitnessResolverError> {
+        self.fetch_spent(outpoint)
+    }
+}
+
+#[cfg(feature = "mempool_blocking")]
+impl ResolveWitnessAtTip for mempool_blocking::MemPoolClient {
+    fn chain_net(&self) -> Option<ChainNet> { self.inner.chain_net_cache.detected() }
+
+    fn resolve_spent(&self, outpoint: Outpoint) -> Result<SpentStatus, WitnessResolverError> {
+        self.inner.fetch_spent(outpoint)
+    }
+}
+
+#[cfg(feature = "bitcoincore")]
+impl ResolveWitnessAtTip for bitcoincore::BitcoinCoreClient {
+    fn chain_net(&self) -> Option<ChainNet> { self.chain_net_cache.detected() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::mock::{txid, MockResolver};
+    use super::*;
+
+    #[test]
+    fn spent_status() {
+        let (txid, spender) = (txid(1), txid(2));
+        let mut resolver = MockResolver::unresolved();
+        resolver.outputs.insert(Outpoint::new(txid, 0), None);
+        resolver
+            .outputs
+            .insert(Outpoint::new(txid, 1), Some(spender));
+        assert_eq!(resolver.resolve_spent(Outpoint::new(txid, 0)).unwrap(), SpentStatus::Unspent);
+        assert_eq!(resolver.resolve_spent(Outpoint::new(txid, 1)).unwrap(), SpentStatus::Spent {
+            by: spender
+        });
+        assert_eq!(resolver.resolve_spent(Outpoint::new(txid, 2)).unwrap(), SpentStatus::Unknown);
+
+        let offline = OfflineResolver::new(ChainNet::BitcoinRegtest);
+        assert_eq!(offline.resolve_spent(Outpoint::new(txid, 0)).unwrap(), SpentStatus::Unknown);
+    }
+}
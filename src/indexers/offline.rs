@@ -0,0 +1,109 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use rgb::bitcoin::Txid;
+use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgbcore::ChainNet;
+
+use super::ResolveWitnessAtTip;
+
+/// Resolver which never accesses the network, answering from a set of witness statuses known in
+/// advance. Useful for tests and for validation on air-gapped machines.
+///
+/// Witnesses which were not inserted resolve to [`WitnessStatus::Unresolved`], while
+/// [`ResolveWitness::check_chain_net`] succeeds only for the configured [`ChainNet`].
+///
+/// ```
+/// use std::num::NonZeroU32;
+///
+/// use rgbstd::indexers::OfflineResolver;
+/// use rgbstd::rgbcore::bitcoin::absolute::LockTime;
+/// use rgbstd::rgbcore::bitcoin::transaction::Version;
+/// use rgbstd::rgbcore::bitcoin::Transaction;
+/// use rgbstd::rgbcore::validation::{ResolveWitness, WitnessStatus};
+/// use rgbstd::rgbcore::vm::{WitnessOrd, WitnessPos};
+/// use rgbstd::rgbcore::ChainNet;
+///
+/// let tx = Transaction {
+///     version: Version::TWO,
+///     lock_time: LockTime::ZERO,
+///     input: vec![],
+///     output: vec![],
+/// };
+/// let txid = tx.compute_txid();
+/// let pos = WitnessPos::bitcoin(NonZeroU32::new(800_000).unwrap(), 1_690_000_000).unwrap();
+///
+/// let mut resolver = OfflineResolver::new(ChainNet::BitcoinRegtest);
+/// resolver.insert(txid, WitnessStatus::Resolved(tx, WitnessOrd::Mined(pos)));
+///
+/// assert!(resolver.check_chain_net(ChainNet::BitcoinRegtest).is_ok());
+/// assert!(resolver.check_chain_net(ChainNet::BitcoinMainnet).is_err());
+/// match resolver.resolve_witness(txid).unwrap() {
+///     WitnessStatus::Resolved(_, ord) => assert_eq!(ord, WitnessOrd::Mined(pos)),
+///     WitnessStatus::Unresolved => unreachable!(),
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct OfflineResolver {
+    chain_net: ChainNet,
+    statuses: HashMap<Txid, WitnessStatus>,
+}
+
+impl OfflineResolver {
+    /// Return an [`OfflineResolver`] for the given `chain_net`, not knowing any witness.
+    pub fn new(chain_net: ChainNet) -> Self {
+        Self {
+            chain_net,
+            statuses: empty!(),
+        }
+    }
+
+    /// Seed the resolver with the status of the witness `txid`, returning the previous one.
+    pub fn insert(&mut self, txid: Txid, status: WitnessStatus) -> Option<WitnessStatus> {
+        self.statuses.insert(txid, status)
+    }
+
+    /// Forget the status of the witness `txid`, returning it if it was known.
+    pub fn remove(&mut self, txid: Txid) -> Option<WitnessStatus> { self.statuses.remove(&txid) }
+
+    /// Return the [`ChainNet`] the resolver is configured for.
+    pub fn chain_net(&self) -> ChainNet { self.chain_net }
+}
+
+impl ResolveWitness for OfflineResolver {
+    fn resolve_witness(&self, witness_id: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        Ok(self
+            .statuses
+            .get(&witness_id)
+            .cloned()
+            .unwrap_or(WitnessStatus::Unresolved))
+    }
+
+    fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        if chain_net != self.chain_net {
+            return Err(WitnessResolverError::WrongChainNet);
+        }
+        Ok(())
+    }
+}
+
+impl ResolveWitnessAtTip for OfflineResolver {
+    fn chain_net(&self) -> Option<ChainNet> { Some(self.chain_net) }
+}
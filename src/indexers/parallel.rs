@@ -0,0 +1,145 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeSet, HashMap};
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+use rgb::bitcoin::Txid;
+use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgbcore::ChainNet;
+
+/// Resolver wrapping any implementation of the [`ResolveWitness`] trait which is able to resolve
+/// batches of witnesses concurrently, using a bounded pool of worker threads.
+pub struct ParallelResolver<R: ResolveWitness + Sync> {
+    inner: R,
+    threads: NonZeroUsize,
+}
+
+impl<R: ResolveWitness + Sync> ParallelResolver<R> {
+    /// Return a [`ParallelResolver`] wrapping the `inner` resolver, which resolves batches using
+    /// up to `threads` worker threads.
+    pub fn new(inner: R, threads: NonZeroUsize) -> Self { Self { inner, threads } }
+
+    /// Return the wrapped resolver.
+    pub fn into_inner(self) -> R { self.inner }
+
+    /// Return the maximum number of worker threads used to resolve a batch.
+    pub fn threads(&self) -> NonZeroUsize { self.threads }
+
+    /// Resolve all the given witnesses, spreading the requests among the worker threads.
+    ///
+    /// The failure to resolve a witness doesn't abort the batch: each witness gets its own result.
+    /// Duplicated witness IDs are requested only once.
+    pub fn resolve_batch(
+        &self,
+        witness_ids: impl IntoIterator<Item = Txid>,
+    ) -> HashMap<Txid, Result<WitnessStatus, WitnessResolverError>> {
+        let witness_ids = witness_ids.into_iter().collect::<BTreeSet<_>>();
+        let workers = self.threads.get().min(witness_ids.len());
+        let queue = Mutex::new(witness_ids.into_iter());
+        let results = Mutex::new(HashMap::new());
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let next = queue.lock().unwrap_or_else(PoisonError::into_inner).next();
+                    let Some(witness_id) = next else {
+                        break;
+                    };
+                    let result = self.inner.resolve_witness(witness_id);
+                    results
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .insert(witness_id, result);
+                });
+            }
+        });
+        results.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<R: ResolveWitness + Sync> ResolveWitness for ParallelResolver<R> {
+    fn resolve_witness(&self, witness_id: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        self.inner.resolve_witness(witness_id)
+    }
+
+    fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        self.inner.check_chain_net(chain_net)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use rgb::bitcoin::hashes::Hash;
+    use rgbcore::vm::WitnessOrd;
+
+    use super::*;
+    use crate::indexers::mock::dummy_tx;
+
+    /// Resolver failing for the TXs whose id starts with `00`, resolving the ones starting with an
+    /// odd byte and not finding the others.
+    struct ByIdResolver;
+
+    impl ResolveWitness for ByIdResolver {
+        fn resolve_witness(&self, witness_id: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+            match witness_id.to_byte_array()[0] {
+                0 => Err(WitnessResolverError::ResolverIssue(Some(witness_id), s!("offline"))),
+                byte if byte % 2 == 1 => {
+                    Ok(WitnessStatus::Resolved(dummy_tx(), WitnessOrd::Tentative))
+                }
+                _ => Ok(WitnessStatus::Unresolved),
+            }
+        }
+
+        fn check_chain_net(&self, _: ChainNet) -> Result<(), WitnessResolverError> { Ok(()) }
+    }
+
+    fn txids() -> Vec<Txid> {
+        (0..8u8)
+            .map(|no| Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap())
+            .collect()
+    }
+
+    fn resolve(threads: usize) -> HashMap<Txid, Result<WitnessStatus, WitnessResolverError>> {
+        ParallelResolver::new(ByIdResolver, NonZeroUsize::new(threads).unwrap())
+            .resolve_batch(txids())
+    }
+
+    #[test]
+    fn batch_errors_are_isolated() {
+        let results = resolve(3);
+        assert_eq!(results.len(), 8);
+        assert_eq!(results.values().filter(|res| res.is_err()).count(), 1);
+        assert!(matches!(results[&txids()[0]], Err(WitnessResolverError::ResolverIssue(..))));
+        assert_eq!(results[&txids()[2]], Ok(WitnessStatus::Unresolved));
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let sequential = txids()
+            .into_iter()
+            .map(|txid| (txid, ByIdResolver.resolve_witness(txid)))
+            .collect::<HashMap<_, _>>();
+        for threads in [1, 3, 8, 16] {
+            assert_eq!(resolve(threads), sequential);
+        }
+    }
+}
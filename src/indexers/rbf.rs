@@ -0,0 +1,95 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rgb::bitcoin::{OutPoint as Outpoint, Transaction as Tx, Txid};
+use rgbcore::validation::WitnessResolverError;
+use rgbcore::vm::WitnessOrd;
+
+/// Resolvers able to detect a witness transaction which was replaced (e.g. via RBF) by a
+/// different transaction spending the same inputs.
+///
+/// A replaced witness becomes permanently unknown to the indexers, so
+/// [`ResolveWitness::resolve_witness`](rgbcore::validation::ResolveWitness::resolve_witness)
+/// reports it as [`WitnessStatus::Unresolved`](rgbcore::validation::WitnessStatus::Unresolved).
+/// Since the replaced transaction can't be retrieved from the indexer, the caller must provide it
+/// (e.g. from the public witness kept in the stash) to learn the id of the replacing one.
+pub trait ResolveReplacement {
+    /// Return the id of the transaction spending the output at `outpoint`, together with its
+    /// [`WitnessOrd`], or `None` if the output is unspent.
+    fn output_spender(
+        &self,
+        outpoint: Outpoint,
+    ) -> Result<Option<(Txid, WitnessOrd)>, WitnessResolverError>;
+
+    /// Return the id of a mined transaction, different from `tx`, spending any of the inputs of
+    /// `tx`, or `None` if `tx` has not been replaced by a mined transaction.
+    fn replacement_of(&self, tx: &Tx) -> Result<Option<Txid>, WitnessResolverError> {
+        let txid = tx.compute_txid();
+        for input in &tx.input {
+            match self.output_spender(input.previous_output)? {
+                Some((spender, WitnessOrd::Mined(_))) if spender != txid => {
+                    return Ok(Some(spender));
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU32;
+
+    use rgbcore::vm::WitnessPos;
+
+    use super::*;
+    use crate::indexers::mock::{tx, txid, MockResolver};
+
+    #[test]
+    fn rbf_replacement() {
+        let prev = txid(1);
+        let input1 = Outpoint::new(prev, 0);
+        let input2 = Outpoint::new(prev, 1);
+        let original = tx(&[input1, input2], &[10_000]);
+        // the replacement pays a higher fee, spending only one of the original inputs
+        let replacement = tx(&[input2], &[9_000]);
+        let pos = WitnessPos::bitcoin(NonZeroU32::new(100).unwrap(), 1_700_000_000).unwrap();
+
+        let mut resolver = MockResolver::unresolved();
+        assert_eq!(resolver.replacement_of(&original).unwrap(), None);
+
+        // the original tx spending its own inputs is not a replacement
+        resolver
+            .spenders
+            .insert(input1, (original.compute_txid(), WitnessOrd::Tentative));
+        assert_eq!(resolver.replacement_of(&original).unwrap(), None);
+
+        // a replacement which is not mined yet may still be replaced back
+        resolver
+            .spenders
+            .insert(input2, (replacement.compute_txid(), WitnessOrd::Tentative));
+        assert_eq!(resolver.replacement_of(&original).unwrap(), None);
+
+        resolver
+            .spenders
+            .insert(input2, (replacement.compute_txid(), WitnessOrd::Mined(pos)));
+        assert_eq!(resolver.replacement_of(&original).unwrap(), Some(replacement.compute_txid()));
+        assert_eq!(resolver.replacement_of(&replacement).unwrap(), None);
+    }
+}
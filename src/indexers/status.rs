@@ -0,0 +1,202 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rgb::bitcoin::Transaction as Tx;
+use rgbcore::validation::WitnessStatus;
+use rgbcore::vm::WitnessOrd;
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+
+use crate::LIB_NAME_RGB_STORAGE;
+
+/// Persistable form of a [`WitnessStatus`].
+///
+/// [`WitnessStatus`] is defined in the consensus library and doesn't support strict encoding, so
+/// resolvers which need to store their results (e.g. to survive process restarts) should convert
+/// to and from this type. The witness transaction is kept in its consensus encoding.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STORAGE, tags = custom, dumb = Self::Unresolved)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum WitnessStatusRecord {
+    /// TX has not been found.
+    #[strict_type(tag = 0x00)]
+    Unresolved,
+    /// TX has been found.
+    #[strict_type(tag = 0x01)]
+    Resolved(Tx, WitnessOrd),
+}
+
+/// Extension of the foreign [`WitnessStatus`] type with helpers checking the confirmation depth
+/// of a witness transaction, e.g. to decide whether its allocations are safe to spend.
+pub trait WitnessStatusExt {
+    /// Return the [`WitnessOrd`] of the witness transaction.
+    fn witness_ord(&self) -> WitnessOrd;
+
+    /// Return the number of confirmations of the witness transaction with respect to the
+    /// blockchain tip at `tip_height`, or `None` if the transaction is not mined.
+    ///
+    /// A transaction mined in the tip block has a single confirmation; a transaction mined above
+    /// `tip_height` (i.e. when the provided tip is outdated) is reported with zero confirmations.
+    fn confirmations(&self, tip_height: u32) -> Option<u32> {
+        match self.witness_ord() {
+            WitnessOrd::Mined(pos) => Some(
+                tip_height
+                    .checked_sub(pos.height().get())
+                    .map(|depth| depth + 1)
+                    .unwrap_or_default(),
+            ),
+            WitnessOrd::Tentative | WitnessOrd::Ignored | WitnessOrd::Archived => None,
+        }
+    }
+
+    /// Detect whether the witness transaction has at least `min_depth` confirmations with respect
+    /// to the blockchain tip at `tip_height`. Not mined transactions are never final.
+    fn is_final(&self, tip_height: u32, min_depth: u32) -> bool {
+        self.confirmations(tip_height)
+            .is_some_and(|confirmations| confirmations >= min_depth)
+    }
+}
+
+impl WitnessStatusExt for WitnessStatus {
+    fn witness_ord(&self) -> WitnessOrd {
+        match self {
+            Self::Unresolved => WitnessOrd::Archived,
+            Self::Resolved(_, ord) => *ord,
+        }
+    }
+}
+
+impl WitnessStatusExt for WitnessStatusRecord {
+    fn witness_ord(&self) -> WitnessOrd { WitnessStatusRecord::witness_ord(self) }
+}
+
+impl WitnessStatusExt for WitnessOrd {
+    fn witness_ord(&self) -> WitnessOrd { *self }
+}
+
+/// Classifies a mined witness as [`WitnessOrd::Tentative`] until it reaches `min_confirmations`
+/// with respect to the blockchain tip at `tip_height`.
+///
+/// A `min_confirmations` of 0 or 1 keeps the ordinal unchanged. Resolvers supporting a
+/// confirmation threshold apply it through this function, so that they agree on its meaning.
+pub fn require_confirmations(
+    ord: WitnessOrd,
+    tip_height: u32,
+    min_confirmations: u32,
+) -> WitnessOrd {
+    match ord {
+        WitnessOrd::Mined(_)
+            if min_confirmations > 1 && !ord.is_final(tip_height, min_confirmations) =>
+        {
+            WitnessOrd::Tentative
+        }
+        ord => ord,
+    }
+}
+
+impl WitnessStatusRecord {
+    /// Return the [`WitnessOrd`] for this [`WitnessStatusRecord`].
+    pub fn witness_ord(&self) -> WitnessOrd {
+        match self {
+            Self::Unresolved => WitnessOrd::Archived,
+            Self::Resolved(_, ord) => *ord,
+        }
+    }
+}
+
+impl From<WitnessStatus> for WitnessStatusRecord {
+    fn from(status: WitnessStatus) -> Self {
+        match status {
+            WitnessStatus::Unresolved => Self::Unresolved,
+            WitnessStatus::Resolved(tx, ord) => Self::Resolved(tx, ord),
+        }
+    }
+}
+
+impl From<WitnessStatusRecord> for WitnessStatus {
+    fn from(record: WitnessStatusRecord) -> Self {
+        match record {
+            WitnessStatusRecord::Unresolved => Self::Unresolved,
+            WitnessStatusRecord::Resolved(tx, ord) => Self::Resolved(tx, ord),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU32;
+
+    use rgb::bitcoin::absolute::LockTime;
+    use rgb::bitcoin::transaction::Version;
+    use rgbcore::vm::WitnessPos;
+
+    use super::*;
+
+    fn status(ord: WitnessOrd) -> WitnessStatus {
+        let tx = Tx {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+        WitnessStatus::Resolved(tx, ord)
+    }
+
+    #[test]
+    fn tentative_is_not_final() {
+        let tentative = status(WitnessOrd::Tentative);
+        assert_eq!(tentative.confirmations(100), None);
+        assert!(!tentative.is_final(100, 0));
+        assert_eq!(WitnessStatus::Unresolved.confirmations(100), None);
+        assert!(!WitnessStatus::Unresolved.is_final(100, 1));
+    }
+
+    #[test]
+    fn mined_confirmations() {
+        let pos = WitnessPos::bitcoin(NonZeroU32::new(100).unwrap(), 1231006505).unwrap();
+        let mined = status(WitnessOrd::Mined(pos));
+        assert_eq!(mined.confirmations(100), Some(1));
+        assert_eq!(mined.confirmations(199), Some(100));
+        assert_eq!(mined.confirmations(99), Some(0));
+        assert!(mined.is_final(199, 6));
+        assert!(mined.is_final(105, 6));
+        assert!(!mined.is_final(104, 6));
+        assert!(!mined.is_final(99, 1));
+
+        let record = WitnessStatusRecord::from(mined);
+        assert_eq!(record.confirmations(199), Some(100));
+        assert!(record.is_final(199, 100));
+    }
+
+    #[test]
+    fn min_confirmations() {
+        let pos = WitnessPos::bitcoin(NonZeroU32::new(100).unwrap(), 1231006505).unwrap();
+        let mined = WitnessOrd::Mined(pos);
+        // two confirmations
+        assert_eq!(require_confirmations(mined, 101, 6), WitnessOrd::Tentative);
+        assert_eq!(require_confirmations(mined, 105, 6), mined);
+        assert_eq!(require_confirmations(mined, 101, 1), mined);
+        assert_eq!(require_confirmations(mined, 99, 1), mined);
+        assert_eq!(require_confirmations(WitnessOrd::Archived, 200, 6), WitnessOrd::Archived);
+    }
+}
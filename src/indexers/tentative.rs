@@ -0,0 +1,163 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use rgb::bitcoin::Txid;
+use rgbcore::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgbcore::vm::WitnessOrd;
+use rgbcore::ChainNet;
+
+/// Resolver wrapping any implementation of the [`ResolveWitness`] trait which keeps track of
+/// the witnesses found in the mempool.
+///
+/// Indexers report a TX which is not (or no longer) known to them as [`WitnessStatus::Unresolved`],
+/// so a tentative witness dropped from the mempool is indistinguishable from one that was never
+/// broadcast. This wrapper records when each witness was first seen as [`WitnessOrd::Tentative`]
+/// and marks as evicted the ones later resolving to [`WitnessStatus::Unresolved`], letting the
+/// caller decide when a stale tentative witness should be archived.
+pub struct TentativeTracker<R: ResolveWitness> {
+    inner: R,
+    log: Mutex<TentativeLog>,
+}
+
+#[derive(Default)]
+struct TentativeLog {
+    first_seen: HashMap<Txid, SystemTime>,
+    evicted: BTreeSet<Txid>,
+}
+
+impl<R: ResolveWitness> TentativeTracker<R> {
+    /// Return a [`TentativeTracker`] wrapping the `inner` resolver.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            log: default!(),
+        }
+    }
+
+    /// Return the wrapped resolver, dropping the tracked information.
+    pub fn into_inner(self) -> R { self.inner }
+
+    /// Return the time a witness was first resolved as tentative, if it is still in the mempool.
+    pub fn first_seen(&self, witness_id: Txid) -> Option<SystemTime> {
+        self.log().first_seen.get(&witness_id).copied()
+    }
+
+    /// Return the witnesses which have been tentative for at least `max_age`.
+    pub fn stale_tentatives(&self, max_age: Duration) -> BTreeSet<Txid> {
+        let now = SystemTime::now();
+        self.log()
+            .first_seen
+            .iter()
+            .filter(|(_, seen)| now.duration_since(**seen).unwrap_or_default() >= max_age)
+            .map(|(witness_id, _)| *witness_id)
+            .collect()
+    }
+
+    /// Return the witnesses which were seen as tentative and are no longer known to the indexer.
+    pub fn evicted(&self) -> BTreeSet<Txid> { self.log().evicted.clone() }
+
+    /// Check whether a witness was seen as tentative and is no longer known to the indexer.
+    pub fn is_evicted(&self, witness_id: Txid) -> bool { self.log().evicted.contains(&witness_id) }
+
+    fn log(&self) -> MutexGuard<'_, TentativeLog> {
+        self.log.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<R: ResolveWitness> ResolveWitness for TentativeTracker<R> {
+    fn resolve_witness(&self, witness_id: Txid) -> Result<WitnessStatus, WitnessResolverError> {
+        let status = self.inner.resolve_witness(witness_id)?;
+        let mut log = self.log();
+        match &status {
+            WitnessStatus::Resolved(_, WitnessOrd::Tentative) => {
+                log.first_seen
+                    .entry(witness_id)
+                    .or_insert_with(SystemTime::now);
+                log.evicted.remove(&witness_id);
+            }
+            WitnessStatus::Unresolved => {
+                if log.first_seen.remove(&witness_id).is_some() {
+                    log.evicted.insert(witness_id);
+                }
+            }
+            WitnessStatus::Resolved(..) => {
+                log.first_seen.remove(&witness_id);
+                log.evicted.remove(&witness_id);
+            }
+        }
+        Ok(status)
+    }
+
+    fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), WitnessResolverError> {
+        self.inner.check_chain_net(chain_net)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU32;
+
+    use rgbcore::vm::WitnessPos;
+
+    use super::*;
+    use crate::indexers::mock::{self, MockResolver};
+
+    fn txid() -> Txid { mock::txid(1) }
+
+    #[test]
+    fn evicted_witness_is_unresolved() {
+        let tracker = TentativeTracker::new(MockResolver::unresolved());
+        assert_eq!(tracker.resolve_witness(txid()).unwrap(), WitnessStatus::Unresolved);
+        assert!(!tracker.is_evicted(txid()));
+
+        tracker.inner.set(Some(WitnessOrd::Tentative));
+        tracker.resolve_witness(txid()).unwrap();
+        let seen = tracker.first_seen(txid()).unwrap();
+        tracker.resolve_witness(txid()).unwrap();
+        assert_eq!(tracker.first_seen(txid()), Some(seen));
+        assert!(tracker.stale_tentatives(Duration::ZERO).contains(&txid()));
+        assert!(tracker
+            .stale_tentatives(Duration::from_secs(3600))
+            .is_empty());
+
+        tracker.inner.set(None);
+        assert_eq!(tracker.resolve_witness(txid()).unwrap(), WitnessStatus::Unresolved);
+        assert_eq!(tracker.first_seen(txid()), None);
+        assert!(tracker.is_evicted(txid()));
+    }
+
+    #[test]
+    fn mined_witness_is_forgotten() {
+        let tracker = TentativeTracker::new(MockResolver::unresolved());
+        tracker.inner.set(Some(WitnessOrd::Tentative));
+        tracker.resolve_witness(txid()).unwrap();
+        tracker.inner.set(None);
+        tracker.resolve_witness(txid()).unwrap();
+        assert_eq!(tracker.evicted(), bset![txid()]);
+
+        let pos = WitnessPos::bitcoin(NonZeroU32::new(100).unwrap(), 1231006505).unwrap();
+        tracker.inner.set(Some(WitnessOrd::Mined(pos)));
+        tracker.resolve_witness(txid()).unwrap();
+        assert_eq!(tracker.first_seen(txid()), None);
+        assert!(tracker.evicted().is_empty());
+    }
+}
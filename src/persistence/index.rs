@@ -19,7 +19,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::fmt::Debug;
 
@@ -28,7 +28,7 @@ use nonasync::persistence::{CloneNoPersistence, Persisting};
 use rgb::bitcoin::{OutPoint as Outpoint, Txid};
 use rgb::{
     Assign, AssignmentType, BundleId, ContractId, ExposedState, Genesis, GenesisSeal, GraphSeal,
-    KnownTransition, OpId, Operation, Opout, TransitionBundle, TypedAssigns,
+    KnownTransition, OpId, Operation, Opout, OutputSeal, TransitionBundle, TypedAssigns,
 };
 
 use crate::containers::{ConsignmentExt, ToWitnessId, WitnessBundle};
@@ -212,7 +212,8 @@ impl<P: IndexProvider> Index<P> {
             .register_bundle(bundle_id, witness_id, contract_id)?;
 
         for KnownTransition { opid, transition } in &bundle.known_transitions {
-            self.provider.register_operation(*opid, bundle_id)?;
+            self.provider
+                .register_operation(*opid, bundle_id, &transition.inputs)?;
             for input in &transition.inputs {
                 self.provider.register_spending(input.op, bundle_id)?;
             }
@@ -252,6 +253,12 @@ impl<P: IndexProvider> Index<P> {
         Ok(())
     }
 
+    pub(super) fn forget_contract(&mut self, contract_id: ContractId) -> Result<(), IndexError<P>> {
+        self.provider
+            .forget_contract(contract_id)
+            .map_err(IndexError::WriteProvider)
+    }
+
     pub(super) fn contracts_assigning(
         &self,
         outputs: BTreeSet<Outpoint>,
@@ -261,6 +268,16 @@ impl<P: IndexProvider> Index<P> {
             .map_err(IndexError::ReadProvider)
     }
 
+    #[allow(clippy::type_complexity)]
+    pub(super) fn allocations_at(
+        &self,
+        outpoints: BTreeSet<Outpoint>,
+    ) -> Result<BTreeMap<Outpoint, BTreeSet<(ContractId, Opout)>>, IndexError<P>> {
+        self.provider
+            .allocations_at(outpoints)
+            .map_err(IndexError::ReadProvider)
+    }
+
     pub(super) fn public_opouts(
         &self,
         contract_id: ContractId,
@@ -276,6 +293,13 @@ impl<P: IndexProvider> Index<P> {
         Ok(self.provider.opouts_by_outputs(contract_id, outputs)?)
     }
 
+    pub(super) fn outpoints_for_contract(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<BTreeSet<OutputSeal>, IndexError<P>> {
+        Ok(self.provider.outpoints_for_contract(contract_id)?)
+    }
+
     pub(super) fn opouts_by_terminals(
         &self,
         terminals: impl IntoIterator<Item = SecretSeal>,
@@ -296,6 +320,16 @@ impl<P: IndexProvider> Index<P> {
         Ok(self.provider.bundle_ids_children_of_op(opid)?)
     }
 
+    pub(super) fn spenders_of(&self, opout: Opout) -> Result<BTreeSet<OpId>, IndexError<P>> {
+        self.provider
+            .spenders_of(opout)
+            .map_err(IndexError::ReadProvider)
+    }
+
+    pub(super) fn descendant_bundles(&self, opid: OpId) -> Result<Vec<BundleId>, IndexError<P>> {
+        Ok(self.provider.descendant_bundles(opid)?)
+    }
+
     pub(super) fn bundle_info(
         &self,
         bundle_id: BundleId,
@@ -335,6 +369,16 @@ pub trait IndexReadProvider {
         outputs: BTreeSet<Outpoint>,
     ) -> Result<impl Iterator<Item = ContractId> + '_, Self::Error>;
 
+    /// Returns the operation outputs of all the contracts assigning state to each of the
+    /// provided outpoints.
+    ///
+    /// The outpoints to which no contract assigns state are not present in the returned map.
+    #[allow(clippy::type_complexity)]
+    fn allocations_at(
+        &self,
+        outpoints: BTreeSet<Outpoint>,
+    ) -> Result<BTreeMap<Outpoint, BTreeSet<(ContractId, Opout)>>, Self::Error>;
+
     fn public_opouts(
         &self,
         contract_id: ContractId,
@@ -346,6 +390,12 @@ pub trait IndexReadProvider {
         outputs: impl IntoIterator<Item = impl Into<Outpoint>>,
     ) -> Result<BTreeSet<Opout>, IndexReadError<Self::Error>>;
 
+    /// Returns all the outputs the contract has ever assigned state to, including the spent ones.
+    fn outpoints_for_contract(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<BTreeSet<OutputSeal>, IndexReadError<Self::Error>>;
+
     fn opouts_by_terminals(
         &self,
         terminals: impl IntoIterator<Item = SecretSeal>,
@@ -358,6 +408,24 @@ pub trait IndexReadProvider {
         opid: OpId,
     ) -> Result<SmallOrdSet<BundleId>, IndexReadError<Self::Error>>;
 
+    /// Returns ids of all the known bundles descending from the operation, i.e. the bundles
+    /// spending its outputs, the bundles spending the outputs of the former ones, and so on.
+    ///
+    /// The bundles are listed in the breadth-first order, each one only once, so the traversal
+    /// terminates even if the index contains a cycle.
+    fn descendant_bundles(&self, opid: OpId) -> Result<Vec<BundleId>, IndexReadError<Self::Error>>;
+
+    /// Returns ids of all the known operations which use the provided opout as their input.
+    ///
+    /// More than one spender indicates a conflicting (double-spending) situation.
+    fn spenders_of(&self, opout: Opout) -> Result<BTreeSet<OpId>, Self::Error>;
+
+    /// Returns the contract of the bundle and the ids of all the witnesses known for it.
+    ///
+    /// Each witness id is returned only once and the witnesses are ordered by their id; use
+    /// [`Stock::bundle_witnesses`] to get them ordered by their mining status.
+    ///
+    /// [`Stock::bundle_witnesses`]: super::Stock::bundle_witnesses
     fn bundle_info(
         &self,
         bundle_id: BundleId,
@@ -380,6 +448,7 @@ pub trait IndexWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
         &mut self,
         opid: OpId,
         bundle_id: BundleId,
+        inputs: impl IntoIterator<Item = Opout>,
     ) -> Result<bool, IndexWriteError<Self::Error>>;
 
     fn register_spending(
@@ -404,4 +473,7 @@ pub trait IndexWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
         type_id: AssignmentType,
         witness_id: Txid,
     ) -> Result<(), IndexWriteError<Self::Error>>;
+
+    /// Removes all the index entries related to the contract.
+    fn forget_contract(&mut self, contract_id: ContractId) -> Result<(), Self::Error>;
 }
@@ -20,41 +20,45 @@
 // limitations under the License.
 
 use std::borrow::Borrow;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::convert::Infallible;
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 
 use aluvm::library::{Lib, LibId};
 use amplify::confinement::{
-    self, LargeOrdMap, LargeOrdSet, MediumOrdSet, SmallOrdMap, SmallOrdSet, TinyOrdMap,
+    self, Confined, LargeOrdMap, LargeOrdSet, MediumOrdSet, SmallOrdMap, SmallOrdSet, TinyOrdMap,
 };
 use amplify::num::u24;
+use amplify::Wrapper;
 use nonasync::persistence::{CloneNoPersistence, Persistence, PersistenceError, Persisting};
 use rgb::bitcoin::{OutPoint as Outpoint, Txid};
-use rgb::commit_verify::{CommitId, Conceal};
+use rgb::commit_verify::{mpc, CommitId, Conceal};
 use rgb::dbc::tapret::TapretCommitment;
+use rgb::dbc::Method as DbcMethod;
 use rgb::validation::DbcProof;
 use rgb::vm::{
     ContractStateAccess, ContractStateEvolve, GlobalOrd, GlobalStateEntry, GlobalsIter, OrdOpRef,
     UnknownGlobalStateType, WitnessOrd,
 };
 use rgb::{
-    Assign, AssignmentType, Assignments, AssignmentsRef, BundleId, ContractId, ExposedSeal,
-    ExposedState, FungibleState, Genesis, GenesisSeal, GlobalStateType, GraphSeal, OpId, Operation,
-    Opout, OutputSeal, RevealedData, RevealedValue, Schema, SchemaId, SecretSeal, Transition,
-    TransitionBundle, TypedAssigns, VoidState,
+    Assign, AssignmentType, Assignments, AssignmentsRef, BundleId, ChainNet, ContractId,
+    ExposedSeal, ExposedState, FungibleState, Genesis, GenesisSeal, GlobalStateType, GraphSeal,
+    OpId, Operation, Opout, OutputSeal, RevealedData, RevealedValue, Schema, SchemaId, SecretSeal,
+    Transition, TransitionBundle, TypedAssigns, VoidState,
 };
-use strict_encoding::{DefaultBasedStrictDumb, StrictDeserialize, StrictSerialize};
-use strict_types::TypeSystem;
+use strict_encoding::{
+    DefaultBasedStrictDumb, StrictDeserialize, StrictEncode, StrictSerialize, StrictWriter,
+};
+use strict_types::{SemId, TypeSystem};
 
 use super::{
     ContractStateRead, ContractStateWrite, IndexInconsistency, IndexProvider, IndexReadError,
-    IndexReadProvider, IndexWriteError, IndexWriteProvider, StashInconsistency, StashProvider,
-    StashProviderError, StashReadProvider, StashWriteProvider, StateInconsistency, StateProvider,
-    StateReadProvider, StateWriteProvider, StoreTransaction,
+    IndexReadProvider, IndexWriteError, IndexWriteProvider, InvalidReason, StashInconsistency,
+    StashProvider, StashProviderError, StashReadProvider, StashWriteProvider, StateError,
+    StateInconsistency, StateProvider, StateReadProvider, StateWriteProvider, StoreTransaction,
 };
-use crate::containers::SealWitness;
+use crate::containers::{SealWitness, SealWitnessMergeError};
 use crate::contract::{GlobalOut, KnownState, OpWitness, OutputAssignment};
 use crate::LIB_NAME_RGB_STORAGE;
 
@@ -66,6 +70,80 @@ pub enum MemError {
 
     #[from]
     Confinement(confinement::Error),
+
+    #[from]
+    WitnessMerge(SealWitnessMergeError),
+
+    #[display(doc_comments)]
+    /// contract {0} is not known to the state.
+    UnknownContract(ContractId),
+
+    #[display(doc_comments)]
+    /// state of contract {0} exceeded the maximum number of items which can be stored.
+    StateOverflow(ContractId),
+
+    #[display(doc_comments)]
+    /// schema {schema_id} uses type {sem_id} which is not present in the type system.
+    SchemaTypeMissing { schema_id: SchemaId, sem_id: SemId },
+
+    #[display(doc_comments)]
+    /// genesis of contract {contract_id} is issued on {actual}, while the state is bound to
+    /// {expected}.
+    ChainMismatch {
+        contract_id: ContractId,
+        expected: ChainNet,
+        actual: ChainNet,
+    },
+
+    #[display(doc_comments)]
+    /// snapshot of contract {contract_id} uses schema {schema_id} which is not known.
+    UnknownSchema {
+        contract_id: ContractId,
+        schema_id: SchemaId,
+    },
+
+    #[display(doc_comments)]
+    /// snapshot of contract {contract_id} uses schema {actual}, while the contract is known to use
+    /// schema {expected}.
+    SchemaMismatch {
+        contract_id: ContractId,
+        expected: SchemaId,
+        actual: SchemaId,
+    },
+}
+
+/// State of a transaction on an in-memory provider.
+///
+/// The provider data is snapshotted lazily, before the first write performed after the
+/// transaction has started, so transactions which don't modify the data don't need to copy it.
+#[derive(Debug)]
+struct MemTransaction<T> {
+    active: bool,
+    snapshot: Option<Box<T>>,
+}
+
+impl<T> Default for MemTransaction<T> {
+    fn default() -> Self {
+        Self {
+            active: false,
+            snapshot: None,
+        }
+    }
+}
+
+impl<T> MemTransaction<T> {
+    fn begin(&mut self) {
+        self.active = true;
+        self.snapshot = None;
+    }
+
+    fn needs_snapshot(&self) -> bool { self.active && self.snapshot.is_none() }
+
+    /// Ends the transaction, returning the data snapshot to be restored (if any write happened).
+    fn end(&mut self) -> Option<Box<T>> {
+        self.active = false;
+        self.snapshot.take()
+    }
 }
 
 //////////
@@ -82,11 +160,15 @@ pub struct MemStash {
     #[strict_type(skip)]
     persistence: Option<Persistence<Self>>,
 
+    #[getter(skip)]
+    #[strict_type(skip)]
+    transaction: MemTransaction<Self>,
+
     schemata: TinyOrdMap<SchemaId, Schema>,
     geneses: SmallOrdMap<ContractId, Genesis>,
     bundles: LargeOrdMap<BundleId, TransitionBundle>,
     witnesses: LargeOrdMap<Txid, SealWitness>,
-    secret_seals: LargeOrdSet<GraphSeal>,
+    secret_seals: LargeOrdMap<SecretSeal, GraphSeal>,
     type_system: TypeSystem,
     libs: SmallOrdMap<LibId, Lib>,
 }
@@ -98,6 +180,7 @@ impl MemStash {
     pub fn in_memory() -> Self {
         Self {
             persistence: none!(),
+            transaction: default!(),
             schemata: empty!(),
             geneses: empty!(),
             bundles: empty!(),
@@ -113,6 +196,7 @@ impl CloneNoPersistence for MemStash {
     fn clone_no_persistence(&self) -> Self {
         Self {
             persistence: None,
+            transaction: default!(),
             schemata: self.schemata.clone(),
             geneses: self.geneses.clone(),
             bundles: self.bundles.clone(),
@@ -124,6 +208,111 @@ impl CloneNoPersistence for MemStash {
     }
 }
 
+impl MemStash {
+    fn snapshot(&mut self) {
+        if self.transaction.needs_snapshot() {
+            self.transaction.snapshot = Some(Box::new(self.clone_no_persistence()));
+        }
+    }
+}
+
+/// Summary of the data kept by a [`MemStash`], returned by [`MemStash::stats`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct StashStats {
+    pub schemata: usize,
+    pub geneses: usize,
+    pub bundles: usize,
+    pub witnesses: usize,
+    pub secret_seals: usize,
+    pub libs: usize,
+    /// Size of the strict-encoded stash, in bytes, i.e. the size of its persisted data.
+    pub encoded_size: usize,
+}
+
+impl MemStash {
+    /// Returns the number of items of each kind kept by the stash, together with its size.
+    ///
+    /// The size is computed by encoding the whole stash, so it takes time proportional to it.
+    pub fn stats(&self) -> StashStats {
+        let encoded_size = self
+            .strict_encode(StrictWriter::counter::<{ usize::MAX }>())
+            .expect("counting writer doesn't fail")
+            .unbox()
+            .unconfine()
+            .count;
+        StashStats {
+            schemata: self.schemata.len(),
+            geneses: self.geneses.len(),
+            bundles: self.bundles.len(),
+            witnesses: self.witnesses.len(),
+            secret_seals: self.secret_seals.len(),
+            libs: self.libs.len(),
+            encoded_size,
+        }
+    }
+
+    /// Removes the stored seal secrets matching the provided revealed seals, which are no longer
+    /// needed once the allocations they define have been revealed and confirmed.
+    ///
+    /// Returns the number of removed seals.
+    pub fn prune_revealed_seals(
+        &mut self,
+        revealed: impl IntoIterator<Item = SecretSeal>,
+    ) -> Result<usize, MemError> {
+        self.snapshot();
+        let mut removed = 0;
+        for secret in revealed {
+            if self.secret_seals.remove(&secret)?.is_some() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Registers the schemata together with the type system extension providing their types.
+    ///
+    /// The operation is atomic: the extension and all the schemata are validated before any of
+    /// them is stored, so if the extension can't be merged into the stash type system, or some
+    /// schema uses a type which is neither known to the stash nor provided by the extension, the
+    /// stash is left untouched.
+    ///
+    /// Returns the number of schemata which were not known to the stash before.
+    pub fn register_schemata(
+        &mut self,
+        types: TypeSystem,
+        schemata: impl IntoIterator<Item = Schema>,
+    ) -> Result<usize, MemError> {
+        let mut type_system = self.type_system.clone();
+        type_system.extend(types)?;
+
+        let mut registry = self.schemata.clone();
+        let mut added = 0;
+        for schema in schemata {
+            let schema_id = schema.schema_id();
+            if let Some(sem_id) = schema
+                .types()
+                .find(|sem_id| type_system.extract([*sem_id]).is_err())
+            {
+                return Err(MemError::SchemaTypeMissing { schema_id, sem_id });
+            }
+            if !registry.contains_key(&schema_id) {
+                registry.insert(schema_id, schema)?;
+                added += 1;
+            }
+        }
+
+        self.snapshot();
+        self.type_system = type_system;
+        self.schemata = registry;
+        Ok(added)
+    }
+}
+
 impl Persisting for MemStash {
     #[inline]
     fn persistence(&self) -> Option<&Persistence<Self>> { self.persistence.as_ref() }
@@ -137,13 +326,23 @@ impl StoreTransaction for MemStash {
     type TransactionErr = MemError;
     #[inline]
     fn begin_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.transaction.begin();
         self.mark_dirty();
         Ok(())
     }
     #[inline]
-    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> { Ok(self.store()?) }
+    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.transaction.end();
+        Ok(self.store()?)
+    }
     #[inline]
-    fn rollback_transaction(&mut self) { unreachable!() }
+    fn rollback_transaction(&mut self) {
+        if let Some(snapshot) = self.transaction.end() {
+            let persistence = self.persistence.take();
+            *self = *snapshot;
+            self.persistence = persistence;
+        }
+    }
 }
 
 impl StashProvider for MemStash {}
@@ -160,7 +359,7 @@ impl StashReadProvider for MemStash {
             .ok_or_else(|| StashInconsistency::LibAbsent(id).into())
     }
 
-    fn schemata(&self) -> Result<impl Iterator<Item = &Schema>, Self::Error> {
+    fn schemata(&self) -> Result<impl ExactSizeIterator<Item = &Schema>, Self::Error> {
         Ok(self.schemata.values())
     }
 
@@ -170,7 +369,7 @@ impl StashReadProvider for MemStash {
             .ok_or_else(|| StashInconsistency::SchemaAbsent(schema_id).into())
     }
 
-    fn geneses(&self) -> Result<impl Iterator<Item = &Genesis>, Self::Error> {
+    fn geneses(&self) -> Result<impl ExactSizeIterator<Item = &Genesis>, Self::Error> {
         Ok(self.geneses.values())
     }
 
@@ -183,11 +382,11 @@ impl StashReadProvider for MemStash {
             .ok_or(StashInconsistency::ContractAbsent(contract_id).into())
     }
 
-    fn witness_ids(&self) -> Result<impl Iterator<Item = Txid>, Self::Error> {
+    fn witness_ids(&self) -> Result<impl ExactSizeIterator<Item = Txid>, Self::Error> {
         Ok(self.witnesses.keys().copied())
     }
 
-    fn bundle_ids(&self) -> Result<impl Iterator<Item = BundleId>, Self::Error> {
+    fn bundle_ids(&self) -> Result<impl ExactSizeIterator<Item = BundleId>, Self::Error> {
         Ok(self.bundles.keys().copied())
     }
 
@@ -200,12 +399,41 @@ impl StashReadProvider for MemStash {
             .ok_or(StashInconsistency::BundleAbsent(bundle_id).into())
     }
 
+    fn contract_bundles(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<impl Iterator<Item = &TransitionBundle>, StashProviderError<Self::Error>> {
+        self.genesis(contract_id)?;
+        Ok(self.bundles.values().filter(move |bundle| {
+            bundle
+                .known_transitions
+                .iter()
+                .any(|kt| kt.transition.contract_id == contract_id)
+        }))
+    }
+
     fn witness(&self, witness_id: Txid) -> Result<&SealWitness, StashProviderError<Self::Error>> {
         self.witnesses
             .get(&witness_id)
             .ok_or(StashInconsistency::WitnessAbsent(witness_id).into())
     }
 
+    fn contains_schema(&self, schema_id: SchemaId) -> Result<bool, Self::Error> {
+        Ok(self.schemata.contains_key(&schema_id))
+    }
+
+    fn contains_genesis(&self, contract_id: ContractId) -> Result<bool, Self::Error> {
+        Ok(self.geneses.contains_key(&contract_id))
+    }
+
+    fn contains_bundle(&self, bundle_id: BundleId) -> Result<bool, Self::Error> {
+        Ok(self.bundles.contains_key(&bundle_id))
+    }
+
+    fn contains_witness(&self, witness_id: Txid) -> Result<bool, Self::Error> {
+        Ok(self.witnesses.contains_key(&witness_id))
+    }
+
     fn taprets(&self) -> Result<impl Iterator<Item = (Txid, TapretCommitment)>, Self::Error> {
         Ok(self
             .witnesses
@@ -219,16 +447,52 @@ impl StashReadProvider for MemStash {
             }))
     }
 
-    fn seal_secret(&self, secret: SecretSeal) -> Result<Option<GraphSeal>, Self::Error> {
+    fn oprets(&self) -> Result<impl Iterator<Item = (Txid, mpc::Commitment)>, Self::Error> {
+        Ok(self
+            .witnesses
+            .iter()
+            .filter_map(|(witness_id, witness)| match &witness.dbc_proof {
+                DbcProof::Opret(_) => Some((*witness_id, witness.merkle_block.commit_id())),
+                _ => None,
+            }))
+    }
+
+    fn dbc_proofs_by_method(&self) -> Result<BTreeMap<DbcMethod, Vec<Txid>>, Self::Error> {
+        let mut map = BTreeMap::<DbcMethod, Vec<Txid>>::new();
+        for (witness_id, witness) in &self.witnesses {
+            let method = match &witness.dbc_proof {
+                DbcProof::Tapret(_) => DbcMethod::TapretFirst,
+                DbcProof::Opret(_) => DbcMethod::OpretFirst,
+            };
+            map.entry(method).or_default().push(*witness_id);
+        }
+        Ok(map)
+    }
+
+    fn dbc_proof(&self, witness_id: Txid) -> Result<Option<&DbcProof>, Self::Error> {
+        Ok(self
+            .witnesses
+            .get(&witness_id)
+            .map(|witness| &witness.dbc_proof))
+    }
+
+    fn witnesses_by_dbc(
+        &self,
+        pred: impl Fn(&DbcProof) -> bool,
+    ) -> Result<impl Iterator<Item = Txid>, Self::Error> {
         Ok(self
-            .secret_seals
+            .witnesses
             .iter()
-            .find(|s| s.conceal() == secret)
-            .copied())
+            .filter(move |(_, witness)| pred(&witness.dbc_proof))
+            .map(|(witness_id, _)| *witness_id))
+    }
+
+    fn seal_secret(&self, secret: SecretSeal) -> Result<Option<GraphSeal>, Self::Error> {
+        Ok(self.secret_seals.get(&secret).copied())
     }
 
     fn secret_seals(&self) -> Result<impl Iterator<Item = GraphSeal>, Self::Error> {
-        Ok(self.secret_seals.iter().copied())
+        Ok(self.secret_seals.values().copied())
     }
 }
 
@@ -236,6 +500,7 @@ impl StashWriteProvider for MemStash {
     type Error = MemError;
 
     fn replace_schema(&mut self, schema: Schema) -> Result<bool, Self::Error> {
+        self.snapshot();
         let schema_id = schema.schema_id();
         if !self.schemata.contains_key(&schema_id) {
             self.schemata.insert(schema_id, schema)?;
@@ -245,37 +510,100 @@ impl StashWriteProvider for MemStash {
     }
 
     fn replace_genesis(&mut self, genesis: Genesis) -> Result<bool, Self::Error> {
+        self.snapshot();
         let contract_id = genesis.contract_id();
         let present = self.geneses.insert(contract_id, genesis)?.is_some();
         Ok(!present)
     }
 
     fn replace_bundle(&mut self, bundle: TransitionBundle) -> Result<bool, Self::Error> {
+        self.snapshot();
         let bundle_id = bundle.bundle_id();
         let present = self.bundles.insert(bundle_id, bundle)?.is_some();
         Ok(!present)
     }
 
+    fn replace_bundles(
+        &mut self,
+        bundles: impl IntoIterator<Item = TransitionBundle>,
+    ) -> Result<usize, Self::Error> {
+        self.snapshot();
+        let mut added = 0;
+        for bundle in bundles {
+            added += self.bundles.insert(bundle.bundle_id(), bundle)?.is_none() as usize;
+        }
+        Ok(added)
+    }
+
+    /// Stores the witness, merging it with the already known witness with the same id (if any),
+    /// so that the revealed transaction and MPC messages of both are preserved.
+    ///
+    /// Fails, without modifying the stash, if the witnesses have different DBC proofs.
     fn replace_witness(&mut self, witness: SealWitness) -> Result<bool, Self::Error> {
         let witness_id = witness.witness_id();
-        let present = self.witnesses.insert(witness_id, witness)?.is_some();
+        let (witness, present) = match self.witnesses.get(&witness_id) {
+            Some(known) => {
+                let mut merged = known.clone();
+                merged.merge_reveal(&witness)?;
+                (merged, true)
+            }
+            None => (witness, false),
+        };
+        self.snapshot();
+        self.witnesses.insert(witness_id, witness)?;
         Ok(!present)
     }
 
     fn consume_types(&mut self, types: TypeSystem) -> Result<(), Self::Error> {
+        self.snapshot();
         Ok(self.type_system.extend(types)?)
     }
 
     fn replace_lib(&mut self, lib: Lib) -> Result<bool, Self::Error> {
+        self.snapshot();
         let present = self.libs.insert(lib.id(), lib)?.is_some();
         Ok(!present)
     }
 
     fn add_secret_seal(&mut self, seal: GraphSeal) -> Result<bool, Self::Error> {
-        let present = self.secret_seals.contains(&seal);
-        self.secret_seals.push(seal)?;
+        self.snapshot();
+        let present = self.secret_seals.insert(seal.conceal(), seal)?.is_some();
         Ok(!present)
     }
+
+    fn forget_contract(&mut self, contract_id: ContractId) -> Result<usize, Self::Error> {
+        self.snapshot();
+        self.geneses.remove(&contract_id)?;
+        let bundle_ids = self
+            .bundles
+            .iter()
+            .filter(|(_, bundle)| {
+                bundle
+                    .known_transitions
+                    .iter()
+                    .any(|kt| kt.transition.contract_id == contract_id)
+            })
+            .map(|(bundle_id, _)| *bundle_id)
+            .collect::<BTreeSet<_>>();
+        for bundle_id in &bundle_ids {
+            self.bundles.remove(bundle_id)?;
+        }
+        // Witnesses are kept as long as they commit to a bundle of some other contract
+        let orphaned = self
+            .witnesses
+            .iter()
+            .filter(|(_, witness)| {
+                let known = witness.known_bundle_ids().collect::<BTreeSet<_>>();
+                !known.is_disjoint(&bundle_ids)
+                    && !known.iter().any(|id| self.bundles.contains_key(id))
+            })
+            .map(|(witness_id, _)| *witness_id)
+            .collect::<Vec<_>>();
+        for witness_id in &orphaned {
+            self.witnesses.remove(witness_id)?;
+        }
+        Ok(orphaned.len())
+    }
 }
 
 //////////
@@ -291,8 +619,16 @@ pub struct MemState {
     #[strict_type(skip)]
     persistence: Option<Persistence<Self>>,
 
+    #[getter(skip)]
+    #[strict_type(skip)]
+    transaction: MemTransaction<Self>,
+
+    #[getter(skip)]
+    #[strict_type(skip)]
+    chain_net: Option<ChainNet>,
+
     witnesses: LargeOrdMap<Txid, WitnessOrd>,
-    invalid_bundles: LargeOrdSet<BundleId>,
+    invalid_bundles: LargeOrdMap<BundleId, InvalidReason>,
     contracts: SmallOrdMap<ContractId, MemContractState>,
 }
 
@@ -303,17 +639,41 @@ impl MemState {
     pub fn in_memory() -> Self {
         Self {
             persistence: none!(),
+            transaction: default!(),
+            chain_net: None,
             witnesses: empty!(),
             invalid_bundles: empty!(),
             contracts: empty!(),
         }
     }
+
+    /// Binds the state to the given [`ChainNet`], so that registering a contract whose genesis is
+    /// issued on a different chain fails with [`MemError::ChainMismatch`].
+    ///
+    /// The binding is not persisted, thus it has to be set again after loading the state.
+    pub fn with_chain_net(mut self, chain_net: ChainNet) -> Self {
+        self.chain_net = Some(chain_net);
+        self
+    }
+
+    /// Binds the state to the given [`ChainNet`], see [`MemState::with_chain_net`].
+    pub fn set_chain_net(&mut self, chain_net: ChainNet) { self.chain_net = Some(chain_net); }
+
+    /// Returns the [`ChainNet`] the state is bound to, if any.
+    pub fn chain_net(&self) -> Option<ChainNet> { self.chain_net }
 }
 
 impl CloneNoPersistence for MemState {
+    /// Clones the state without its persistence provider.
+    ///
+    /// **Note:** the clone doesn't keep the set of invalid bundles, so the allocations which are
+    /// suppressed because of an invalid bundle become visible in the clone. Use
+    /// [`MemState::clone_full`] to get an exact copy of the state, e.g. for backups.
     fn clone_no_persistence(&self) -> Self {
         Self {
             persistence: None,
+            transaction: default!(),
+            chain_net: self.chain_net,
             witnesses: self.witnesses.clone(),
             invalid_bundles: empty!(),
             contracts: self.contracts.clone(),
@@ -321,6 +681,22 @@ impl CloneNoPersistence for MemState {
     }
 }
 
+impl MemState {
+    /// Clones the state without its persistence provider, preserving the witnesses, the contracts
+    /// and the set of invalid bundles exactly, unlike [`CloneNoPersistence::clone_no_persistence`].
+    pub fn clone_full(&self) -> Self {
+        let mut clone = self.clone_no_persistence();
+        clone.invalid_bundles = self.invalid_bundles.clone();
+        clone
+    }
+
+    fn snapshot(&mut self) {
+        if self.transaction.needs_snapshot() {
+            self.transaction.snapshot = Some(Box::new(self.clone_full()));
+        }
+    }
+}
+
 impl Persisting for MemState {
     #[inline]
     fn persistence(&self) -> Option<&Persistence<Self>> { self.persistence.as_ref() }
@@ -334,25 +710,48 @@ impl StoreTransaction for MemState {
     type TransactionErr = MemError;
     #[inline]
     fn begin_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.transaction.begin();
         self.mark_dirty();
         Ok(())
     }
     #[inline]
-    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> { Ok(self.store()?) }
+    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.transaction.end();
+        Ok(self.store()?)
+    }
     #[inline]
-    fn rollback_transaction(&mut self) { unreachable!() }
+    fn rollback_transaction(&mut self) {
+        if let Some(snapshot) = self.transaction.end() {
+            let persistence = self.persistence.take();
+            *self = *snapshot;
+            self.persistence = persistence;
+        }
+    }
 }
 
 impl StateProvider for MemState {}
 
-impl StateReadProvider for MemState {
-    type ContractRead<'a> = MemContract<&'a MemContractState>;
-    type Error = StateInconsistency;
+impl MemState {
+    /// Returns the contract state, filtering out the allocations whose witness has an ordinal
+    /// following `min_ord` in the [`WitnessOrd`] ordering, where mined witnesses come first.
+    ///
+    /// For instance, passing [`WitnessOrd::Tentative`] hides allocations from ignored witnesses,
+    /// while passing [`WitnessOrd::Mined`] with the position of a block keeps only the
+    /// allocations whose witness was mined in or before that block. Archived witnesses are always
+    /// filtered out, as with [`StateReadProvider::contract_state`].
+    pub fn contract_state_filtered(
+        &self,
+        contract_id: ContractId,
+        min_ord: WitnessOrd,
+    ) -> Result<MemContract<&MemContractState>, StateInconsistency> {
+        self.contract_state_with(contract_id, |ord| ord <= min_ord)
+    }
 
-    fn contract_state(
+    fn contract_state_with(
         &self,
         contract_id: ContractId,
-    ) -> Result<Self::ContractRead<'_>, Self::Error> {
+        keep: impl Fn(WitnessOrd) -> bool,
+    ) -> Result<MemContract<&MemContractState>, StateInconsistency> {
         let unfiltered = self
             .contracts
             .get(&contract_id)
@@ -360,6 +759,7 @@ impl StateReadProvider for MemState {
         let filter = self
             .witnesses
             .iter()
+            .filter(|(_, ord)| keep(**ord))
             .filter(|(id, _)| {
                 let id = Some(**id);
                 unfiltered
@@ -370,28 +770,203 @@ impl StateReadProvider for MemState {
                     || unfiltered.rights.iter().any(|a| a.witness == id)
                     || unfiltered.fungibles.iter().any(|a| a.witness == id)
                     || unfiltered.data.iter().any(|a| a.witness == id)
+                    || unfiltered.concealed_data.values().any(|c| c.witness == id)
             })
             .map(|(id, ord)| (*id, *ord))
             .collect();
-        Ok(MemContract::new(filter, self.invalid_bundles.clone().release(), unfiltered))
+        Ok(MemContract::new(filter, self.invalid_bundle_ids(), unfiltered))
     }
 
-    fn witnesses(&self) -> LargeOrdMap<Txid, WitnessOrd> { self.witnesses.clone() }
+    /// Returns statistics on the validity of the bundles of a contract, including the number of
+    /// allocations suppressed because their bundle is invalid.
+    ///
+    /// For a contract unknown to the state all the counters are zero.
+    pub fn bundle_stats(&self, contract_id: ContractId) -> BundleStats {
+        let Some(contract) = self.contracts.get(&contract_id) else {
+            return BundleStats::default();
+        };
+        let invalid_bundles = self.invalid_bundle_ids();
+        let suppressed_allocations = contract
+            .rights
+            .iter()
+            .filter(|a| !a.check_bundle(&invalid_bundles))
+            .count()
+            + contract
+                .fungibles
+                .iter()
+                .filter(|a| !a.check_bundle(&invalid_bundles))
+                .count()
+            + contract
+                .data
+                .iter()
+                .filter(|a| !a.check_bundle(&invalid_bundles))
+                .count();
+        let bundle_ids = contract.bundle_ids();
+        BundleStats {
+            total_bundles: bundle_ids.len(),
+            invalid_bundles: bundle_ids.intersection(&invalid_bundles).count(),
+            suppressed_allocations,
+        }
+    }
 
-    fn invalid_bundles(&self) -> LargeOrdSet<BundleId> { self.invalid_bundles.clone() }
+    fn invalid_bundle_ids(&self) -> BTreeSet<BundleId> {
+        self.invalid_bundles.keys().copied().collect()
+    }
 }
 
-impl StateWriteProvider for MemState {
-    type ContractWrite<'a> = MemContractWriter<'a>;
-    type Error = MemError;
+impl MemState {
+    /// Exports the complete derived state of a contract, together with the ordinals of its
+    /// witnesses and its invalid bundles, as a self-contained [`ContractSnapshot`].
+    pub fn export_contract(&self, contract_id: ContractId) -> Result<ContractSnapshot, MemError> {
+        let state = self
+            .contracts
+            .get(&contract_id)
+            .ok_or(MemError::UnknownContract(contract_id))?
+            .clone();
+        let witness_ids = state.witness_ids();
+        let bundle_ids = state.bundle_ids();
+        let witnesses = LargeOrdMap::try_from_iter(
+            self.witnesses
+                .iter()
+                .filter(|(id, _)| witness_ids.contains(*id))
+                .map(|(id, ord)| (*id, *ord)),
+        )?;
+        let invalid_bundles = LargeOrdMap::try_from_iter(
+            self.invalid_bundles
+                .iter()
+                .filter(|(id, _)| bundle_ids.contains(*id))
+                .map(|(id, reason)| (*id, *reason)),
+        )?;
+        Ok(ContractSnapshot {
+            state,
+            witnesses,
+            invalid_bundles,
+        })
+    }
 
-    fn register_contract(
+    /// Imports a contract state previously exported with [`MemState::export_contract`], without
+    /// re-running the contract validation.
+    ///
+    /// The state of the contract, if already present, is replaced with the snapshot one; witness
+    /// ordinals already known to the state are kept, since they may be more recent than the
+    /// snapshot ones.
+    ///
+    /// The snapshot is rejected if its schema is not known, as reported by `is_known_schema`
+    /// (since the state doesn't keep schemata, this is usually a lookup into the stash), or if
+    /// the contract is already known with a different schema.
+    pub fn import_contract(
         &mut self,
+        snapshot: ContractSnapshot,
+        is_known_schema: impl Fn(SchemaId) -> bool,
+    ) -> Result<(), MemError> {
+        let ContractSnapshot {
+            state,
+            witnesses,
+            invalid_bundles,
+        } = snapshot;
+        let contract_id = state.contract_id;
+        if !is_known_schema(state.schema_id) {
+            return Err(MemError::UnknownSchema {
+                contract_id,
+                schema_id: state.schema_id,
+            });
+        }
+        if let Some(known) = self.contracts.get(&contract_id) {
+            if known.schema_id != state.schema_id {
+                return Err(MemError::SchemaMismatch {
+                    contract_id,
+                    expected: known.schema_id,
+                    actual: state.schema_id,
+                });
+            }
+        }
+        self.snapshot();
+        for (witness_id, ord) in witnesses {
+            if !self.witnesses.contains_key(&witness_id) {
+                self.witnesses.insert(witness_id, ord)?;
+            }
+        }
+        for (bundle_id, reason) in invalid_bundles {
+            self.invalid_bundles.insert(bundle_id, reason)?;
+        }
+        self.contracts.insert(contract_id, state)?;
+        Ok(())
+    }
+}
+
+impl StateReadProvider for MemState {
+    type ContractRead<'a> = MemContract<&'a MemContractState>;
+    type Error = StateInconsistency;
+
+    fn contract_state(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<Self::ContractRead<'_>, Self::Error> {
+        self.contract_state_with(contract_id, |_| true)
+    }
+
+    fn contract_ids(&self) -> Result<impl Iterator<Item = ContractId> + '_, Self::Error> {
+        Ok(self.contracts.keys().copied())
+    }
+
+    fn contract_exists(&self, contract_id: ContractId) -> bool {
+        self.contracts.contains_key(&contract_id)
+    }
+
+    fn witnesses(&self) -> LargeOrdMap<Txid, WitnessOrd> { self.witnesses.clone() }
+
+    fn witnesses_with_status(
+        &self,
+        filter: impl Fn(&WitnessOrd) -> bool,
+    ) -> LargeOrdMap<Txid, WitnessOrd> {
+        LargeOrdMap::from_iter_checked(
+            self.witnesses
+                .iter()
+                .filter(|(_, ord)| filter(ord))
+                .map(|(id, ord)| (*id, *ord)),
+        )
+    }
+
+    fn invalid_bundles(&self) -> LargeOrdSet<BundleId> {
+        LargeOrdSet::from_iter_checked(self.invalid_bundles.keys().copied())
+    }
+
+    fn bundle_invalid_reason(&self, bundle_id: BundleId) -> Option<InvalidReason> {
+        self.invalid_bundles.get(&bundle_id).copied()
+    }
+}
+
+impl MemState {
+    /// Registers the contract like [`StateWriteProvider::register_contract`], reporting to the
+    /// `observer` each [`StateChange`] made by the genesis and by the operations added later with
+    /// the returned writer.
+    pub fn register_contract_observed<'mem>(
+        &'mem mut self,
         schema: &Schema,
         genesis: &Genesis,
-    ) -> Result<Self::ContractWrite<'_>, Self::Error> {
-        // TODO: Add begin/commit transaction
+        observer: impl FnMut(StateChange) + 'mem,
+    ) -> Result<MemContractWriter<'mem>, MemError> {
+        self.register_contract_with(schema, genesis, Some(Box::new(observer)))
+    }
+
+    fn register_contract_with<'mem>(
+        &'mem mut self,
+        schema: &Schema,
+        genesis: &Genesis,
+        observer: Option<Box<dyn FnMut(StateChange) + 'mem>>,
+    ) -> Result<MemContractWriter<'mem>, MemError> {
         let contract_id = genesis.contract_id();
+        if let Some(expected) = self
+            .chain_net
+            .filter(|chain_net| *chain_net != genesis.chain_net)
+        {
+            return Err(MemError::ChainMismatch {
+                contract_id,
+                expected,
+                actual: genesis.chain_net,
+            });
+        }
+        self.snapshot();
         // This crazy construction is caused by a stupidity of rust borrow checker
         let contract = if self.contracts.contains_key(&contract_id) {
             if let Some(contract) = self.contracts.get_mut(&contract_id) {
@@ -414,16 +989,30 @@ impl StateWriteProvider for MemState {
                 },
             ),
             contract,
+            observer,
         };
         writer.add_genesis(genesis)?;
         Ok(writer)
     }
+}
+
+impl StateWriteProvider for MemState {
+    type ContractWrite<'a> = MemContractWriter<'a>;
+    type Error = MemError;
+
+    fn register_contract(
+        &mut self,
+        schema: &Schema,
+        genesis: &Genesis,
+    ) -> Result<Self::ContractWrite<'_>, Self::Error> {
+        self.register_contract_with(schema, genesis, None)
+    }
 
     fn update_contract(
         &mut self,
         contract_id: ContractId,
     ) -> Result<Option<Self::ContractWrite<'_>>, Self::Error> {
-        // TODO: Add begin/commit transaction
+        self.snapshot();
         Ok(self
             .contracts
             .get_mut(&contract_id)
@@ -440,6 +1029,7 @@ impl StateWriteProvider for MemState {
                     },
                 ),
                 contract,
+                observer: None,
             }))
     }
 
@@ -448,36 +1038,192 @@ impl StateWriteProvider for MemState {
         witness_id: Txid,
         witness_ord: WitnessOrd,
     ) -> Result<(), Self::Error> {
+        self.snapshot();
         self.witnesses.insert(witness_id, witness_ord)?;
         Ok(())
     }
 
-    fn update_bundle(&mut self, bundle_id: BundleId, valid: bool) -> Result<(), Self::Error> {
-        if valid {
-            self.invalid_bundles.remove(&bundle_id)?;
-        } else {
-            self.invalid_bundles.push(bundle_id)?;
+    fn update_bundle(
+        &mut self,
+        bundle_id: BundleId,
+        invalid: Option<InvalidReason>,
+    ) -> Result<(), Self::Error> {
+        self.snapshot();
+        match invalid {
+            Some(reason) => {
+                self.invalid_bundles.insert(bundle_id, reason)?;
+            }
+            None => {
+                self.invalid_bundles.remove(&bundle_id)?;
+            }
         }
         Ok(())
     }
+
+    fn forget_contract(&mut self, contract_id: ContractId) -> Result<usize, Self::Error> {
+        self.snapshot();
+        let Some(contract) = self.contracts.remove(&contract_id)? else {
+            return Ok(0);
+        };
+        let retained = self
+            .contracts
+            .values()
+            .flat_map(MemContractState::witness_ids)
+            .collect::<BTreeSet<_>>();
+        let mut removed = 0;
+        for witness_id in contract.witness_ids().difference(&retained) {
+            if self.witnesses.remove(witness_id)?.is_some() {
+                removed += 1;
+            }
+        }
+        for bundle_id in contract.bundle_ids() {
+            self.invalid_bundles.remove(&bundle_id)?;
+        }
+        Ok(removed)
+    }
 }
 
+/// Self-contained snapshot of the derived state of a single contract, produced by
+/// [`MemState::export_contract`] and consumed by [`MemState::import_contract`].
 #[derive(Getters, Clone, Eq, PartialEq, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB_STORAGE)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
-pub struct MemGlobalState {
-    known: LargeOrdMap<GlobalOut, RevealedData>,
-    limit: u24,
-}
-
-impl MemGlobalState {
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ContractSnapshot {
+    /// Contract state.
+    state: MemContractState,
+    /// Ordinals of the contract witnesses.
+    witnesses: LargeOrdMap<Txid, WitnessOrd>,
+    /// Contract bundles which are marked as invalid, with the reason.
+    invalid_bundles: LargeOrdMap<BundleId, InvalidReason>,
+}
+
+impl StrictSerialize for ContractSnapshot {}
+impl StrictDeserialize for ContractSnapshot {}
+
+impl ContractSnapshot {
+    #[inline]
+    pub fn contract_id(&self) -> ContractId { self.state.contract_id }
+
+    #[inline]
+    pub fn schema_id(&self) -> SchemaId { self.state.schema_id }
+}
+
+/// Statistics on the validity of the bundles of a contract, returned by
+/// [`MemState::bundle_stats`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct BundleStats {
+    /// Number of bundles contributing to the contract state.
+    pub total_bundles: usize,
+    /// Number of the contract bundles which are marked as invalid.
+    pub invalid_bundles: usize,
+    /// Number of allocations hidden from the contract state because their bundle is invalid.
+    pub suppressed_allocations: usize,
+}
+
+/// All the RGB state of a contract allocated to a single outpoint, grouped by assignment type
+/// and returned by [`MemContract::outpoint_summary`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct OutpointSummary {
+    /// Sum of the fungible allocations of each type, saturating at `u64::MAX`.
+    pub fungible: BTreeMap<AssignmentType, u64>,
+    /// Structured data allocations of each type.
+    pub data: BTreeMap<AssignmentType, Vec<RevealedData>>,
+    /// Number of rights of each type.
+    pub rights: BTreeMap<AssignmentType, u32>,
+}
+
+impl OutpointSummary {
+    /// Detects whether no state is allocated to the outpoint.
+    pub fn is_empty(&self) -> bool {
+        self.fungible.is_empty() && self.data.is_empty() && self.rights.is_empty()
+    }
+}
+
+#[derive(Getters, Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STORAGE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+pub struct MemGlobalState {
+    #[cfg_attr(feature = "serde", serde(with = "global_known_serde"))]
+    known: LargeOrdMap<GlobalOut, RevealedData>,
+    limit: u24,
+}
+
+impl MemGlobalState {
     pub fn new(limit: u24) -> Self {
         MemGlobalState {
             known: empty!(),
             limit,
         }
     }
+
+    /// Number of all known global state items, which is taken in constant time.
+    #[inline]
+    pub fn size(&self) -> usize { self.known.len() }
+}
+
+/// Serializes the known global state as a list of entries in the [`GlobalOut`] order, since
+/// its keys are structures, which can't be used as keys by formats like JSON.
+#[cfg(feature = "serde")]
+mod global_known_serde {
+    use amplify::confinement::LargeOrdMap;
+    use rgb::RevealedData;
+    use serde_crate::ser::SerializeSeq;
+    use serde_crate::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::contract::GlobalOut;
+
+    #[derive(Serialize)]
+    #[serde(crate = "serde_crate")]
+    struct EntryRef<'a> {
+        out: &'a GlobalOut,
+        data: &'a RevealedData,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Entry {
+        out: GlobalOut,
+        data: RevealedData,
+    }
+
+    pub fn serialize<S>(
+        known: &LargeOrdMap<GlobalOut, RevealedData>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(known.len()))?;
+        for (out, data) in known.iter() {
+            seq.serialize_element(&EntryRef { out, data })?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<LargeOrdMap<GlobalOut, RevealedData>, D::Error>
+    where D: Deserializer<'de> {
+        let entries = Vec::<Entry>::deserialize(deserializer)?;
+        LargeOrdMap::try_from_iter(entries.into_iter().map(|entry| (entry.out, entry.data)))
+            .map_err(de::Error::custom)
+    }
 }
 
 /// Contract history accumulates raw data from the contract history, extracted
@@ -507,6 +1253,23 @@ pub struct MemContractState {
     rights: LargeOrdSet<OutputAssignment<VoidState>>,
     fungibles: LargeOrdSet<OutputAssignment<RevealedValue>>,
     data: LargeOrdSet<OutputAssignment<RevealedData>>,
+    concealed_data: LargeOrdMap<Opout, ConcealedData>,
+}
+
+/// Structured data allocation whose seal is known only in its concealed form, and which thus is
+/// not part of [`MemContractState::data`] until the seal gets revealed.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STORAGE)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ConcealedData {
+    pub seal: SecretSeal,
+    pub witness: Option<Txid>,
+    pub bundle_id: Option<BundleId>,
 }
 
 impl MemContractState {
@@ -524,11 +1287,110 @@ impl MemContractState {
             rights: empty!(),
             fungibles: empty!(),
             data: empty!(),
+            concealed_data: empty!(),
+        }
+    }
+
+    /// Ids of all the witnesses referenced by the contract state.
+    pub fn witness_ids(&self) -> BTreeSet<Txid> {
+        self.global
+            .values()
+            .flat_map(|state| state.known.keys())
+            .filter_map(GlobalOut::witness_id)
+            .chain(self.rights.iter().filter_map(|a| a.witness))
+            .chain(self.fungibles.iter().filter_map(|a| a.witness))
+            .chain(self.data.iter().filter_map(|a| a.witness))
+            .chain(self.concealed_data.values().filter_map(|c| c.witness))
+            .collect()
+    }
+
+    /// Ids of all the bundles referenced by the contract state.
+    pub fn bundle_ids(&self) -> BTreeSet<BundleId> {
+        self.rights
+            .iter()
+            .filter_map(|a| a.bundle_id)
+            .chain(self.fungibles.iter().filter_map(|a| a.bundle_id))
+            .chain(self.data.iter().filter_map(|a| a.bundle_id))
+            .chain(self.concealed_data.values().filter_map(|c| c.bundle_id))
+            .collect()
+    }
+
+    /// Detects whether a structured data allocation with a revealed seal is known for `opout`.
+    ///
+    /// The allocations are ordered by their opout first, so instead of scanning all of them the
+    /// lookup starts from the lowest possible allocation for `opout`.
+    fn has_revealed_data(&self, opout: Opout) -> bool {
+        let lowest = OutputAssignment {
+            opout,
+            seal: OutputSeal::with(<Txid as rgb::bitcoin::hashes::Hash>::all_zeros(), 0u32),
+            state: strict_dumb!(),
+            witness: None,
+            bundle_id: None,
+        };
+        self.data
+            .as_unconfined()
+            .range(lowest..)
+            .next()
+            .is_some_and(|a| a.opout == opout)
+    }
+
+    pub(crate) fn add_operation(&mut self, op: OrdOpRef) -> Result<(), MemError> {
+        self.add_operation_observed(op, None)
+    }
+
+    /// Adds the operation to the contract state, but fails without modifying the state if its
+    /// global state would exceed the limit defined by the schema, or has a type not defined by the
+    /// schema.
+    ///
+    /// Use this method for the data which weren't validated against the contract schema.
+    pub fn add_operation_checked(&mut self, op: OrdOpRef) -> Result<(), StateError<MemState>> {
+        let opid = op.id();
+        for (ty, state) in op.globals() {
+            let map = self
+                .global
+                .get(ty)
+                .ok_or(StateError::UnknownGlobalType(*ty))?;
+            let added = (0..state.len())
+                .map(|idx| GlobalOut {
+                    index: idx as u16,
+                    op_witness: OpWitness::from(op),
+                    nonce: op.nonce(),
+                    opid,
+                })
+                .filter(|out| !map.known.contains_key(out))
+                .count();
+            if map.known.len() + added > map.limit.to_usize() {
+                return Err(StateError::GlobalStateOverflow {
+                    ty: *ty,
+                    limit: map.limit,
+                });
+            }
         }
+        self.add_operation(op).map_err(StateError::WriteProvider)
+    }
+
+    /// Updates the limit of the global state items of the given type, which is set from the
+    /// schema when the contract state is created.
+    ///
+    /// The items which were hidden by the previous limit are not discarded, so increasing the
+    /// limit makes them visible with the next call of [`ContractStateAccess::global`].
+    pub fn set_global_limit(
+        &mut self,
+        ty: GlobalStateType,
+        new_limit: u24,
+    ) -> Result<(), UnknownGlobalStateType> {
+        let state = self.global.get_mut(&ty).ok_or(UnknownGlobalStateType(ty))?;
+        state.limit = new_limit;
+        Ok(())
     }
 
-    pub(crate) fn add_operation(&mut self, op: OrdOpRef) {
+    fn add_operation_observed(
+        &mut self,
+        op: OrdOpRef,
+        mut observer: Option<&mut (dyn FnMut(StateChange) + '_)>,
+    ) -> Result<(), MemError> {
         let opid = op.id();
+        let contract_id = self.contract_id;
 
         for (ty, state) in op.globals() {
             let map = self
@@ -544,7 +1406,10 @@ impl MemContractState {
                 };
                 map.known
                     .insert(out, s.clone())
-                    .expect("contract global state exceeded 2^32 items, which is unrealistic");
+                    .map_err(|_| MemError::StateOverflow(contract_id))?;
+                if let Some(observer) = observer.as_deref_mut() {
+                    observer(StateChange::Global(out, s.clone()));
+                }
             }
         }
 
@@ -552,10 +1417,10 @@ impl MemContractState {
         let witness_id = op.witness_id();
         match op.assignments() {
             AssignmentsRef::Genesis(assignments) => {
-                self.add_assignments(bundle_id, witness_id, opid, assignments)
+                self.add_assignments(bundle_id, witness_id, opid, assignments, observer)
             }
             AssignmentsRef::Graph(assignments) => {
-                self.add_assignments(bundle_id, witness_id, opid, assignments)
+                self.add_assignments(bundle_id, witness_id, opid, assignments, observer)
             }
         }
     }
@@ -566,50 +1431,131 @@ impl MemContractState {
         witness_id: Option<Txid>,
         opid: OpId,
         assignments: &Assignments<Seal>,
-    ) {
-        fn process<State: ExposedState + KnownState, Seal: ExposedSeal>(
-            contract_state: &mut LargeOrdSet<OutputAssignment<State>>,
-            assignments: &[Assign<State, Seal>],
-            bundle_id: Option<BundleId>,
-            opid: OpId,
-            ty: AssignmentType,
-            witness_id: Option<Txid>,
-        ) {
-            for (no, seal, state) in assignments
-                .iter()
-                .enumerate()
-                .filter_map(|(n, a)| a.to_revealed().map(|(seal, state)| (n, seal, state)))
-            {
-                let assigned_state = match witness_id {
-                    Some(witness_id) => OutputAssignment::with_witness(
-                        seal, witness_id, state, bundle_id, opid, ty, no as u16,
-                    ),
-                    None => OutputAssignment::with_no_witness(
-                        seal, state, bundle_id, opid, ty, no as u16,
-                    ),
-                };
-                contract_state
-                    .push(assigned_state)
-                    .expect("contract state exceeded 2^32 items, which is unrealistic");
-            }
-        }
-
+        mut observer: Option<&mut (dyn FnMut(StateChange) + '_)>,
+    ) -> Result<(), MemError> {
+        let contract_id = self.contract_id;
         for (ty, assignments) in assignments.iter() {
+            let observer = observer.as_deref_mut();
+            let origin = AssignmentsOrigin {
+                contract_id,
+                bundle_id,
+                witness_id,
+                opid,
+                ty: *ty,
+            };
             match assignments {
                 TypedAssigns::Declarative(assignments) => {
-                    process(&mut self.rights, assignments, bundle_id, opid, *ty, witness_id)
+                    origin.push_to(&mut self.rights, assignments, observer)?
                 }
                 TypedAssigns::Fungible(assignments) => {
-                    process(&mut self.fungibles, assignments, bundle_id, opid, *ty, witness_id)
+                    origin.push_to(&mut self.fungibles, assignments, observer)?
                 }
                 TypedAssigns::Structured(assignments) => {
-                    process(&mut self.data, assignments, bundle_id, opid, *ty, witness_id)
+                    for (no, assign) in assignments.iter().enumerate() {
+                        let opout = Opout::new(opid, *ty, no as u16);
+                        match assign {
+                            Assign::ConfidentialSeal { seal, .. } => {
+                                if self.has_revealed_data(opout) {
+                                    continue;
+                                }
+                                let concealed = ConcealedData {
+                                    seal: *seal,
+                                    witness: witness_id,
+                                    bundle_id,
+                                };
+                                self.concealed_data
+                                    .insert(opout, concealed)
+                                    .map_err(|_| MemError::StateOverflow(contract_id))?;
+                            }
+                            Assign::Revealed { .. } => {
+                                self.concealed_data
+                                    .remove(&opout)
+                                    .expect("confined map without lower bound");
+                            }
+                        }
+                    }
+                    origin.push_to(&mut self.data, assignments, observer)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Context of the assignments of a single type created by an operation, which are added to the
+/// contract state by [`MemContractState::add_assignments`].
+#[derive(Copy, Clone)]
+struct AssignmentsOrigin {
+    contract_id: ContractId,
+    bundle_id: Option<BundleId>,
+    witness_id: Option<Txid>,
+    opid: OpId,
+    ty: AssignmentType,
+}
+
+impl AssignmentsOrigin {
+    /// Adds the revealed assignments to the contract state collection, failing with
+    /// [`MemError::StateOverflow`] once the collection can't hold more items.
+    fn push_to<State: ExposedState + KnownState, Seal: ExposedSeal, const MAX: usize>(
+        self,
+        contract_state: &mut Confined<BTreeSet<OutputAssignment<State>>, 0, MAX>,
+        assignments: &[Assign<State, Seal>],
+        mut observer: Option<&mut (dyn FnMut(StateChange) + '_)>,
+    ) -> Result<(), MemError>
+    where
+        StateChange: From<OutputAssignment<State>>,
+    {
+        let Self {
+            contract_id,
+            bundle_id,
+            witness_id,
+            opid,
+            ty,
+        } = self;
+        for (no, seal, state) in assignments
+            .iter()
+            .enumerate()
+            .filter_map(|(n, a)| a.to_revealed().map(|(seal, state)| (n, seal, state)))
+        {
+            let assigned_state = match witness_id {
+                Some(witness_id) => OutputAssignment::with_witness(
+                    seal, witness_id, state, bundle_id, opid, ty, no as u16,
+                ),
+                None => {
+                    OutputAssignment::with_no_witness(seal, state, bundle_id, opid, ty, no as u16)
                 }
+            };
+            contract_state
+                .push(assigned_state.clone())
+                .map_err(|_| MemError::StateOverflow(contract_id))?;
+            if let Some(observer) = observer.as_deref_mut() {
+                observer(assigned_state.into());
             }
         }
+        Ok(())
     }
 }
 
+/// Change to the contract state made by an operation added via [`MemContractWriter`], which is
+/// reported to the writer observer.
+#[derive(Clone, Debug, From)]
+pub enum StateChange {
+    /// New global state item.
+    Global(GlobalOut, RevealedData),
+
+    /// New rights allocation.
+    #[from]
+    Rights(OutputAssignment<VoidState>),
+
+    /// New fungible allocation.
+    #[from]
+    Fungible(OutputAssignment<RevealedValue>),
+
+    /// New structured data allocation.
+    #[from]
+    Data(OutputAssignment<RevealedData>),
+}
+
 pub struct MemContract<M: Borrow<MemContractState> = MemContractState> {
     filter: HashMap<Txid, WitnessOrd>,
     invalid_bundles: BTreeSet<BundleId>,
@@ -628,6 +1574,114 @@ impl<M: Borrow<MemContractState>> MemContract<M> {
             unfiltered,
         }
     }
+
+    /// Returns up to `limit` global state items of type `ty`, skipping the first `offset` ones.
+    ///
+    /// The items are sorted and subject to the same schema-defined limit as in
+    /// [`ContractStateAccess::global`], so the pages split the same sequence of items returned by
+    /// it. Unlike [`ContractStateAccess::global`], only the items of the requested page are
+    /// cloned.
+    pub fn global_paged(
+        &self,
+        ty: GlobalStateType,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<(GlobalOrd, RevealedData)>, UnknownGlobalStateType> {
+        let state = self.global_state(ty)?;
+        let mut items = self
+            .global_items(state)
+            .take(state.limit.to_usize())
+            .collect::<Vec<_>>();
+        items.sort_by(|a, b| b.cmp(a));
+        Ok(items
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(ord, data)| (ord, data.to_owned()))
+            .collect())
+    }
+
+    /// Returns all the known global state items of type `ty`, ignoring the schema-defined limit
+    /// applied by [`ContractStateAccess::global`].
+    ///
+    /// The items are returned in the same order as by [`ContractStateAccess::global`], i.e. from
+    /// the most recent one in the consensus ordering. Since the whole global state history is
+    /// collected, the result may be very large: this is meant for audit and export purposes, use
+    /// [`ContractStateAccess::global`] or [`MemContract::global_paged`] for displaying the state.
+    pub fn global_full(
+        &self,
+        ty: GlobalStateType,
+    ) -> Result<impl Iterator<Item = (GlobalOrd, &RevealedData)>, UnknownGlobalStateType> {
+        let state = self.global_state(ty)?;
+        let mut items = self.global_items(state).collect::<Vec<_>>();
+        items.sort_by(|(a, _), (b, _)| b.cmp(a));
+        Ok(items.into_iter())
+    }
+
+    /// Returns all the valid allocations of the contract sitting on the `outpoint`, across all
+    /// the assignment types.
+    ///
+    /// This is equivalent to calling [`ContractStateAccess::rights`],
+    /// [`ContractStateAccess::fungible`] and [`ContractStateAccess::data`] for each assignment
+    /// type, but traverses each of the rights, fungible and data allocation lists only once,
+    /// instead of once per assignment type.
+    pub fn outpoint_summary(&self, outpoint: Outpoint) -> OutpointSummary {
+        let mut summary = OutpointSummary::default();
+        for assignment in self
+            .rights_all()
+            .filter(|assignment| assignment.seal.to_outpoint() == outpoint)
+        {
+            *summary.rights.entry(assignment.opout.ty).or_default() += 1;
+        }
+        for assignment in self
+            .fungible_all()
+            .filter(|assignment| assignment.seal.to_outpoint() == outpoint)
+        {
+            let total = summary.fungible.entry(assignment.opout.ty).or_default();
+            *total = total.saturating_add(FungibleState::from(assignment.state).as_u64());
+        }
+        for assignment in self
+            .data_all()
+            .filter(|assignment| assignment.seal.to_outpoint() == outpoint)
+        {
+            summary
+                .data
+                .entry(assignment.opout.ty)
+                .or_default()
+                .push(assignment.state.clone());
+        }
+        summary
+    }
+
+    fn global_state(&self, ty: GlobalStateType) -> Result<&MemGlobalState, UnknownGlobalStateType> {
+        self.unfiltered
+            .borrow()
+            .global
+            .get(&ty)
+            .ok_or(UnknownGlobalStateType(ty))
+    }
+
+    fn global_items<'a>(
+        &'a self,
+        state: &'a MemGlobalState,
+    ) -> impl Iterator<Item = (GlobalOrd, &'a RevealedData)> + 'a {
+        state
+            .known
+            .as_unconfined()
+            .iter()
+            .rev()
+            .filter_map(|(out, data)| {
+                let ord = match out.op_witness {
+                    OpWitness::Genesis => GlobalOrd::genesis(out.index),
+                    OpWitness::Transition(id, ty) => {
+                        // skip globals for which we don't have a WitnessOrd
+                        let ord = self.filter.get(&id)?;
+                        GlobalOrd::transition(out.opid, out.index, ty, out.nonce, *ord)
+                    }
+                };
+                Some((ord, data))
+            })
+    }
 }
 
 impl<M: Borrow<MemContractState>> Debug for MemContract<M> {
@@ -669,10 +1723,19 @@ impl Iterator for MemGlobalStateAccess {
         self.values.get(self.last_idx?.into_usize()).map(Rc::clone)
     }
 
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let consumed = self.last_idx.map_or(0, |idx| idx.into_usize() + 1);
+        let remaining = self.values.len().saturating_sub(consumed);
+        (remaining, Some(remaining))
+    }
+
     #[inline]
-    fn count(self) -> usize { self.values.len() }
+    fn count(self) -> usize { self.len() }
 }
 
+// The global state items are collected on construction, so their number is known upfront
+impl ExactSizeIterator for MemGlobalStateAccess {}
+
 impl GlobalsIter for MemGlobalStateAccess {
     fn at_depth(&self, depth: usize) -> Option<Self::Item> {
         let depth = u24::try_from(depth as u32).ok()?;
@@ -687,28 +1750,10 @@ impl<M: Borrow<MemContractState>> ContractStateAccess for MemContract<M> {
         ty: GlobalStateType,
     ) -> Result<impl GlobalsIter<Item = impl Borrow<GlobalStateEntry>>, UnknownGlobalStateType>
     {
-        let state = self
-            .unfiltered
-            .borrow()
-            .global
-            .get(&ty)
-            .ok_or(UnknownGlobalStateType(ty))?;
-        let items = state
-            .known
-            .as_unconfined()
-            .iter()
-            .rev()
-            .filter_map(|(out, data)| {
-                let ord = match out.op_witness {
-                    OpWitness::Genesis => GlobalOrd::genesis(out.index),
-                    OpWitness::Transition(id, ty) => {
-                        // skip globals for which we don't have a WitnessOrd
-                        let ord = self.filter.get(&id)?;
-                        GlobalOrd::transition(out.opid, out.index, ty, out.nonce, *ord)
-                    }
-                };
-                Some((ord, data.to_owned()))
-            });
+        let state = self.global_state(ty)?;
+        let items = self
+            .global_items(state)
+            .map(|(ord, data)| (ord, data.to_owned()));
         Ok(MemGlobalStateAccess::new(items, state.limit))
     }
 
@@ -785,6 +1830,7 @@ impl ContractStateEvolve for MemContract<MemContractState> {
                     },
                 ),
                 contract: &mut me.unfiltered,
+                observer: None,
             }
         }
         match op {
@@ -813,6 +1859,7 @@ impl<M: Borrow<MemContractState>> ContractStateRead for MemContract<M> {
         self.filter.get(&witness_id).copied()
     }
 
+    // The allocations are kept in ordered sets, so the `_all` iterators yield them sorted
     #[inline]
     fn rights_all(&self) -> impl Iterator<Item = &OutputAssignment<VoidState>> {
         self.unfiltered
@@ -842,11 +1889,58 @@ impl<M: Borrow<MemContractState>> ContractStateRead for MemContract<M> {
             .filter(|assignment| assignment.check_witness(&self.filter))
             .filter(|assignment| assignment.check_bundle(&self.invalid_bundles))
     }
+
+    fn data_concealed(&self, ty: AssignmentType) -> impl Iterator<Item = (SecretSeal, Opout)> {
+        self.unfiltered
+            .borrow()
+            .concealed_data
+            .iter()
+            .filter(move |(opout, _)| opout.ty == ty)
+            .filter(|(_, concealed)| match concealed.witness {
+                None => true,
+                Some(witness_id) => {
+                    !matches!(self.filter.get(&witness_id), None | Some(WitnessOrd::Archived))
+                }
+            })
+            .filter(|(_, concealed)| {
+                concealed
+                    .bundle_id
+                    .is_none_or(|bundle_id| !self.invalid_bundles.contains(&bundle_id))
+            })
+            .map(|(opout, concealed)| (concealed.seal, *opout))
+    }
+
+    fn global_latest(
+        &self,
+        ty: GlobalStateType,
+    ) -> Result<Option<&RevealedData>, UnknownGlobalStateType> {
+        let state = self.global_state(ty)?;
+        // same items as in `ContractStateAccess::global`, which are then sorted
+        Ok(self
+            .global_items(state)
+            .take(state.limit.to_usize())
+            .max()
+            .map(|(_, data)| data))
+    }
 }
 
 pub struct MemContractWriter<'mem> {
     writer: Box<dyn FnMut(Txid, WitnessOrd) -> Result<(), confinement::Error> + 'mem>,
     contract: &'mem mut MemContractState,
+    observer: Option<Box<dyn FnMut(StateChange) + 'mem>>,
+}
+
+impl<'mem> MemContractWriter<'mem> {
+    /// Sets an observer which will be called with each [`StateChange`] made by the operations
+    /// added with this writer.
+    ///
+    /// The genesis is added by [`StateWriteProvider::register_contract`] before the observer can
+    /// be set, so its allocations are not reported: use [`MemState::register_contract_observed`]
+    /// to observe them too.
+    pub fn with_observer(mut self, observer: impl FnMut(StateChange) + 'mem) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
 }
 
 impl ContractStateWrite for MemContractWriter<'_> {
@@ -857,8 +1951,8 @@ impl ContractStateWrite for MemContractWriter<'_> {
     /// If genesis violates RGB consensus rules and wasn't checked against the
     /// schema before adding to the history.
     fn add_genesis(&mut self, genesis: &Genesis) -> Result<(), Self::Error> {
-        self.contract.add_operation(OrdOpRef::Genesis(genesis));
-        Ok(())
+        self.contract
+            .add_operation_observed(OrdOpRef::Genesis(genesis), self.observer.as_deref_mut())
     }
 
     /// # Panics
@@ -873,9 +1967,10 @@ impl ContractStateWrite for MemContractWriter<'_> {
         bundle_id: BundleId,
     ) -> Result<(), Self::Error> {
         (self.writer)(witness_id, ord)?;
-        self.contract
-            .add_operation(OrdOpRef::Transition(transition, witness_id, ord, bundle_id));
-        Ok(())
+        self.contract.add_operation_observed(
+            OrdOpRef::Transition(transition, witness_id, ord, bundle_id),
+            self.observer.as_deref_mut(),
+        )
     }
 }
 
@@ -915,8 +2010,13 @@ pub struct MemIndex {
     #[strict_type(skip)]
     persistence: Option<Persistence<Self>>,
 
+    #[getter(skip)]
+    #[strict_type(skip)]
+    transaction: MemTransaction<Self>,
+
     op_bundle_children_index: LargeOrdMap<OpId, SmallOrdSet<BundleId>>,
     op_bundle_index: LargeOrdMap<OpId, BundleId>,
+    opout_spenders_index: LargeOrdMap<Opout, SmallOrdSet<OpId>>,
     bundle_contract_index: LargeOrdMap<BundleId, ContractId>,
     bundle_witness_index: LargeOrdMap<BundleId, LargeOrdSet<Txid>>,
     contract_index: SmallOrdMap<ContractId, ContractIndex>,
@@ -930,8 +2030,10 @@ impl MemIndex {
     pub fn in_memory() -> Self {
         Self {
             persistence: None,
+            transaction: default!(),
             op_bundle_children_index: empty!(),
             op_bundle_index: empty!(),
+            opout_spenders_index: empty!(),
             bundle_contract_index: empty!(),
             bundle_witness_index: empty!(),
             contract_index: empty!(),
@@ -944,8 +2046,10 @@ impl CloneNoPersistence for MemIndex {
     fn clone_no_persistence(&self) -> Self {
         Self {
             persistence: None,
+            transaction: default!(),
             op_bundle_children_index: self.op_bundle_children_index.clone(),
             op_bundle_index: self.op_bundle_index.clone(),
+            opout_spenders_index: self.opout_spenders_index.clone(),
             bundle_contract_index: self.bundle_contract_index.clone(),
             bundle_witness_index: self.bundle_witness_index.clone(),
             contract_index: self.contract_index.clone(),
@@ -954,6 +2058,14 @@ impl CloneNoPersistence for MemIndex {
     }
 }
 
+impl MemIndex {
+    fn snapshot(&mut self) {
+        if self.transaction.needs_snapshot() {
+            self.transaction.snapshot = Some(Box::new(self.clone_no_persistence()));
+        }
+    }
+}
+
 impl Persisting for MemIndex {
     #[inline]
     fn persistence(&self) -> Option<&Persistence<Self>> { self.persistence.as_ref() }
@@ -967,13 +2079,23 @@ impl StoreTransaction for MemIndex {
     type TransactionErr = MemError;
     #[inline]
     fn begin_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.transaction.begin();
         self.mark_dirty();
         Ok(())
     }
     #[inline]
-    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> { Ok(self.store()?) }
+    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.transaction.end();
+        Ok(self.store()?)
+    }
     #[inline]
-    fn rollback_transaction(&mut self) { unreachable!() }
+    fn rollback_transaction(&mut self) {
+        if let Some(snapshot) = self.transaction.end() {
+            let persistence = self.persistence.take();
+            *self = *snapshot;
+            self.persistence = persistence;
+        }
+    }
 }
 
 impl IndexProvider for MemIndex {}
@@ -1003,6 +2125,25 @@ impl IndexReadProvider for MemIndex {
             }))
     }
 
+    fn allocations_at(
+        &self,
+        outpoints: BTreeSet<Outpoint>,
+    ) -> Result<BTreeMap<Outpoint, BTreeSet<(ContractId, Opout)>>, Self::Error> {
+        let mut allocations = BTreeMap::<Outpoint, BTreeSet<_>>::new();
+        for (contract_id, index) in &self.contract_index {
+            for (seal, opouts) in &index.outpoint_opouts {
+                let outpoint = seal.to_outpoint();
+                if outpoints.contains(&outpoint) {
+                    allocations
+                        .entry(outpoint)
+                        .or_default()
+                        .extend(opouts.iter().map(|opout| (*contract_id, *opout)));
+                }
+            }
+        }
+        Ok(allocations)
+    }
+
     fn public_opouts(
         &self,
         contract_id: ContractId,
@@ -1036,6 +2177,17 @@ impl IndexReadProvider for MemIndex {
         Ok(opouts)
     }
 
+    fn outpoints_for_contract(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<BTreeSet<OutputSeal>, IndexReadError<Self::Error>> {
+        let index = self
+            .contract_index
+            .get(&contract_id)
+            .ok_or(IndexInconsistency::ContractAbsent(contract_id))?;
+        Ok(index.outpoint_opouts.keys().copied().collect())
+    }
+
     fn opouts_by_terminals(
         &self,
         terminals: impl IntoIterator<Item = SecretSeal>,
@@ -1067,6 +2219,39 @@ impl IndexReadProvider for MemIndex {
             .cloned()
     }
 
+    fn descendant_bundles(&self, opid: OpId) -> Result<Vec<BundleId>, IndexReadError<Self::Error>> {
+        let mut bundle_ops = BTreeMap::<BundleId, Vec<OpId>>::new();
+        for (opid, bundle_id) in &self.op_bundle_index {
+            bundle_ops.entry(*bundle_id).or_default().push(*opid);
+        }
+
+        let mut descendants = vec![];
+        let mut visited = BTreeSet::new();
+        let mut queue = VecDeque::from([opid]);
+        while let Some(opid) = queue.pop_front() {
+            let Some(children) = self.op_bundle_children_index.get(&opid) else {
+                continue;
+            };
+            for bundle_id in children {
+                // a bundle seen before is skipped, protecting from cycles in a corrupted index
+                if !visited.insert(*bundle_id) {
+                    continue;
+                }
+                descendants.push(*bundle_id);
+                queue.extend(bundle_ops.get(bundle_id).into_iter().flatten().copied());
+            }
+        }
+        Ok(descendants)
+    }
+
+    fn spenders_of(&self, opout: Opout) -> Result<BTreeSet<OpId>, Self::Error> {
+        Ok(self
+            .opout_spenders_index
+            .get(&opout)
+            .map(|opids| opids.to_unconfined())
+            .unwrap_or_default())
+    }
+
     fn bundle_info(
         &self,
         bundle_id: BundleId,
@@ -1087,6 +2272,7 @@ impl IndexWriteProvider for MemIndex {
     type Error = MemError;
 
     fn register_contract(&mut self, contract_id: ContractId) -> Result<bool, Self::Error> {
+        self.snapshot();
         if !self.contract_index.contains_key(&contract_id) {
             self.contract_index.insert(contract_id, empty!())?;
             Ok(true)
@@ -1101,6 +2287,7 @@ impl IndexWriteProvider for MemIndex {
         witness_id: Txid,
         contract_id: ContractId,
     ) -> Result<bool, IndexWriteError<Self::Error>> {
+        self.snapshot();
         if let Some(alt) = self
             .bundle_contract_index
             .get(&bundle_id)
@@ -1113,6 +2300,7 @@ impl IndexWriteProvider for MemIndex {
             }
             .into());
         }
+        // the witness set is ordered, so registering the same witness again is a no-op
         self.bundle_witness_index
             .entry(bundle_id)?
             .or_default()
@@ -1128,7 +2316,9 @@ impl IndexWriteProvider for MemIndex {
         &mut self,
         opid: OpId,
         bundle_id: BundleId,
+        inputs: impl IntoIterator<Item = Opout>,
     ) -> Result<bool, IndexWriteError<Self::Error>> {
+        self.snapshot();
         if let Some(alt) = self
             .op_bundle_index
             .get(&opid)
@@ -1141,15 +2331,26 @@ impl IndexWriteProvider for MemIndex {
             }
             .into());
         }
-        let present = self.op_bundle_index.insert(opid, bundle_id)?.is_some();
-        Ok(!present)
-    }
+        for opout in inputs {
+            match self.opout_spenders_index.get_mut(&opout) {
+                Some(opids) => {
+                    opids.push(opid)?;
+                }
+                None => {
+                    self.opout_spenders_index.insert(opout, small_bset!(opid))?;
+                }
+            }
+        }
+        let present = self.op_bundle_index.insert(opid, bundle_id)?.is_some();
+        Ok(!present)
+    }
 
     fn register_spending(
         &mut self,
         opid: OpId,
         bundle_id: BundleId,
     ) -> Result<bool, IndexWriteError<Self::Error>> {
+        self.snapshot();
         let mut present = false;
         match self.op_bundle_children_index.get_mut(&opid) {
             Some(opids) => {
@@ -1171,6 +2372,7 @@ impl IndexWriteProvider for MemIndex {
         opid: OpId,
         type_id: AssignmentType,
     ) -> Result<(), IndexWriteError<Self::Error>> {
+        self.snapshot();
         let index = self
             .contract_index
             .get_mut(&contract_id)
@@ -1205,6 +2407,7 @@ impl IndexWriteProvider for MemIndex {
         type_id: AssignmentType,
         witness_id: Txid,
     ) -> Result<(), IndexWriteError<Self::Error>> {
+        self.snapshot();
         let index = self
             .contract_index
             .get_mut(&contract_id)
@@ -1228,6 +2431,72 @@ impl IndexWriteProvider for MemIndex {
         // We need two cycles due to the borrow checker
         self.extend_terminals(vec, opid, type_id)
     }
+
+    fn forget_contract(&mut self, contract_id: ContractId) -> Result<(), Self::Error> {
+        self.snapshot();
+        self.contract_index.remove(&contract_id)?;
+
+        let bundle_ids = self
+            .bundle_contract_index
+            .iter()
+            .filter(|(_, id)| **id == contract_id)
+            .map(|(bundle_id, _)| *bundle_id)
+            .collect::<BTreeSet<_>>();
+        for bundle_id in &bundle_ids {
+            self.bundle_contract_index.remove(bundle_id)?;
+            self.bundle_witness_index.remove(bundle_id)?;
+        }
+
+        let mut opids = self
+            .op_bundle_index
+            .iter()
+            .filter(|(_, bundle_id)| bundle_ids.contains(*bundle_id))
+            .map(|(opid, _)| *opid)
+            .collect::<BTreeSet<_>>();
+        for opid in &opids {
+            self.op_bundle_index.remove(opid)?;
+        }
+        opids.insert(OpId::from_inner(contract_id.into_inner()));
+        for opid in &opids {
+            self.op_bundle_children_index.remove(opid)?;
+        }
+
+        let opouts = self
+            .opout_spenders_index
+            .keys()
+            .filter(|opout| opids.contains(&opout.op))
+            .copied()
+            .collect::<Vec<_>>();
+        for opout in &opouts {
+            self.opout_spenders_index.remove(opout)?;
+        }
+
+        let terminals = self
+            .terminal_index
+            .iter()
+            .filter(|(_, opouts)| opouts.iter().any(|opout| opids.contains(&opout.op)))
+            .map(|(seal, _)| *seal)
+            .collect::<Vec<_>>();
+        for seal in terminals {
+            let opouts = self
+                .terminal_index
+                .get_mut(&seal)
+                .expect("terminal is present in the index");
+            let removed = opouts
+                .iter()
+                .filter(|opout| opids.contains(&opout.op))
+                .copied()
+                .collect::<Vec<_>>();
+            for opout in &removed {
+                opouts.remove(opout)?;
+            }
+            if opouts.is_empty() {
+                self.terminal_index.remove(&seal)?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl MemIndex {
@@ -1267,3 +2536,2131 @@ impl MemIndex {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU32;
+    use std::str::FromStr;
+
+    use amplify::confinement::{LargeVec, NonEmptyOrdMap, NonEmptyOrdSet, NonEmptyVec, U24, U32};
+    use amplify::hex::FromHex;
+    use amplify::ByteArray;
+    use rgb::bitcoin::absolute::LockTime;
+    use rgb::bitcoin::transaction::Version;
+    use rgb::bitcoin::Transaction as Tx;
+    use rgb::commit_verify::mpc::{MerkleBlock, MerkleTree, Message, MultiSource, ProtocolId};
+    use rgb::commit_verify::TryCommitVerify;
+    use rgb::dbc::opret::OpretProof;
+    use rgb::validation::OpRef;
+    use rgb::vm::WitnessPos;
+    use rgb::{
+        AssignData, AssignFungible, ChainNet, GlobalDetails, GlobalStateSchema, KnownTransition,
+        TransitionType,
+    };
+    use strict_encoding::FieldName;
+
+    use super::*;
+    use crate::containers::{Consignment, PubWitness, WitnessBundle};
+    use crate::contract::AllocatedState;
+    use crate::indexers::mock::MockResolver;
+    use crate::indexers::OfflineResolver;
+    use crate::persistence::{
+        height_sorted, Stash, StashDataError, StashError, State, ValidationProgress,
+    };
+    use crate::stl::StandardTypes;
+
+    fn schema() -> Schema {
+        Schema {
+            ffv: Default::default(),
+            name: strict_encoding::TypeName::from_str("Name").unwrap(),
+            meta_types: Default::default(),
+            global_types: Default::default(),
+            owned_types: Default::default(),
+            genesis: Default::default(),
+            transitions: Default::default(),
+            default_assignment: Default::default(),
+        }
+    }
+
+    fn genesis(schema: &Schema) -> Genesis {
+        Genesis {
+            ffv: Default::default(),
+            schema_id: schema.schema_id(),
+            timestamp: Default::default(),
+            issuer: Default::default(),
+            chain_net: Default::default(),
+            seal_closing_strategy: Default::default(),
+            metadata: Default::default(),
+            globals: Default::default(),
+            assignments: Default::default(),
+        }
+    }
+
+    #[test]
+    fn register_schemata() {
+        let schema = schema();
+        let schema_id = schema.schema_id();
+        let mut conflicting = schema.clone();
+        conflicting.name = strict_encoding::TypeName::from_str("Conflicting").unwrap();
+        let sem_id = SemId::from_hex(&"01".repeat(32)).unwrap();
+        conflicting
+            .global_types
+            .insert(GlobalStateType::with(0), GlobalDetails {
+                global_state_schema: GlobalStateSchema::once(sem_id),
+                name: FieldName::from_str("data").unwrap(),
+            })
+            .unwrap();
+        let conflicting_id = conflicting.schema_id();
+
+        let mut stash = MemStash::in_memory();
+        let err = stash
+            .register_schemata(none!(), [schema.clone(), conflicting])
+            .unwrap_err();
+        let MemError::SchemaTypeMissing {
+            schema_id: failed_id,
+            sem_id: missing,
+        } = err
+        else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!((failed_id, missing), (conflicting_id, sem_id));
+        assert!(!stash.contains_schema(schema_id).unwrap());
+        assert!(!stash.contains_schema(conflicting_id).unwrap());
+
+        assert_eq!(stash.register_schemata(none!(), [schema.clone()]).unwrap(), 1);
+        assert_eq!(stash.register_schemata(none!(), [schema]).unwrap(), 0);
+        assert!(stash.contains_schema(schema_id).unwrap());
+    }
+
+    #[test]
+    fn register_schema_checked() {
+        use rgb::stl::rgb_contract_id_stl;
+
+        let standard_types = StandardTypes::with(rgb_contract_id_stl());
+        let sem_id = standard_types.get("RGBCommit.ContractId");
+        let mut schema = schema();
+        schema
+            .global_types
+            .insert(GlobalStateType::with(0), GlobalDetails {
+                global_state_schema: GlobalStateSchema::once(sem_id),
+                name: FieldName::from_str("contractId").unwrap(),
+            })
+            .unwrap();
+        let schema_id = schema.schema_id();
+
+        let mut stash = Stash::new(MemStash::in_memory());
+        let err = stash.register_schema(schema.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            StashError::Data(StashDataError::MissingTypes(ref types)) if types == &vec![sem_id]
+        ));
+        assert!(!stash.as_provider().contains_schema(schema_id).unwrap());
+
+        stash
+            .as_provider_mut()
+            .consume_types(standard_types.type_system(schema.clone()))
+            .unwrap();
+        assert!(stash.register_schema(schema.clone()).unwrap());
+        assert!(!stash.register_schema(schema).unwrap());
+        assert!(stash.as_provider().contains_schema(schema_id).unwrap());
+    }
+
+    #[test]
+    fn stash_rollback() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let mut stash = MemStash::in_memory();
+
+        stash.begin_transaction().unwrap();
+        stash.replace_schema(schema).unwrap();
+        stash.replace_genesis(genesis).unwrap();
+        assert!(stash.geneses.contains_key(&contract_id));
+        stash.rollback_transaction();
+
+        assert!(stash.geneses.is_empty());
+        assert!(stash.schemata.is_empty());
+    }
+
+    #[test]
+    fn state_rollback() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let mut state = MemState::in_memory();
+
+        state.begin_transaction().unwrap();
+        state.register_contract(&schema, &genesis).unwrap();
+        assert!(state.contracts.contains_key(&contract_id));
+        state.rollback_transaction();
+
+        assert!(!state.contracts.contains_key(&contract_id));
+        assert!(state.witnesses.is_empty());
+    }
+
+    #[test]
+    fn invalid_bundle_reason() {
+        let bundle_id = BundleId::from_byte_array([1; 32]);
+        let mut state = MemState::in_memory();
+        assert_eq!(state.bundle_invalid_reason(bundle_id), None);
+
+        state
+            .update_bundle(bundle_id, Some(InvalidReason::ManuallyDisabled))
+            .unwrap();
+        assert_eq!(state.bundle_invalid_reason(bundle_id), Some(InvalidReason::ManuallyDisabled));
+
+        let data = state.to_strict_serialized::<U32>().unwrap();
+        let decoded = MemState::from_strict_serialized::<U32>(data).unwrap();
+        assert_eq!(decoded.bundle_invalid_reason(bundle_id), Some(InvalidReason::ManuallyDisabled));
+        assert!(decoded.invalid_bundles().contains(&bundle_id));
+
+        state
+            .update_bundle(bundle_id, Some(InvalidReason::AncestorInvalid))
+            .unwrap();
+        assert_eq!(state.bundle_invalid_reason(bundle_id), Some(InvalidReason::AncestorInvalid));
+        state.update_bundle(bundle_id, None).unwrap();
+        assert_eq!(state.bundle_invalid_reason(bundle_id), None);
+        assert!(state.invalid_bundles().is_empty());
+    }
+
+    #[test]
+    fn chain_mismatch() {
+        let schema = schema();
+        let mut genesis = genesis(&schema);
+        genesis.chain_net = ChainNet::BitcoinTestnet4;
+        let contract_id = genesis.contract_id();
+
+        let mut state = MemState::in_memory().with_chain_net(ChainNet::BitcoinMainnet);
+        assert!(matches!(
+            state.register_contract(&schema, &genesis),
+            Err(MemError::ChainMismatch {
+                contract_id: id,
+                expected: ChainNet::BitcoinMainnet,
+                actual: ChainNet::BitcoinTestnet4,
+            }) if id == contract_id
+        ));
+        assert!(!state.contract_exists(contract_id));
+
+        state.set_chain_net(ChainNet::BitcoinTestnet4);
+        state.register_contract(&schema, &genesis).unwrap();
+        assert!(state.contract_exists(contract_id));
+        assert_eq!(state.clone_no_persistence().chain_net(), Some(ChainNet::BitcoinTestnet4));
+    }
+
+    #[test]
+    fn witnesses_with_status() {
+        let mined = Txid::from_str(&"01".repeat(32)).unwrap();
+        let tentative = Txid::from_str(&"02".repeat(32)).unwrap();
+        let archived = Txid::from_str(&"03".repeat(32)).unwrap();
+        let mut state = MemState::in_memory();
+        let pos = WitnessPos::bitcoin(NonZeroU32::new(100).unwrap(), 1_700_000_000).unwrap();
+        state
+            .witnesses
+            .insert(mined, WitnessOrd::Mined(pos))
+            .unwrap();
+        state
+            .witnesses
+            .insert(tentative, WitnessOrd::Tentative)
+            .unwrap();
+        state
+            .witnesses
+            .insert(archived, WitnessOrd::Archived)
+            .unwrap();
+
+        let tentatives = state.tentative_witnesses();
+        assert_eq!(tentatives.len(), 1);
+        assert_eq!(tentatives.get(&tentative), Some(&WitnessOrd::Tentative));
+
+        let mined_only = state.witnesses_with_status(|ord| matches!(ord, WitnessOrd::Mined(_)));
+        assert_eq!(mined_only.keys().collect::<Vec<_>>(), vec![&mined]);
+        assert_eq!(state.witnesses_with_status(|_| true), state.witnesses());
+        assert!(state
+            .witnesses_with_status(|ord| *ord == WitnessOrd::Ignored)
+            .is_empty());
+    }
+
+    #[test]
+    fn state_clone_full() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let bundle_id = BundleId::from_byte_array([1; 32]);
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let mut state = MemState::in_memory();
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 1, &[(0, 100)]),
+                witness_id,
+                WitnessOrd::Tentative,
+                bundle_id,
+            )
+            .unwrap();
+        drop(writer);
+        state
+            .update_bundle(bundle_id, Some(InvalidReason::ManuallyDisabled))
+            .unwrap();
+        assert_eq!(
+            state
+                .contract_state(contract_id)
+                .unwrap()
+                .fungible_all()
+                .count(),
+            0
+        );
+
+        let clone = state.clone_full();
+        assert_eq!(clone.invalid_bundles, state.invalid_bundles);
+        assert_eq!(clone.witnesses, state.witnesses);
+        assert_eq!(clone.contracts, state.contracts);
+        assert_eq!(
+            clone
+                .contract_state(contract_id)
+                .unwrap()
+                .fungible_all()
+                .count(),
+            0
+        );
+
+        // the persistence-resetting clone loses the invalid bundles
+        let clone = state.clone_no_persistence();
+        assert_eq!(
+            clone
+                .contract_state(contract_id)
+                .unwrap()
+                .fungible_all()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn state_commit() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let mut state = MemState::in_memory();
+
+        state.begin_transaction().unwrap();
+        state.register_contract(&schema, &genesis).unwrap();
+        state.commit_transaction().unwrap();
+        state.rollback_transaction();
+
+        assert!(state.contracts.contains_key(&contract_id));
+    }
+
+    #[test]
+    fn index_rollback() {
+        let contract_id = genesis(&schema()).contract_id();
+        let mut index = MemIndex::in_memory();
+
+        index.begin_transaction().unwrap();
+        index.register_contract(contract_id).unwrap();
+        index.rollback_transaction();
+
+        assert!(index.contract_index.is_empty());
+    }
+
+    #[test]
+    fn bundle_multiple_witnesses() {
+        let contract_id = genesis(&schema()).contract_id();
+        let bundle_id = BundleId::from_byte_array([1; 32]);
+        let witness1 = Txid::from_str(&"02".repeat(32)).unwrap();
+        let witness2 = Txid::from_str(&"01".repeat(32)).unwrap();
+        let mut index = MemIndex::in_memory();
+
+        index.register_contract(contract_id).unwrap();
+        index
+            .register_bundle(bundle_id, witness1, contract_id)
+            .unwrap();
+        index
+            .register_bundle(bundle_id, witness2, contract_id)
+            .unwrap();
+        index
+            .register_bundle(bundle_id, witness1, contract_id)
+            .unwrap();
+
+        let (witnesses, bundle_contract) = index.bundle_info(bundle_id).unwrap();
+        assert_eq!(bundle_contract, contract_id);
+        let witnesses = witnesses.collect::<Vec<_>>();
+        let mut expected = vec![witness1, witness2];
+        expected.sort();
+        assert_eq!(witnesses, expected);
+
+        let mut state = MemState::in_memory();
+        state
+            .witnesses
+            .insert(witness1, WitnessOrd::Tentative)
+            .unwrap();
+        state
+            .witnesses
+            .insert(witness2, WitnessOrd::Archived)
+            .unwrap();
+        let state = State::new(state);
+        let by_ord = state.witnesses_by_ord(&witnesses).unwrap();
+        assert_eq!(by_ord, vec![
+            (witness1, WitnessOrd::Tentative),
+            (witness2, WitnessOrd::Archived)
+        ]);
+    }
+
+    fn fungible_assigns(
+        assigns: impl IntoIterator<Item = AssignFungible<GraphSeal>>,
+    ) -> TypedAssigns<GraphSeal> {
+        let mut typed = TypedAssigns::Fungible(strict_dumb!());
+        *typed.as_fungible_mut().unwrap() = NonEmptyVec::from_iter_checked(assigns);
+        typed
+    }
+
+    fn data_assigns(
+        assigns: impl IntoIterator<Item = AssignData<GraphSeal>>,
+    ) -> TypedAssigns<GraphSeal> {
+        let mut typed = TypedAssigns::Structured(strict_dumb!());
+        *typed.as_structured_mut().unwrap() = NonEmptyVec::from_iter_checked(assigns);
+        typed
+    }
+
+    fn transition(contract_id: ContractId, nonce: u64, allocations: &[(u32, u64)]) -> Transition {
+        let ty = AssignmentType::with(0);
+        let assigns = allocations
+            .iter()
+            .map(|(vout, amount)| {
+                Assign::revealed(
+                    GraphSeal::with_blinded_vout(*vout, nonce),
+                    RevealedValue::new(*amount),
+                )
+            })
+            .collect::<Vec<_>>();
+        let assignments = SmallOrdMap::from_iter_checked([(ty, fungible_assigns(assigns))]);
+        Transition {
+            ffv: none!(),
+            contract_id,
+            nonce,
+            transition_type: TransitionType::with(0),
+            metadata: none!(),
+            globals: none!(),
+            inputs: NonEmptyOrdSet::with(Opout::new(
+                OpId::from_inner(contract_id.into_inner()),
+                ty,
+                nonce as u16,
+            ))
+            .into(),
+            assignments: assignments.into(),
+            signature: none!(),
+        }
+    }
+
+    fn bundle(transition: Transition) -> TransitionBundle {
+        let opid = transition.id();
+        TransitionBundle {
+            input_map: NonEmptyOrdMap::from_iter_checked(
+                transition.inputs.into_iter().map(|opout| (opout, opid)),
+            ),
+            known_transitions: NonEmptyVec::with(KnownTransition::new(opid, transition)),
+        }
+    }
+
+    #[test]
+    fn stash_contract_bundles() {
+        let schema = schema();
+        let genesis1 = genesis(&schema);
+        let mut genesis2 = genesis(&schema);
+        genesis2.timestamp = 1;
+        let mut unknown = genesis(&schema);
+        unknown.timestamp = 2;
+        let contract1 = genesis1.contract_id();
+        let contract2 = genesis2.contract_id();
+        let mut stash = MemStash::in_memory();
+        stash.replace_schema(schema).unwrap();
+        stash.replace_genesis(genesis1).unwrap();
+        stash.replace_genesis(genesis2).unwrap();
+        for (contract_id, nonce) in [(contract1, 1), (contract2, 2), (contract1, 3)] {
+            stash
+                .replace_bundle(bundle(transition(contract_id, nonce, &[(0, 100)])))
+                .unwrap();
+        }
+
+        let bundle_ids = stash
+            .contract_bundles(contract1)
+            .unwrap()
+            .map(TransitionBundle::bundle_id)
+            .collect::<Vec<_>>();
+        assert_eq!(bundle_ids.len(), 2);
+        assert!(bundle_ids.is_sorted());
+        assert_eq!(stash.contract_bundles(contract2).unwrap().count(), 1);
+        assert!(stash.contract_bundles(unknown.contract_id()).is_err());
+    }
+
+    #[test]
+    fn stash_schema_for_contract() {
+        let schema = schema();
+        let genesis1 = genesis(&schema);
+        let other_schema = Schema {
+            name: strict_encoding::TypeName::from_str("Other").unwrap(),
+            ..schema.clone()
+        };
+        let genesis2 = genesis(&other_schema);
+        let mut stash = MemStash::in_memory();
+        stash.replace_schema(schema.clone()).unwrap();
+        stash.replace_genesis(genesis1.clone()).unwrap();
+        stash.replace_genesis(genesis2.clone()).unwrap();
+
+        assert_eq!(stash.schema_for_contract(genesis1.contract_id()).unwrap(), &schema);
+        assert_eq!(stash.contract_schema(genesis1.contract_id()).unwrap(), &schema);
+        assert!(matches!(
+            stash.schema_for_contract(genesis2.contract_id()),
+            Err(StashProviderError::Inconsistency(StashInconsistency::SchemaAbsent(id)))
+                if id == other_schema.schema_id()
+        ));
+        let mut unknown = genesis(&schema);
+        unknown.timestamp = 1;
+        assert!(matches!(
+            stash.schema_for_contract(unknown.contract_id()),
+            Err(StashProviderError::Inconsistency(StashInconsistency::ContractAbsent(id)))
+                if id == unknown.contract_id()
+        ));
+    }
+
+    #[test]
+    fn stash_contains() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let bundle = bundle(transition(contract_id, 1, &[(0, 100)]));
+        let bundle_id = bundle.bundle_id();
+        let mut stash = MemStash::in_memory();
+
+        assert!(!stash.contains_schema(schema.schema_id()).unwrap());
+        assert!(!stash.contains_genesis(contract_id).unwrap());
+        assert!(!stash.contains_bundle(bundle_id).unwrap());
+
+        stash.replace_schema(schema.clone()).unwrap();
+        stash.replace_genesis(genesis).unwrap();
+        stash.replace_bundle(bundle).unwrap();
+
+        assert!(stash.contains_schema(schema.schema_id()).unwrap());
+        assert!(stash.contains_genesis(contract_id).unwrap());
+        assert!(stash.contains_bundle(bundle_id).unwrap());
+
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        assert!(!stash.contains_witness(witness_id).unwrap());
+        stash
+            .replace_witness(SealWitness::new(
+                PubWitness::new(witness_id),
+                strict_dumb!(),
+                DbcProof::Opret(OpretProof::default()),
+            ))
+            .unwrap();
+        assert!(stash.contains_witness(witness_id).unwrap());
+    }
+
+    #[test]
+    fn stash_operation_for_global() {
+        let ty = GlobalStateType::with(0);
+        let schema = schema();
+        let mut genesis = genesis(&schema);
+        genesis
+            .globals
+            .add_state(ty, RevealedData::new(small_vec![1]))
+            .unwrap();
+        let contract_id = genesis.contract_id();
+        let transition = transition(contract_id, 1, &[(0, 100)]);
+        let mut stash = MemStash::in_memory();
+        stash.replace_schema(schema.clone()).unwrap();
+        stash.replace_genesis(genesis.clone()).unwrap();
+        stash.replace_bundle(bundle(transition.clone())).unwrap();
+
+        let mut state = MemContractState::new(&schema, contract_id);
+        state
+            .global
+            .insert(ty, MemGlobalState::new(u24::ONE))
+            .unwrap();
+        state.add_operation(OrdOpRef::Genesis(&genesis)).unwrap();
+        let out = *state.global.get(&ty).unwrap().known.keys().next().unwrap();
+        assert!(out.is_genesis());
+        assert_eq!(out.opid(), genesis.id());
+        assert!(matches!(
+            stash.operation_for_global(&out).unwrap(),
+            OpRef::Genesis(found) if found == &genesis
+        ));
+
+        let out = GlobalOut {
+            index: 0,
+            op_witness: OpWitness::Transition(
+                Txid::from_str(&"01".repeat(32)).unwrap(),
+                transition.transition_type,
+            ),
+            nonce: transition.nonce,
+            opid: transition.id(),
+        };
+        assert!(!out.is_genesis());
+        assert!(matches!(
+            stash.operation_for_global(&out).unwrap(),
+            OpRef::Transition(found) if found == &transition
+        ));
+        let unknown = GlobalOut {
+            opid: OpId::from_byte_array([9; 32]),
+            ..out
+        };
+        assert!(stash.operation_for_global(&unknown).is_err());
+    }
+
+    #[test]
+    fn stash_witnesses_by_dbc() {
+        let mut stash = MemStash::in_memory();
+        let proofs = [
+            DbcProof::Opret(OpretProof::default()),
+            DbcProof::Tapret(strict_dumb!()),
+            DbcProof::Opret(OpretProof::default()),
+        ];
+        for (no, proof) in proofs.into_iter().enumerate() {
+            let txid = Txid::from_str(&format!("{:02x}", no + 1).repeat(32)).unwrap();
+            stash
+                .replace_witness(SealWitness::new(PubWitness::Txid(txid), strict_dumb!(), proof))
+                .unwrap();
+        }
+        let opret = stash
+            .witnesses_by_dbc(|proof| matches!(proof, DbcProof::Opret(_)))
+            .unwrap()
+            .collect::<Vec<_>>();
+        let tapret = stash
+            .witnesses_by_dbc(|proof| matches!(proof, DbcProof::Tapret(_)))
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(opret.len(), 2);
+        assert_eq!(tapret.len(), 1);
+        assert!(matches!(stash.dbc_proof(tapret[0]).unwrap(), Some(DbcProof::Tapret(_))));
+        assert_eq!(
+            stash
+                .taprets()
+                .unwrap()
+                .map(|(txid, _)| txid)
+                .collect::<Vec<_>>(),
+            tapret
+        );
+        let unknown = Txid::from_str(&"ff".repeat(32)).unwrap();
+        assert_eq!(stash.dbc_proof(unknown).unwrap(), None);
+    }
+
+    #[test]
+    fn stash_oprets() {
+        let mut stash = MemStash::in_memory();
+        let opret_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let tapret_id = Txid::from_str(&"02".repeat(32)).unwrap();
+        let opret = SealWitness::new(
+            PubWitness::new(opret_id),
+            strict_dumb!(),
+            DbcProof::Opret(OpretProof::default()),
+        );
+        let commitment = opret.merkle_block.commit_id();
+        stash.replace_witness(opret).unwrap();
+        stash
+            .replace_witness(SealWitness::new(
+                PubWitness::new(tapret_id),
+                strict_dumb!(),
+                DbcProof::Tapret(strict_dumb!()),
+            ))
+            .unwrap();
+
+        assert_eq!(stash.oprets().unwrap().collect::<Vec<_>>(), vec![(opret_id, commitment)]);
+        assert_eq!(
+            stash
+                .taprets()
+                .unwrap()
+                .map(|(txid, _)| txid)
+                .collect::<Vec<_>>(),
+            vec![tapret_id]
+        );
+    }
+
+    #[test]
+    fn stash_witness_merge() {
+        let tx = Tx {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+        let txid = tx.compute_txid();
+        let opret = DbcProof::Opret(OpretProof::default());
+        let mut stash = MemStash::in_memory();
+
+        let txid_only = SealWitness::new(PubWitness::new(txid), strict_dumb!(), opret.clone());
+        let full = SealWitness::new(PubWitness::with(tx), strict_dumb!(), opret);
+        assert!(stash.replace_witness(txid_only.clone()).unwrap());
+        assert!(!stash.replace_witness(full).unwrap());
+        assert!(matches!(stash.witnesses.get(&txid).unwrap().public, PubWitness::Tx(_)));
+        assert!(!stash.replace_witness(txid_only).unwrap());
+        assert!(matches!(stash.witnesses.get(&txid).unwrap().public, PubWitness::Tx(_)));
+
+        let tapret = SealWitness::new(
+            PubWitness::new(txid),
+            strict_dumb!(),
+            DbcProof::Tapret(strict_dumb!()),
+        );
+        assert!(matches!(
+            stash.replace_witness(tapret),
+            Err(MemError::WitnessMerge(SealWitnessMergeError::DbcMismatch))
+        ));
+        let stored = stash.witnesses.get(&txid).unwrap();
+        assert!(matches!(stored.dbc_proof, DbcProof::Opret(_)));
+        assert!(matches!(stored.public, PubWitness::Tx(_)));
+    }
+
+    #[test]
+    fn state_forget_contract() {
+        let schema = schema();
+        let genesis1 = genesis(&schema);
+        let mut genesis2 = genesis(&schema);
+        genesis2.timestamp = 1;
+        let contract1 = genesis1.contract_id();
+        let contract2 = genesis2.contract_id();
+        let shared = Txid::from_str(&"01".repeat(32)).unwrap();
+        let exclusive = Txid::from_str(&"02".repeat(32)).unwrap();
+        let ord = WitnessOrd::Tentative;
+        let mut state = MemState::in_memory();
+
+        let mut writer = state.register_contract(&schema, &genesis1).unwrap();
+        writer
+            .add_transition(
+                &transition(contract1, 1, &[(0, 100)]),
+                shared,
+                ord,
+                BundleId::from_byte_array([1; 32]),
+            )
+            .unwrap();
+        writer
+            .add_transition(
+                &transition(contract1, 2, &[(0, 100)]),
+                exclusive,
+                ord,
+                BundleId::from_byte_array([2; 32]),
+            )
+            .unwrap();
+        drop(writer);
+        let mut writer = state.register_contract(&schema, &genesis2).unwrap();
+        writer
+            .add_transition(
+                &transition(contract2, 3, &[(0, 100)]),
+                shared,
+                ord,
+                BundleId::from_byte_array([3; 32]),
+            )
+            .unwrap();
+        drop(writer);
+
+        assert_eq!(state.forget_contract(contract1).unwrap(), 1);
+        assert!(!state.contracts.contains_key(&contract1));
+        assert!(!state.witnesses.contains_key(&exclusive));
+        assert_eq!(state.witnesses.get(&shared), Some(&ord));
+        let contract = state.contract_state(contract2).unwrap();
+        assert_eq!(contract.witness_ord(shared), Some(ord));
+        assert_eq!(contract.fungible_all().count(), 1);
+    }
+
+    #[test]
+    fn register_contract_observed() {
+        let schema = schema();
+        let ty = AssignmentType::with(0);
+        let txid = Txid::from_str(&"01".repeat(32)).unwrap();
+        let mut genesis = genesis(&schema);
+        let mut assigns = TypedAssigns::Fungible(strict_dumb!());
+        *assigns.as_fungible_mut().unwrap() = NonEmptyVec::with(Assign::revealed(
+            GenesisSeal::with_blinding(txid, 0u32, 1),
+            RevealedValue::new(100),
+        ));
+        genesis.assignments = SmallOrdMap::from_iter_checked([(ty, assigns)]).into();
+        let contract_id = genesis.contract_id();
+        let mut state = MemState::in_memory();
+        let mut changes = vec![];
+
+        // the genesis is added before an observer can be set on the writer
+        let writer = state.register_contract(&schema, &genesis).unwrap();
+        drop(writer.with_observer(|change| changes.push(change)));
+        assert!(changes.is_empty());
+
+        let mut state = MemState::in_memory();
+        let mut writer = state
+            .register_contract_observed(&schema, &genesis, |change| changes.push(change))
+            .unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 1, &[(0, 50), (1, 50)]),
+                txid,
+                WitnessOrd::Tentative,
+                BundleId::from_byte_array([1; 32]),
+            )
+            .unwrap();
+        drop(writer);
+        let amounts = changes
+            .iter()
+            .map(|change| match change {
+                StateChange::Fungible(assignment) => assignment.state.as_u64(),
+                _ => panic!("unexpected state change {change:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(amounts, [100, 50, 50]);
+    }
+
+    #[test]
+    fn stash_forget_contract() {
+        let schema = schema();
+        let genesis1 = genesis(&schema);
+        let mut genesis2 = genesis(&schema);
+        genesis2.timestamp = 1;
+        let contract1 = genesis1.contract_id();
+        let contract2 = genesis2.contract_id();
+        let bundle1 = bundle(transition(contract1, 1, &[(0, 100)]));
+        let bundle2 = bundle(transition(contract2, 2, &[(0, 100)]));
+        let bundle_id1 = bundle1.bundle_id();
+        let bundle_id2 = bundle2.bundle_id();
+        let witness = |no: u8, bundles: &[&TransitionBundle]| {
+            let source = MultiSource {
+                messages: Confined::from_iter_checked(bundles.iter().map(|bundle| {
+                    let contract_id = bundle.known_transitions[0].transition.contract_id;
+                    (
+                        ProtocolId::from_byte_array(contract_id.to_byte_array()),
+                        Message::from_byte_array(bundle.bundle_id().to_byte_array()),
+                    )
+                })),
+                static_entropy: Some(1),
+                ..default!()
+            };
+            let merkle_block = MerkleBlock::from(MerkleTree::try_commit(&source).unwrap());
+            SealWitness::new(
+                PubWitness::Txid(Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap()),
+                merkle_block,
+                DbcProof::Opret(OpretProof::default()),
+            )
+        };
+        let exclusive = witness(1, &[&bundle1]);
+        let shared = witness(2, &[&bundle1, &bundle2]);
+        let mut stash = MemStash::in_memory();
+        stash.replace_schema(schema).unwrap();
+        stash.replace_genesis(genesis1).unwrap();
+        stash.replace_genesis(genesis2).unwrap();
+        stash.replace_bundle(bundle1).unwrap();
+        stash.replace_bundle(bundle2).unwrap();
+        stash.replace_witness(exclusive.clone()).unwrap();
+        stash.replace_witness(shared.clone()).unwrap();
+
+        assert_eq!(stash.forget_contract(contract1).unwrap(), 1);
+        assert!(stash.genesis(contract1).is_err());
+        assert!(stash.bundle(bundle_id1).is_err());
+        assert!(stash.witness(exclusive.witness_id()).is_err());
+        // the data of the other contract are kept
+        assert!(stash.genesis(contract2).is_ok());
+        assert!(stash.bundle(bundle_id2).is_ok());
+        assert!(stash.witness(shared.witness_id()).is_ok());
+        assert_eq!(stash.forget_contract(contract1).unwrap(), 0);
+    }
+
+    #[test]
+    fn index_forget_contract() {
+        let ty = AssignmentType::with(0);
+        let contract1 = genesis(&schema()).contract_id();
+        let contract2 = genesis(&Schema {
+            name: strict_encoding::TypeName::from_str("Other").unwrap(),
+            ..schema()
+        })
+        .contract_id();
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let mut index = MemIndex::in_memory();
+        let mut bundle_ids = vec![];
+        for (no, contract_id) in [contract1, contract2].into_iter().enumerate() {
+            let transition = transition(contract_id, no as u64 + 1, &[(0, 100)]);
+            let opid = transition.id();
+            let bundle_id = BundleId::from_byte_array([no as u8 + 1; 32]);
+            index.register_contract(contract_id).unwrap();
+            index
+                .register_bundle(bundle_id, witness_id, contract_id)
+                .unwrap();
+            index
+                .index_genesis_assignments(
+                    contract_id,
+                    &[Assign::revealed(
+                        GenesisSeal::with_blinding(witness_id, 1u32, 1),
+                        RevealedValue::new(100),
+                    )],
+                    OpId::from_inner(contract_id.into_inner()),
+                    ty,
+                )
+                .unwrap();
+            index
+                .index_transition_assignments(
+                    contract_id,
+                    transition.assignments.get(&ty).unwrap().as_fungible(),
+                    opid,
+                    ty,
+                    witness_id,
+                )
+                .unwrap();
+            bundle_ids.push(bundle_id);
+        }
+
+        index.forget_contract(contract1).unwrap();
+        assert!(index.outpoints_for_contract(contract1).is_err());
+        assert!(index.bundle_info(bundle_ids[0]).is_err());
+        let (_, contract_id) = index.bundle_info(bundle_ids[1]).unwrap();
+        assert_eq!(contract_id, contract2);
+        assert_eq!(index.outpoints_for_contract(contract2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn global_paged() {
+        let ty = GlobalStateType::with(0);
+        let schema = schema();
+        let mut genesis = genesis(&schema);
+        for no in 0..5u8 {
+            genesis
+                .globals
+                .add_state(ty, RevealedData::new(small_vec![no]))
+                .unwrap();
+        }
+        let mut state = MemContractState::new(&schema, genesis.contract_id());
+        state
+            .global
+            .insert(ty, MemGlobalState::new(u24::try_from(4u32).unwrap()))
+            .unwrap();
+        state.add_operation(OrdOpRef::Genesis(&genesis)).unwrap();
+        assert_eq!(state.global.get(&ty).unwrap().size(), 5);
+        state.add_operation(OrdOpRef::Genesis(&genesis)).unwrap();
+        assert_eq!(state.global.get(&ty).unwrap().size(), 5);
+
+        let contract = MemContract::new(empty!(), empty!(), state);
+        let all = contract.global(ty).unwrap().count();
+        assert_eq!(all, 4);
+        let page1 = contract.global_paged(ty, 0, 3).unwrap();
+        let page2 = contract.global_paged(ty, 3, 3).unwrap();
+        assert_eq!(page1.len(), 3);
+        assert_eq!(page2.len(), 1);
+        assert!(page1.iter().all(|item| !page2.contains(item)));
+        let global = contract
+            .global(ty)
+            .unwrap()
+            .map(|entry| *entry.borrow().ord())
+            .collect::<Vec<_>>();
+        let paged = page1
+            .iter()
+            .chain(&page2)
+            .map(|(ord, _)| *ord)
+            .collect::<Vec<_>>();
+        assert_eq!(paged, global);
+        assert!(contract
+            .global_paged(GlobalStateType::with(1), 0, 1)
+            .is_err());
+
+        let full = contract.global_full(ty).unwrap().collect::<Vec<_>>();
+        assert_eq!(full.len(), 5);
+        assert!(full.windows(2).all(|pair| pair[0].0 > pair[1].0));
+        let limited = contract
+            .global(ty)
+            .unwrap()
+            .map(|entry| *entry.borrow().ord())
+            .collect::<Vec<_>>();
+        assert_eq!(full.iter().take(4).map(|(ord, _)| *ord).collect::<Vec<_>>(), limited);
+        assert!(contract.global_full(GlobalStateType::with(1)).is_err());
+    }
+
+    #[test]
+    fn global_size() {
+        let ty = GlobalStateType::with(0);
+        let schema = schema();
+        let mut genesis = genesis(&schema);
+        for no in 0..5u8 {
+            genesis
+                .globals
+                .add_state(ty, RevealedData::new(small_vec![no]))
+                .unwrap();
+        }
+        let mut state = MemContractState::new(&schema, genesis.contract_id());
+        state
+            .global
+            .insert(ty, MemGlobalState::new(u24::try_from(4u32).unwrap()))
+            .unwrap();
+        state.add_operation(OrdOpRef::Genesis(&genesis)).unwrap();
+
+        let contract = MemContract::new(empty!(), empty!(), state);
+        let mut global = MemGlobalStateAccess::new(
+            contract
+                .global_items(contract.global_state(ty).unwrap())
+                .map(|(ord, data)| (ord, data.to_owned())),
+            u24::try_from(4u32).unwrap(),
+        );
+        // the size is known without consuming the items
+        assert_eq!(global.len(), 4);
+        let first = global.next().unwrap();
+        assert_eq!(global.len(), 3);
+        assert!(global.at_depth(0) == Some(first));
+        assert_eq!(global.by_ref().count(), 3);
+        assert_eq!(global.len(), 0);
+        assert!(global.next().is_none());
+        assert_eq!(global.len(), 0);
+
+        // counting a partially consumed iterator agrees with its length
+        let mut global = MemGlobalStateAccess::new(
+            contract
+                .global_items(contract.global_state(ty).unwrap())
+                .map(|(ord, data)| (ord, data.to_owned())),
+            u24::try_from(4u32).unwrap(),
+        );
+        global.next().unwrap();
+        global.next().unwrap();
+        assert_eq!(global.len(), 2);
+        assert_eq!(global.count(), 2);
+    }
+
+    #[test]
+    fn global_latest() {
+        let ty = GlobalStateType::with(0);
+        let mut schema = schema();
+        schema
+            .global_types
+            .insert(ty, GlobalDetails {
+                global_state_schema: GlobalStateSchema::many(
+                    SemId::from_hex(&"01".repeat(32)).unwrap(),
+                ),
+                name: FieldName::from_str("supply").unwrap(),
+            })
+            .unwrap();
+        let mut genesis = genesis(&schema);
+        genesis
+            .globals
+            .add_state(ty, RevealedData::new(small_vec![0]))
+            .unwrap();
+        let contract_id = genesis.contract_id();
+        let mut state = MemState::in_memory();
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        // the operation mined later is added first
+        for (nonce, height) in [(2u8, 200), (1u8, 100)] {
+            let mut transition = transition(contract_id, nonce as u64, &[(0, 100)]);
+            transition
+                .globals
+                .add_state(ty, RevealedData::new(small_vec![nonce]))
+                .unwrap();
+            let pos = WitnessPos::bitcoin(
+                NonZeroU32::new(height).unwrap(),
+                1_700_000_000 + height as i64,
+            )
+            .unwrap();
+            writer
+                .add_transition(
+                    &transition,
+                    Txid::from_str(&format!("{nonce:02x}").repeat(32)).unwrap(),
+                    WitnessOrd::Mined(pos),
+                    BundleId::from_byte_array([nonce; 32]),
+                )
+                .unwrap();
+        }
+        drop(writer);
+
+        let contract = state.contract_state(contract_id).unwrap();
+        let latest = contract.global_latest(ty).unwrap();
+        assert_eq!(latest, Some(&RevealedData::new(small_vec![2])));
+        assert_eq!(
+            latest,
+            contract
+                .global(ty)
+                .unwrap()
+                .next()
+                .map(|entry| entry.borrow().data().clone())
+                .as_ref()
+        );
+        assert!(contract.global_latest(GlobalStateType::with(1)).is_err());
+    }
+
+    #[test]
+    fn global_overflow() {
+        let ty = GlobalStateType::with(0);
+        let schema = schema();
+        let mut genesis = genesis(&schema);
+        for no in 0..3u8 {
+            genesis
+                .globals
+                .add_state(ty, RevealedData::new(small_vec![no]))
+                .unwrap();
+        }
+        let mut state = MemContractState::new(&schema, genesis.contract_id());
+        state
+            .global
+            .insert(ty, MemGlobalState::new(u24::try_from(3u32).unwrap()))
+            .unwrap();
+        state
+            .add_operation_checked(OrdOpRef::Genesis(&genesis))
+            .unwrap();
+        // re-adding the same operation doesn't add new items
+        state
+            .add_operation_checked(OrdOpRef::Genesis(&genesis))
+            .unwrap();
+        assert_eq!(state.global.get(&ty).unwrap().size(), 3);
+
+        genesis
+            .globals
+            .add_state(ty, RevealedData::new(small_vec![3]))
+            .unwrap();
+        let mut state = MemContractState::new(&schema, genesis.contract_id());
+        state
+            .global
+            .insert(ty, MemGlobalState::new(u24::try_from(3u32).unwrap()))
+            .unwrap();
+        let err = state
+            .add_operation_checked(OrdOpRef::Genesis(&genesis))
+            .unwrap_err();
+        assert!(matches!(err, StateError::GlobalStateOverflow { ty: t, limit }
+            if t == ty && limit.to_u32() == 3));
+        assert_eq!(state.global.get(&ty).unwrap().size(), 0);
+
+        // the unchecked path is kept for the data already validated against the schema
+        state.add_operation(OrdOpRef::Genesis(&genesis)).unwrap();
+        assert_eq!(state.global.get(&ty).unwrap().size(), 4);
+
+        // a global type unknown to the schema is reported instead of panicking
+        let unknown = GlobalStateType::with(1);
+        genesis
+            .globals
+            .add_state(unknown, RevealedData::new(small_vec![0]))
+            .unwrap();
+        let mut state = MemContractState::new(&schema, genesis.contract_id());
+        state
+            .global
+            .insert(ty, MemGlobalState::new(u24::try_from(10u32).unwrap()))
+            .unwrap();
+        let err = state
+            .add_operation_checked(OrdOpRef::Genesis(&genesis))
+            .unwrap_err();
+        assert!(matches!(err, StateError::UnknownGlobalType(t) if t == unknown));
+        assert_eq!(state.global.get(&ty).unwrap().size(), 0);
+    }
+
+    #[test]
+    fn global_limit_update() {
+        let ty = GlobalStateType::with(0);
+        let schema = schema();
+        let mut genesis = genesis(&schema);
+        for no in 0..3u8 {
+            genesis
+                .globals
+                .add_state(ty, RevealedData::new(small_vec![no]))
+                .unwrap();
+        }
+        let mut state = MemContractState::new(&schema, genesis.contract_id());
+        state
+            .global
+            .insert(ty, MemGlobalState::new(u24::ONE))
+            .unwrap();
+        state.add_operation(OrdOpRef::Genesis(&genesis)).unwrap();
+        let contract = MemContract::new(empty!(), empty!(), state.clone());
+        assert_eq!(contract.global(ty).unwrap().count(), 1);
+
+        state
+            .set_global_limit(ty, u24::try_from(3u32).unwrap())
+            .unwrap();
+        let contract = MemContract::new(empty!(), empty!(), state.clone());
+        assert_eq!(contract.global(ty).unwrap().count(), 3);
+
+        let unknown = GlobalStateType::with(1);
+        assert!(matches!(
+            state.set_global_limit(unknown, u24::ONE),
+            Err(UnknownGlobalStateType(t)) if t == unknown
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn contract_state_json() {
+        let ty = GlobalStateType::with(0);
+        let schema = schema();
+        let mut genesis = genesis(&schema);
+        for no in 0..3u8 {
+            genesis
+                .globals
+                .add_state(ty, RevealedData::new(small_vec![no]))
+                .unwrap();
+        }
+        let contract_id = genesis.contract_id();
+        let mut state = MemContractState::new(&schema, contract_id);
+        state
+            .global
+            .insert(ty, MemGlobalState::new(u24::try_from(3u32).unwrap()))
+            .unwrap();
+        state.add_operation(OrdOpRef::Genesis(&genesis)).unwrap();
+        for nonce in (0..4).rev() {
+            let transition = transition(contract_id, nonce, &[(1, 10), (0, 20)]);
+            let bundle_id = bundle(transition.clone()).bundle_id();
+            state
+                .add_operation(OrdOpRef::Transition(
+                    &transition,
+                    Txid::from_str(&format!("{:02x}", nonce + 1).repeat(32)).unwrap(),
+                    WitnessOrd::Tentative,
+                    bundle_id,
+                ))
+                .unwrap();
+        }
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::to_string(&state.clone()).unwrap(), json);
+        let decoded: MemContractState = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, state);
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+
+        // allocations are listed in the order of their opouts
+        let opouts = state.fungibles.iter().map(|a| a.opout).collect::<Vec<_>>();
+        assert!(opouts.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn state_overflow() {
+        let contract_id = ContractId::from_byte_array([1; 32]);
+        let origin = AssignmentsOrigin {
+            contract_id,
+            bundle_id: Some(BundleId::from_byte_array([2; 32])),
+            witness_id: Some(Txid::from_str(&"03".repeat(32)).unwrap()),
+            opid: OpId::from_byte_array([4; 32]),
+            ty: AssignmentType::with(0),
+        };
+        let assignments = (0..2u32)
+            .map(|vout| {
+                Assign::revealed(GraphSeal::with_blinded_vout(vout, 0), RevealedValue::new(10))
+            })
+            .collect::<Vec<_>>();
+
+        let mut changes = 0;
+        let mut tiny = Confined::<BTreeSet<OutputAssignment<RevealedValue>>, 0, 1>::default();
+        let err = origin
+            .push_to(&mut tiny, &assignments, Some(&mut |_| changes += 1))
+            .unwrap_err();
+        assert!(matches!(err, MemError::StateOverflow(id) if id == contract_id));
+        assert_eq!(tiny.len(), 1);
+        // the allocation which wasn't stored is not reported
+        assert_eq!(changes, 1);
+
+        let mut large = LargeOrdSet::<OutputAssignment<RevealedValue>>::default();
+        origin.push_to(&mut large, &assignments, None).unwrap();
+        assert_eq!(large.len(), 2);
+    }
+
+    #[test]
+    fn contract_exists() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let unknown = ContractId::from_byte_array([0xFF; 32]);
+        let mut state = MemState::in_memory();
+        assert!(!state.contract_exists(contract_id));
+
+        state.register_contract(&schema, &genesis).unwrap();
+        // the existence doesn't depend on the witnesses, so the state is not filtered by them
+        state.witnesses = LargeOrdMap::from_iter_checked([(
+            Txid::from_str(&"01".repeat(32)).unwrap(),
+            WitnessOrd::Archived,
+        )]);
+        assert!(state.contract_exists(contract_id));
+        assert!(!state.contract_exists(unknown));
+
+        state.forget_contract(contract_id).unwrap();
+        assert!(!state.contract_exists(contract_id));
+    }
+
+    #[test]
+    fn fungible_balance() {
+        let ty = AssignmentType::with(0);
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let ord = WitnessOrd::Tentative;
+        let mut state = MemState::in_memory();
+
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 1, &[(0, 100), (1, 50)]),
+                witness_id,
+                ord,
+                BundleId::from_byte_array([1; 32]),
+            )
+            .unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 2, &[(0, 25)]),
+                witness_id,
+                ord,
+                BundleId::from_byte_array([2; 32]),
+            )
+            .unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 3, &[(2, u64::MAX), (2, 1)]),
+                witness_id,
+                ord,
+                BundleId::from_byte_array([3; 32]),
+            )
+            .unwrap();
+        drop(writer);
+
+        let contract = state.contract_state(contract_id).unwrap();
+        assert_eq!(contract.fungible_by_type(ty).count(), 5);
+        assert_eq!(contract.fungible_balance(ty), u64::MAX);
+        assert_eq!(contract.fungible_balance(AssignmentType::with(1)), 0);
+        assert_eq!(contract.fungible_balance_by_outpoint(ty), bmap! {
+            Outpoint::new(witness_id, 0) => 125,
+            Outpoint::new(witness_id, 1) => 50,
+            Outpoint::new(witness_id, 2) => u64::MAX,
+        });
+    }
+
+    #[test]
+    fn assignment_by_opout() {
+        let ty = AssignmentType::with(0);
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let transition = transition(contract_id, 1, &[(0, 100), (1, 50)]);
+        let opid = transition.id();
+        let mut state = MemState::in_memory();
+        let mut index = MemIndex::in_memory();
+
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(
+                &transition,
+                witness_id,
+                WitnessOrd::Tentative,
+                BundleId::from_byte_array([1; 32]),
+            )
+            .unwrap();
+        drop(writer);
+        index.register_contract(contract_id).unwrap();
+        let Some(TypedAssigns::Fungible(assigns)) = transition.assignments.get(&ty) else {
+            unreachable!()
+        };
+        index
+            .index_transition_assignments(contract_id, assigns, opid, ty, witness_id)
+            .unwrap();
+
+        let opouts = index
+            .opouts_by_outputs(contract_id, [Outpoint::new(witness_id, 1)])
+            .unwrap();
+        assert_eq!(opouts.len(), 1);
+        let opout = opouts.into_iter().next().unwrap();
+        assert_eq!(opout, Opout::new(opid, ty, 1));
+
+        let contract = state.contract_state(contract_id).unwrap();
+        let allocation = contract.assignment_by_opout(opout).unwrap();
+        assert_eq!(allocation.opout, opout);
+        assert_eq!(allocation.witness, Some(witness_id));
+        assert_eq!(allocation.state, AllocatedState::Amount(RevealedValue::new(50)));
+        assert!(contract
+            .assignment_by_opout(Opout::new(opid, ty, 2))
+            .is_none());
+        assert!(contract
+            .assignment_by_opout(Opout::new(opid, AssignmentType::with(1), 1))
+            .is_none());
+    }
+
+    #[test]
+    fn outpoints_for_contract() {
+        let ty = AssignmentType::with(0);
+        let contract_id = genesis(&schema()).contract_id();
+        let txid = Txid::from_str(&"01".repeat(32)).unwrap();
+        let opid = OpId::from_inner(contract_id.into_inner());
+        let seals = [
+            GenesisSeal::with_blinding(txid, 0u32, 1),
+            GenesisSeal::with_blinding(txid, 1u32, 2),
+            GenesisSeal::with_blinding(txid, 1u32, 3),
+        ];
+        let assigns = seals
+            .iter()
+            .map(|seal| Assign::revealed(*seal, RevealedValue::new(10)))
+            .collect::<Vec<_>>();
+        let mut index = MemIndex::in_memory();
+
+        index.register_contract(contract_id).unwrap();
+        assert!(index
+            .outpoints_for_contract(contract_id)
+            .unwrap()
+            .is_empty());
+        index
+            .index_genesis_assignments(contract_id, &assigns, opid, ty)
+            .unwrap();
+
+        // two of the seals are on the same output
+        let outpoints = index.outpoints_for_contract(contract_id).unwrap();
+        assert_eq!(outpoints.len(), 2);
+        for seal in seals {
+            assert!(outpoints.contains(&seal.to_output_seal().unwrap()));
+        }
+        let unknown = genesis(&Schema {
+            name: strict_encoding::TypeName::from_str("Other").unwrap(),
+            ..schema()
+        })
+        .contract_id();
+        assert!(index.outpoints_for_contract(unknown).is_err());
+    }
+
+    #[test]
+    fn allocations_at() {
+        let ty = AssignmentType::with(0);
+        let txid = Txid::from_str(&"01".repeat(32)).unwrap();
+        let contract_ids = [
+            genesis(&schema()).contract_id(),
+            genesis(&Schema {
+                name: strict_encoding::TypeName::from_str("Other").unwrap(),
+                ..schema()
+            })
+            .contract_id(),
+        ];
+        let mut index = MemIndex::in_memory();
+        for (no, contract_id) in contract_ids.into_iter().enumerate() {
+            let opid = OpId::from_inner(contract_id.into_inner());
+            // both contracts assign state to the output 0, only the second one to the output 1
+            let assigns = (0..=no as u32)
+                .map(|vout| {
+                    Assign::revealed(
+                        GenesisSeal::with_blinding(txid, vout, vout as u64),
+                        RevealedValue::new(10),
+                    )
+                })
+                .collect::<Vec<_>>();
+            index.register_contract(contract_id).unwrap();
+            index
+                .index_genesis_assignments(contract_id, &assigns, opid, ty)
+                .unwrap();
+        }
+
+        let shared = Outpoint::new(txid, 0);
+        let single = Outpoint::new(txid, 1);
+        let unknown = Outpoint::new(txid, 2);
+        let allocations = index
+            .allocations_at(bset![shared, single, unknown])
+            .unwrap();
+        assert_eq!(allocations.len(), 2);
+        assert!(!allocations.contains_key(&unknown));
+        assert_eq!(
+            allocations[&shared],
+            contract_ids
+                .iter()
+                .map(|id| (*id, Opout::new(OpId::from_inner(id.into_inner()), ty, 0)))
+                .collect()
+        );
+        assert_eq!(allocations[&single], bset![(
+            contract_ids[1],
+            Opout::new(OpId::from_inner(contract_ids[1].into_inner()), ty, 1)
+        )]);
+    }
+
+    #[test]
+    fn fungible_spent() {
+        let ty = AssignmentType::with(0);
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let witness1 = Txid::from_str(&"01".repeat(32)).unwrap();
+        let witness2 = Txid::from_str(&"02".repeat(32)).unwrap();
+        let bundle1 = BundleId::from_byte_array([1; 32]);
+        let bundle2 = BundleId::from_byte_array([2; 32]);
+        let first = transition(contract_id, 1, &[(0, 100), (1, 50)]);
+        let mut second = transition(contract_id, 2, &[(0, 100)]);
+        second.inputs = NonEmptyOrdSet::with(Opout::new(first.id(), ty, 0)).into();
+        let mut state = MemState::in_memory();
+        let mut index = MemIndex::in_memory();
+
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(&first, witness1, WitnessOrd::Tentative, bundle1)
+            .unwrap();
+        writer
+            .add_transition(&second, witness2, WitnessOrd::Tentative, bundle2)
+            .unwrap();
+        drop(writer);
+        index.register_contract(contract_id).unwrap();
+        index
+            .register_operation(first.id(), bundle1, &first.inputs)
+            .unwrap();
+        index
+            .register_operation(second.id(), bundle2, &second.inputs)
+            .unwrap();
+
+        let contract = state.contract_state(contract_id).unwrap();
+        assert_eq!(contract.fungible_all().count(), 3);
+        let spent = contract.fungible_spent(&index).unwrap().collect::<Vec<_>>();
+        assert_eq!(spent.len(), 1);
+        assert_eq!(spent[0].opout, Opout::new(first.id(), ty, 0));
+        assert_eq!(spent[0].state, RevealedValue::new(100));
+        assert_eq!(spent[0].witness, Some(witness1));
+    }
+
+    #[test]
+    fn contract_snapshot() {
+        let schema = schema();
+        let schema_id = schema.schema_id();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let valid = BundleId::from_byte_array([1; 32]);
+        let invalid = BundleId::from_byte_array([2; 32]);
+        let foreign = BundleId::from_byte_array([3; 32]);
+        let mut state = MemState::in_memory();
+
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 1, &[(0, 100), (1, 50)]),
+                witness_id,
+                WitnessOrd::Tentative,
+                valid,
+            )
+            .unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 2, &[(2, 10)]),
+                witness_id,
+                WitnessOrd::Tentative,
+                invalid,
+            )
+            .unwrap();
+        drop(writer);
+        state
+            .update_bundle(invalid, Some(InvalidReason::ValidationFailed))
+            .unwrap();
+        // a bundle not belonging to the contract is not exported
+        state
+            .update_bundle(foreign, Some(InvalidReason::ManuallyDisabled))
+            .unwrap();
+        let fungibles = |state: &MemState| {
+            state
+                .contract_state(contract_id)
+                .unwrap()
+                .fungible_all()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let snapshot = state.export_contract(contract_id).unwrap();
+        assert_eq!(snapshot.contract_id(), contract_id);
+        assert_eq!(snapshot.schema_id(), schema_id);
+        assert_eq!(snapshot.witnesses().len(), 1);
+        assert_eq!(snapshot.invalid_bundles().iter().collect::<Vec<_>>(), vec![(
+            &invalid,
+            &InvalidReason::ValidationFailed
+        )]);
+        let data = snapshot.to_strict_serialized::<U24>().unwrap();
+        let snapshot = ContractSnapshot::from_strict_serialized::<U24>(data).unwrap();
+
+        let mut imported = MemState::in_memory();
+        assert!(matches!(
+            imported.import_contract(snapshot.clone(), |_| false),
+            Err(MemError::UnknownSchema { contract_id: id, schema_id: s })
+                if id == contract_id && s == schema_id
+        ));
+        assert!(imported.contracts.is_empty());
+        imported
+            .import_contract(snapshot.clone(), |id| id == schema_id)
+            .unwrap();
+        // the allocation of the invalid bundle is filtered out
+        assert_eq!(fungibles(&imported).len(), 2);
+        assert_eq!(fungibles(&imported), fungibles(&state));
+        assert_eq!(imported.witnesses, state.witnesses);
+        assert_eq!(imported.bundle_invalid_reason(invalid), Some(InvalidReason::ValidationFailed));
+        assert_eq!(imported.bundle_invalid_reason(foreign), None);
+
+        let mut mismatching = snapshot;
+        mismatching.state.schema_id = SchemaId::from_byte_array([1; 32]);
+        assert!(matches!(
+            imported.import_contract(mismatching, |_| true),
+            Err(MemError::SchemaMismatch { .. })
+        ));
+        assert!(matches!(
+            MemState::in_memory().export_contract(contract_id),
+            Err(MemError::UnknownContract(id)) if id == contract_id
+        ));
+    }
+
+    #[test]
+    fn contract_state_filtered() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let mined_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let tentative_id = Txid::from_str(&"02".repeat(32)).unwrap();
+        let pos =
+            |height, time| WitnessPos::bitcoin(NonZeroU32::new(height).unwrap(), time).unwrap();
+        let mut state = MemState::in_memory();
+
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 1, &[(0, 100)]),
+                mined_id,
+                WitnessOrd::Mined(pos(100, 1_700_000_000)),
+                BundleId::from_byte_array([1; 32]),
+            )
+            .unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 2, &[(0, 50)]),
+                tentative_id,
+                WitnessOrd::Tentative,
+                BundleId::from_byte_array([2; 32]),
+            )
+            .unwrap();
+        drop(writer);
+
+        let contract = state.contract_state(contract_id).unwrap();
+        assert_eq!(contract.fungible_all().count(), 2);
+        let contract = state
+            .contract_state_filtered(contract_id, WitnessOrd::Tentative)
+            .unwrap();
+        assert_eq!(contract.fungible_all().count(), 2);
+        let contract = state
+            .contract_state_filtered(contract_id, WitnessOrd::Mined(pos(200, 1_700_100_000)))
+            .unwrap();
+        assert_eq!(contract.fungible_all().count(), 1);
+        assert!(contract.witness_ord(tentative_id).is_none());
+        let contract = state
+            .contract_state_filtered(contract_id, WitnessOrd::Mined(pos(50, 1_600_000_000)))
+            .unwrap();
+        assert_eq!(contract.fungible_all().count(), 0);
+    }
+
+    #[test]
+    fn reorg() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let pos = WitnessPos::bitcoin(NonZeroU32::new(100).unwrap(), 1_700_000_000).unwrap();
+        let mut state = MemState::in_memory();
+
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 1, &[(0, 100)]),
+                witness_id,
+                WitnessOrd::Mined(pos),
+                BundleId::from_byte_array([1; 32]),
+            )
+            .unwrap();
+        drop(writer);
+
+        state
+            .handle_reorg(witness_id, WitnessOrd::Tentative)
+            .unwrap();
+        let mined = state
+            .contract_state_filtered(contract_id, WitnessOrd::Mined(pos))
+            .unwrap();
+        assert_eq!(mined.fungible_all().count(), 0);
+        let contract = state.contract_state(contract_id).unwrap();
+        assert_eq!(contract.witness_ord(witness_id), Some(WitnessOrd::Tentative));
+        assert_eq!(contract.fungible_all().count(), 1);
+
+        state
+            .handle_reorg(witness_id, WitnessOrd::Archived)
+            .unwrap();
+        let contract = state.contract_state(contract_id).unwrap();
+        assert_eq!(contract.fungible_all().count(), 0);
+    }
+
+    #[test]
+    fn bundle_stats() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let invalid = BundleId::from_byte_array([1; 32]);
+        let mut state = MemState::in_memory();
+
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 1, &[(0, 100), (1, 50)]),
+                witness_id,
+                WitnessOrd::Tentative,
+                invalid,
+            )
+            .unwrap();
+        writer
+            .add_transition(
+                &transition(contract_id, 2, &[(2, 10)]),
+                witness_id,
+                WitnessOrd::Tentative,
+                BundleId::from_byte_array([2; 32]),
+            )
+            .unwrap();
+        drop(writer);
+        assert_eq!(state.bundle_stats(contract_id), BundleStats {
+            total_bundles: 2,
+            invalid_bundles: 0,
+            suppressed_allocations: 0,
+        });
+
+        state
+            .update_bundle(invalid, Some(InvalidReason::WitnessInvalid))
+            .unwrap();
+        assert_eq!(state.bundle_stats(contract_id), BundleStats {
+            total_bundles: 2,
+            invalid_bundles: 1,
+            suppressed_allocations: 2,
+        });
+        assert_eq!(
+            state
+                .contract_state(contract_id)
+                .unwrap()
+                .fungible_all()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn height_sorted_bundles() {
+        let mut state = MemState::in_memory();
+        let pos = |height| {
+            WitnessOrd::Mined(
+                WitnessPos::bitcoin(NonZeroU32::new(height).unwrap(), 1231006505).unwrap(),
+            )
+        };
+        let bundles =
+            [(0x01, pos(300)), (0x02, WitnessOrd::Tentative), (0x03, pos(100)), (0x04, pos(200))]
+                .map(|(no, ord)| {
+                    let txid = Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap();
+                    state.upsert_witness(txid, ord).unwrap();
+                    let mut bundle: WitnessBundle = strict_dumb!();
+                    bundle.pub_witness = PubWitness::Txid(txid);
+                    bundle
+                });
+
+        let sorted = height_sorted(bundles.iter().rev(), &state)
+            .into_iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+        let expected = [0x03, 0x04, 0x01, 0x02]
+            .map(|no: u8| Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap());
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn data_concealed() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let ty = AssignmentType::with(1);
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let bundle_id = BundleId::from_byte_array([1; 32]);
+        let hidden = GraphSeal::with_blinded_vout(0, 1);
+        let shown = GraphSeal::with_blinded_vout(1, 2);
+        let with_data = |assigns: [AssignData<GraphSeal>; 2]| {
+            let mut transition = transition(contract_id, 1, &[(0, 100)]);
+            transition
+                .assignments
+                .insert(ty, data_assigns(assigns))
+                .unwrap();
+            transition
+        };
+        let revealed = with_data([
+            Assign::revealed(hidden, RevealedData::new(small_vec![0])),
+            Assign::revealed(shown, RevealedData::new(small_vec![1])),
+        ]);
+        let concealed = with_data([
+            Assign::ConfidentialSeal {
+                seal: hidden.conceal(),
+                state: RevealedData::new(small_vec![0]),
+            },
+            Assign::revealed(shown, RevealedData::new(small_vec![1])),
+        ]);
+        assert_eq!(concealed.id(), revealed.id());
+
+        let mut state = MemState::in_memory();
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(&concealed, witness_id, WitnessOrd::Tentative, bundle_id)
+            .unwrap();
+        drop(writer);
+        let contract = state.contract_state(contract_id).unwrap();
+        assert_eq!(contract.data_all().count(), 1);
+        let hidden_opouts = contract.data_concealed(ty).collect::<Vec<_>>();
+        assert_eq!(hidden_opouts.len(), 1);
+        assert_eq!(hidden_opouts[0].0, hidden.conceal());
+        assert_eq!(hidden_opouts[0].1.op, concealed.id());
+        assert_eq!(contract.data_concealed(AssignmentType::with(0)).count(), 0);
+
+        let mut writer = state.update_contract(contract_id).unwrap().unwrap();
+        writer
+            .add_transition(&revealed, witness_id, WitnessOrd::Tentative, bundle_id)
+            .unwrap();
+        drop(writer);
+        let contract = state.contract_state(contract_id).unwrap();
+        assert_eq!(contract.data_all().count(), 2);
+        assert_eq!(contract.data_concealed(ty).count(), 0);
+
+        // the concealed copy of an already revealed allocation is ignored
+        let mut writer = state.update_contract(contract_id).unwrap().unwrap();
+        writer
+            .add_transition(&concealed, witness_id, WitnessOrd::Tentative, bundle_id)
+            .unwrap();
+        drop(writer);
+        let contract = state.contract_state(contract_id).unwrap();
+        assert_eq!(contract.data_all().count(), 2);
+        assert_eq!(contract.data_concealed(ty).count(), 0);
+    }
+
+    #[test]
+    fn outpoint_summary() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let fungible_ty = AssignmentType::with(0);
+        let data_ty = AssignmentType::with(1);
+        let witness_id = Txid::from_str(&"01".repeat(32)).unwrap();
+        let mut transition = transition(contract_id, 1, &[(0, 100), (0, 50), (1, 10)]);
+        transition
+            .assignments
+            .insert(
+                data_ty,
+                data_assigns([Assign::revealed(
+                    GraphSeal::with_blinded_vout(0, 2),
+                    RevealedData::new(small_vec![0]),
+                )]),
+            )
+            .unwrap();
+        let mut state = MemState::in_memory();
+        let mut writer = state.register_contract(&schema, &genesis).unwrap();
+        writer
+            .add_transition(
+                &transition,
+                witness_id,
+                WitnessOrd::Tentative,
+                BundleId::from_byte_array([1; 32]),
+            )
+            .unwrap();
+        drop(writer);
+        let contract = state.contract_state(contract_id).unwrap();
+
+        let summary = contract.outpoint_summary(Outpoint::new(witness_id, 0));
+        assert_eq!(summary.fungible, bmap! { fungible_ty => 150 });
+        assert_eq!(summary.data, bmap! { data_ty => vec![RevealedData::new(small_vec![0])] });
+        assert!(summary.rights.is_empty());
+
+        let summary = contract.outpoint_summary(Outpoint::new(witness_id, 1));
+        assert_eq!(summary.fungible, bmap! { fungible_ty => 10 });
+        assert!(summary.data.is_empty());
+        assert!(contract
+            .outpoint_summary(Outpoint::new(witness_id, 2))
+            .is_empty());
+    }
+
+    #[test]
+    fn allocations_order() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let bundle_id = BundleId::from_byte_array([1; 32]);
+        let witness1 = Txid::from_str(&"01".repeat(32)).unwrap();
+        let witness2 = Txid::from_str(&"02".repeat(32)).unwrap();
+        let transitions = [
+            (transition(contract_id, 1, &[(1, 10), (0, 20)]), witness1),
+            (transition(contract_id, 2, &[(0, 30), (2, 40)]), witness2),
+            (transition(contract_id, 3, &[(3, 50)]), witness1),
+        ];
+
+        let build = |order: &[usize]| {
+            let mut state = MemState::in_memory();
+            let mut writer = state.register_contract(&schema, &genesis).unwrap();
+            for no in order {
+                let (transition, witness_id) = &transitions[*no];
+                writer
+                    .add_transition(transition, *witness_id, WitnessOrd::Tentative, bundle_id)
+                    .unwrap();
+            }
+            drop(writer);
+            state
+        };
+        let state1 = build(&[0, 1, 2]);
+        let state2 = build(&[2, 0, 1]);
+        let contract1 = state1.contract_state(contract_id).unwrap();
+        let contract2 = state2.contract_state(contract_id).unwrap();
+
+        let fungibles = contract1.fungible_all().collect::<Vec<_>>();
+        assert_eq!(fungibles.len(), 5);
+        assert!(fungibles.is_sorted());
+        assert_eq!(fungibles, contract2.fungible_all().collect::<Vec<_>>());
+        assert!(contract1.data_all().eq(contract2.data_all()));
+        assert!(contract1.rights_all().eq(contract2.rights_all()));
+    }
+
+    #[test]
+    fn consignment_progress() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let mut consignment: Consignment<true> = strict_dumb!();
+        consignment.transfer = true;
+        consignment.schema = schema;
+        consignment.genesis = genesis;
+        let witness_ids =
+            [1u8, 2, 3].map(|no| Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap());
+        consignment.bundles = LargeVec::from_iter_checked(witness_ids.iter().enumerate().map(
+            |(nonce, witness_id)| {
+                let mut witness_bundle: WitnessBundle = strict_dumb!();
+                witness_bundle.pub_witness = PubWitness::new(*witness_id);
+                witness_bundle.bundle = bundle(transition(contract_id, nonce as u64, &[(0, 10)]));
+                witness_bundle
+            },
+        ));
+
+        let mut reports = vec![];
+        let mut state = State::new(MemState::in_memory());
+        state
+            .update_from_consignment_with_progress(
+                &consignment,
+                OfflineResolver::new(ChainNet::BitcoinRegtest),
+                Some(&mut |progress| reports.push(progress)),
+            )
+            .unwrap();
+
+        assert_eq!(reports.len(), consignment.bundles.len());
+        for (no, progress) in reports.into_iter().enumerate() {
+            assert_eq!(progress, ValidationProgress {
+                bundles_processed: no,
+                bundles_total: 3,
+                witness_id: witness_ids[no],
+            });
+        }
+    }
+
+    #[test]
+    fn simulate_consignment() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let contract_id = genesis.contract_id();
+        let mut consignment: Consignment<true> = strict_dumb!();
+        consignment.transfer = true;
+        consignment.schema = schema;
+        consignment.genesis = genesis;
+        let witness_ids =
+            [1u8, 2, 3].map(|no| Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap());
+        consignment.bundles = LargeVec::from_iter_checked(witness_ids.iter().enumerate().map(
+            |(nonce, witness_id)| {
+                let mut witness_bundle: WitnessBundle = strict_dumb!();
+                witness_bundle.pub_witness = PubWitness::new(*witness_id);
+                witness_bundle.bundle = bundle(transition(contract_id, nonce as u64, &[(0, 10)]));
+                witness_bundle
+            },
+        ));
+        let mut known = consignment.clone();
+        known.bundles = LargeVec::from_iter_checked(consignment.bundles.iter().take(1).cloned());
+
+        let resolver = MockResolver::resolved(WitnessOrd::Tentative);
+        let mut state = State::new(MemState::in_memory());
+        state.update_from_consignment(&known, &resolver).unwrap();
+        let fungible_before = state
+            .contract_state(contract_id)
+            .unwrap()
+            .fungible_all()
+            .count();
+
+        let diff = state.simulate_consignment(&consignment, &resolver).unwrap();
+        assert_eq!(diff.contract_id, contract_id);
+        assert!(!diff.new_contract);
+        assert_eq!(diff.added_fungible.len(), 2);
+        assert_eq!(
+            diff.new_witnesses.keys().copied().collect::<Vec<_>>(),
+            witness_ids[1..].to_vec()
+        );
+        assert!(diff.invalid_bundles.is_empty());
+        assert!(!diff.is_empty());
+
+        assert_eq!(
+            state
+                .contract_state(contract_id)
+                .unwrap()
+                .fungible_all()
+                .count(),
+            fungible_before
+        );
+        assert_eq!(state.as_provider().witnesses().len(), 1);
+        assert!(state
+            .simulate_consignment(&known, &resolver)
+            .unwrap()
+            .is_empty());
+
+        // allocations of the bundles invalid in the state are not reported as added
+        let invalid_bundle = known.bundles[0].bundle.bundle_id();
+        state
+            .update_bundle(invalid_bundle, Some(InvalidReason::default()))
+            .unwrap();
+        let diff = state.simulate_consignment(&consignment, &resolver).unwrap();
+        assert_eq!(diff.added_fungible.len(), 2);
+        assert!(diff.invalid_bundles.is_empty());
+    }
+
+    #[test]
+    fn seal_secret_lookup() {
+        let mut stash = MemStash::in_memory();
+        let seals = (0..100u32)
+            .map(|vout| GraphSeal::with_blinded_vout(vout, vout as u64))
+            .collect::<Vec<_>>();
+        for seal in &seals {
+            assert!(stash.add_secret_seal(*seal).unwrap());
+        }
+        assert!(!stash.add_secret_seal(seals[0]).unwrap());
+        assert_eq!(stash.secret_seals().unwrap().count(), 100);
+
+        for seal in &seals {
+            assert_eq!(stash.seal_secret(seal.conceal()).unwrap(), Some(*seal));
+            // the seals are indexed by their concealed form, so the lookup doesn't scan them
+            assert_eq!(stash.secret_seals.get(&seal.conceal()), Some(seal));
+        }
+        let unknown = GraphSeal::with_blinded_vout(0u32, 1000).conceal();
+        assert_eq!(stash.seal_secret(unknown).unwrap(), None);
+    }
+
+    #[test]
+    fn descendant_bundles() {
+        let opid = |no: u8| OpId::from_byte_array([no; 32]);
+        let bundle_id = |no: u8| BundleId::from_byte_array([no; 32]);
+        let opout = |no: u8| Opout::new(opid(no), AssignmentType::with(0), 0);
+        let mut index = MemIndex::in_memory();
+        // genesis 0 is spent by bundle 1, which is spent by bundles 2 and 3; bundle 2 is spent by
+        // bundle 4, which is also spending bundle 3
+        for (op, bundle, inputs) in
+            [(1, 1, vec![0]), (2, 2, vec![1]), (3, 3, vec![1]), (4, 4, vec![2, 3])]
+        {
+            index
+                .register_operation(opid(op), bundle_id(bundle), inputs.iter().map(|no| opout(*no)))
+                .unwrap();
+            for input in inputs {
+                index
+                    .register_spending(opid(input), bundle_id(bundle))
+                    .unwrap();
+            }
+        }
+
+        assert_eq!(index.descendant_bundles(opid(0)).unwrap(), vec![
+            bundle_id(1),
+            bundle_id(2),
+            bundle_id(3),
+            bundle_id(4)
+        ]);
+        assert_eq!(index.descendant_bundles(opid(2)).unwrap(), vec![bundle_id(4)]);
+        assert!(index.descendant_bundles(opid(4)).unwrap().is_empty());
+
+        // a cycle doesn't make the traversal loop forever
+        index.register_spending(opid(4), bundle_id(1)).unwrap();
+        assert_eq!(index.descendant_bundles(opid(2)).unwrap(), vec![
+            bundle_id(4),
+            bundle_id(1),
+            bundle_id(2),
+            bundle_id(3)
+        ]);
+    }
+
+    #[test]
+    fn stash_stats() {
+        let mut stash = MemStash::in_memory();
+        let empty = stash.stats();
+        assert_eq!(empty, StashStats {
+            encoded_size: empty.encoded_size,
+            ..default!()
+        });
+
+        let schema = schema();
+        stash.replace_schema(schema.clone()).unwrap();
+        stash.replace_genesis(genesis(&schema)).unwrap();
+        for nonce in 0..3 {
+            stash
+                .replace_bundle(bundle(transition(genesis(&schema).contract_id(), nonce, &[(
+                    0, 1,
+                )])))
+                .unwrap();
+        }
+        for vout in 0..5u32 {
+            stash
+                .add_secret_seal(GraphSeal::with_blinded_vout(vout, 0))
+                .unwrap();
+        }
+
+        let stats = stash.stats();
+        assert_eq!(stats.schemata, 1);
+        assert_eq!(stats.geneses, 1);
+        assert_eq!(stats.bundles, 3);
+        assert_eq!(stats.witnesses, 0);
+        assert_eq!(stats.secret_seals, 5);
+        assert_eq!(stats.libs, 0);
+        assert_eq!(stats.encoded_size, stash.to_strict_serialized::<U32>().unwrap().len());
+        assert!(stats.encoded_size > empty.encoded_size);
+    }
+
+    #[test]
+    fn stash_bulk_replace() {
+        let mut stash = MemStash::in_memory();
+        let contract_id = genesis(&schema()).contract_id();
+        let bundles = (0..100)
+            .map(|nonce| bundle(transition(contract_id, nonce, &[(0, 1)])))
+            .collect::<Vec<_>>();
+        assert_eq!(stash.replace_bundles(bundles[..60].to_vec()).unwrap(), 60);
+        assert_eq!(stash.replace_bundles(bundles).unwrap(), 40);
+        assert_eq!(stash.bundle_ids().unwrap().len(), 100);
+
+        let witnesses = (1..=3).map(|no| {
+            let txid = Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap();
+            SealWitness::new(
+                PubWitness::Txid(txid),
+                strict_dumb!(),
+                DbcProof::Opret(OpretProof::default()),
+            )
+        });
+        assert_eq!(stash.replace_witnesses(witnesses.clone()).unwrap(), 3);
+        assert_eq!(stash.replace_witnesses(witnesses).unwrap(), 0);
+        assert_eq!(stash.witness_ids().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn stash_listing_len() {
+        let mut stash = MemStash::in_memory();
+        let schema = schema();
+        let contract_id = genesis(&schema).contract_id();
+        stash.replace_schema(schema.clone()).unwrap();
+        stash.replace_genesis(genesis(&schema)).unwrap();
+        for nonce in 0..4 {
+            stash
+                .replace_bundle(bundle(transition(contract_id, nonce, &[(0, 1)])))
+                .unwrap();
+        }
+        for no in 1..=7 {
+            let txid = Txid::from_str(&format!("{no:02x}").repeat(32)).unwrap();
+            let proof = DbcProof::Opret(OpretProof::default());
+            stash
+                .replace_witness(SealWitness::new(PubWitness::Txid(txid), strict_dumb!(), proof))
+                .unwrap();
+        }
+
+        let witness_ids = stash.witness_ids().unwrap();
+        assert_eq!(witness_ids.len(), 7);
+        assert_eq!(witness_ids.count(), 7);
+        assert_eq!(stash.bundle_ids().unwrap().len(), 4);
+        assert_eq!(stash.geneses().unwrap().len(), 1);
+        assert_eq!(stash.schemata().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn prune_revealed_seals() {
+        let mut stash = MemStash::in_memory();
+        let seal = GraphSeal::with_blinded_vout(0u32, 1);
+        let other = GraphSeal::with_blinded_vout(1u32, 2);
+        stash.add_secret_seal(seal).unwrap();
+        stash.add_secret_seal(other).unwrap();
+
+        let unknown = GraphSeal::with_blinded_vout(2u32, 3).conceal();
+        assert_eq!(
+            stash
+                .prune_revealed_seals([seal.conceal(), unknown])
+                .unwrap(),
+            1
+        );
+        assert_eq!(stash.seal_secret(seal.conceal()).unwrap(), None);
+        assert_eq!(stash.seal_secret(other.conceal()).unwrap(), Some(other));
+        assert_eq!(stash.prune_revealed_seals([seal.conceal()]).unwrap(), 0);
+    }
+}
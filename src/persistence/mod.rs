@@ -34,6 +34,7 @@ mod stock;
 mod stash;
 mod state;
 mod index;
+mod transaction;
 
 mod memory;
 #[cfg(feature = "fs")]
@@ -44,20 +45,24 @@ pub use index::{
     IndexWriteError, IndexWriteProvider,
 };
 pub use memory::{
-    MemContract, MemContractState, MemError, MemGlobalState, MemIndex, MemStash, MemState,
+    BundleStats, ConcealedData, ContractSnapshot, MemContract, MemContractState, MemContractWriter,
+    MemError, MemGlobalState, MemIndex, MemStash, MemState, OutpointSummary, StashStats,
+    StateChange,
 };
 pub use stash::{
     ProviderError as StashProviderError, Stash, StashDataError, StashError, StashInconsistency,
     StashProvider, StashReadProvider, StashWriteProvider,
 };
 pub use state::{
-    ContractStateRead, ContractStateWrite, State, StateError, StateInconsistency, StateProvider,
-    StateReadProvider, StateWriteProvider,
+    height_sorted, ContractStateRead, ContractStateWrite, InvalidReason, State, StateDiff,
+    StateError, StateInconsistency, StateProvider, StateReadProvider, StateWriteProvider,
+    ValidationProgress,
 };
 pub use stock::{
     ComposeError, ConsignError, ContractAssignments, FasciaError, InputError as StockInputError,
     Stock, StockError, StockErrorAll, StockErrorMem, UpdateRes,
 };
+pub use transaction::CombinedTransaction;
 
 pub trait StoreTransaction {
     type TransactionErr: std::error::Error;
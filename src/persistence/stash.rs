@@ -19,29 +19,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::fmt::Debug;
 
 use aluvm::library::{Lib, LibId};
 use amplify::confinement::Confined;
-use amplify::ByteArray;
+use amplify::{ByteArray, Wrapper};
 use nonasync::persistence::{CloneNoPersistence, Persisting};
 use rgb::commit_verify::mpc::{self, MerkleBlock};
 use rgb::dbc::tapret::TapretCommitment;
+use rgb::dbc::Method as DbcMethod;
 use rgb::seals::txout::CloseMethod;
-use rgb::validation::Scripts;
+use rgb::validation::{DbcProof, OpRef, Scripts};
 use rgb::{
     BundleId, ChainNet, ContractId, Genesis, GraphSeal, Identity, OpId, Schema, SchemaId,
     TransitionBundle, TransitionType, Txid,
 };
 use strict_types::typesys::UnknownType;
-use strict_types::{FieldName, TypeSystem};
+use strict_types::{FieldName, SemId, TypeSystem};
 
 use crate::containers::{
     Consignment, ConsignmentExt, Kit, SealWitness, SealWitnessMergeError, WitnessBundle,
 };
-use crate::contract::{ContractBuilder, TransitionBuilder};
+use crate::contract::{ContractBuilder, GlobalOut, TransitionBuilder};
 use crate::persistence::StoreTransaction;
 use crate::{MergeReveal, MergeRevealError, SecretSeal};
 
@@ -122,6 +123,10 @@ pub enum StashDataError {
     /// schema {0} uses too many AluVM libraries.
     TooManyLibs(SchemaId),
 
+    /// schema references types {0:?} which are absent in the stash type system; perhaps you
+    /// need to import them first.
+    MissingTypes(Vec<SemId>),
+
     #[from]
     #[display(inner)]
     UnknownType(UnknownType),
@@ -171,14 +176,18 @@ impl<P: StashProvider> Stash<P> {
     #[doc(hidden)]
     pub(super) fn as_provider_mut(&mut self) -> &mut P { &mut self.provider }
 
-    pub(super) fn schemata(&self) -> Result<impl Iterator<Item = &Schema> + '_, StashError<P>> {
+    pub(super) fn schemata(
+        &self,
+    ) -> Result<impl ExactSizeIterator<Item = &Schema> + '_, StashError<P>> {
         self.provider.schemata().map_err(StashError::ReadProvider)
     }
     pub(super) fn schema(&self, schema_id: SchemaId) -> Result<&Schema, StashError<P>> {
         Ok(self.provider.schema(schema_id)?)
     }
 
-    pub(super) fn geneses(&self) -> Result<impl Iterator<Item = &Genesis> + '_, StashError<P>> {
+    pub(super) fn geneses(
+        &self,
+    ) -> Result<impl ExactSizeIterator<Item = &Genesis> + '_, StashError<P>> {
         self.provider.geneses().map_err(StashError::ReadProvider)
     }
     pub(super) fn genesis(&self, contract_id: ContractId) -> Result<&Genesis, StashError<P>> {
@@ -263,14 +272,40 @@ impl<P: StashProvider> Stash<P> {
         }
 
         for schema in kit.schemata {
-            self.provider
-                .replace_schema(schema)
-                .map_err(StashError::WriteProvider)?;
+            self.register_schema(schema)?;
         }
 
         Ok(())
     }
 
+    /// Registers the schema, checking first that all the types it references are present in
+    /// the stash type system.
+    pub(super) fn register_schema(&mut self, schema: Schema) -> Result<bool, StashError<P>> {
+        let type_system = self
+            .provider
+            .type_system()
+            .map_err(StashError::ReadProvider)?;
+        let missing = schema
+            .types()
+            .filter(|sem_id| type_system.extract([*sem_id]).is_err())
+            .collect::<BTreeSet<_>>();
+        if !missing.is_empty() {
+            return Err(StashDataError::MissingTypes(missing.into_iter().collect()).into());
+        }
+        self.provider
+            .replace_schema(schema)
+            .map_err(StashError::WriteProvider)
+    }
+
+    pub(super) fn forget_contract(
+        &mut self,
+        contract_id: ContractId,
+    ) -> Result<usize, StashError<P>> {
+        self.provider
+            .forget_contract(contract_id)
+            .map_err(StashError::WriteProvider)
+    }
+
     pub(super) fn resolve_secrets<const TRANSFER: bool>(
         &self,
         mut consignment: Consignment<TRANSFER>,
@@ -412,11 +447,15 @@ pub trait StashReadProvider {
     fn type_system(&self) -> Result<&TypeSystem, Self::Error>;
     fn lib(&self, id: LibId) -> Result<&Lib, ProviderError<Self::Error>>;
 
-    fn schemata(&self) -> Result<impl Iterator<Item = &Schema>, Self::Error>;
+    fn schemata(&self) -> Result<impl ExactSizeIterator<Item = &Schema>, Self::Error>;
     fn schema(&self, schema_id: SchemaId) -> Result<&Schema, ProviderError<Self::Error>>;
-    fn geneses(&self) -> Result<impl Iterator<Item = &Genesis>, Self::Error>;
+    fn geneses(&self) -> Result<impl ExactSizeIterator<Item = &Genesis>, Self::Error>;
     fn genesis(&self, contract_id: ContractId) -> Result<&Genesis, ProviderError<Self::Error>>;
 
+    /// Returns the schema of the contract, looking up its genesis first.
+    ///
+    /// Fails with [`StashInconsistency::ContractAbsent`] if the contract is unknown and with
+    /// [`StashInconsistency::SchemaAbsent`] if the contract is known but its schema is not.
     fn contract_schema(
         &self,
         contract_id: ContractId,
@@ -425,12 +464,78 @@ pub trait StashReadProvider {
         self.schema(genesis.schema_id)
     }
 
-    fn witness_ids(&self) -> Result<impl Iterator<Item = Txid>, Self::Error>;
-    fn bundle_ids(&self) -> Result<impl Iterator<Item = BundleId>, Self::Error>;
+    /// Returns the schema of the contract, sparing the callers the lookup of its genesis; an alias
+    /// of [`Self::contract_schema`] failing with the same errors.
+    fn schema_for_contract(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<&Schema, ProviderError<Self::Error>> {
+        self.contract_schema(contract_id)
+    }
+
+    /// Returns the operation which defined the global state at `out`.
+    ///
+    /// A global state defined by a state transition is looked up through all the known bundles,
+    /// so this is meant for analytics rather than for frequent queries.
+    fn operation_for_global(
+        &self,
+        out: &GlobalOut,
+    ) -> Result<OpRef<'_>, ProviderError<Self::Error>> {
+        if out.is_genesis() {
+            let contract_id = ContractId::from_inner(out.opid.into_inner());
+            return self.genesis(contract_id).map(OpRef::Genesis);
+        }
+        for bundle_id in self
+            .bundle_ids()
+            .map_err(ProviderError::Connectivity)?
+            .collect::<Vec<_>>()
+        {
+            if let Some(known) = self
+                .bundle(bundle_id)?
+                .known_transitions
+                .iter()
+                .find(|known| known.opid == out.opid)
+            {
+                return Ok(OpRef::Transition(&known.transition));
+            }
+        }
+        Err(StashInconsistency::OperationAbsent(out.opid).into())
+    }
+
+    fn witness_ids(&self) -> Result<impl ExactSizeIterator<Item = Txid>, Self::Error>;
+    fn bundle_ids(&self) -> Result<impl ExactSizeIterator<Item = BundleId>, Self::Error>;
     fn bundle(&self, bundle_id: BundleId) -> Result<&TransitionBundle, ProviderError<Self::Error>>;
+    /// Returns all known bundles containing transitions of the given contract, ordered by their
+    /// [`BundleId`].
+    fn contract_bundles(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<impl Iterator<Item = &TransitionBundle>, ProviderError<Self::Error>>;
     fn witness(&self, witness_id: Txid) -> Result<&SealWitness, ProviderError<Self::Error>>;
 
+    /// Checks whether the schema is known, without constructing an error if it is absent.
+    fn contains_schema(&self, schema_id: SchemaId) -> Result<bool, Self::Error>;
+    /// Checks whether the contract genesis is known, without constructing an error if it is
+    /// absent.
+    fn contains_genesis(&self, contract_id: ContractId) -> Result<bool, Self::Error>;
+    /// Checks whether the bundle is known, without constructing an error if it is absent.
+    fn contains_bundle(&self, bundle_id: BundleId) -> Result<bool, Self::Error>;
+    /// Checks whether the witness is known, without constructing an error if it is absent.
+    fn contains_witness(&self, witness_id: Txid) -> Result<bool, Self::Error>;
+
     fn taprets(&self) -> Result<impl Iterator<Item = (Txid, TapretCommitment)>, Self::Error>;
+    /// Iterates over the witnesses with an opret proof, returning the MPC commitment placed in
+    /// their `OP_RETURN` output.
+    fn oprets(&self) -> Result<impl Iterator<Item = (Txid, mpc::Commitment)>, Self::Error>;
+    /// Classifies all the stored witnesses by the DBC method of their proof.
+    fn dbc_proofs_by_method(&self) -> Result<BTreeMap<DbcMethod, Vec<Txid>>, Self::Error>;
+    /// Returns the DBC proof anchoring the witness with the given id, if the witness is known.
+    fn dbc_proof(&self, witness_id: Txid) -> Result<Option<&DbcProof>, Self::Error>;
+    /// Iterates over the ids of the known witnesses whose DBC proof matches the predicate.
+    fn witnesses_by_dbc(
+        &self,
+        pred: impl Fn(&DbcProof) -> bool,
+    ) -> Result<impl Iterator<Item = Txid>, Self::Error>;
     fn seal_secret(&self, secret: SecretSeal) -> Result<Option<GraphSeal>, Self::Error>;
     fn secret_seals(&self) -> Result<impl Iterator<Item = GraphSeal>, Self::Error>;
 }
@@ -443,8 +548,38 @@ pub trait StashWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
     fn replace_bundle(&mut self, bundle: TransitionBundle) -> Result<bool, Self::Error>;
     fn replace_witness(&mut self, witness: SealWitness) -> Result<bool, Self::Error>;
 
+    /// Stores all the `bundles` within a single write, returning the number of the bundles
+    /// which were not known before.
+    fn replace_bundles(
+        &mut self,
+        bundles: impl IntoIterator<Item = TransitionBundle>,
+    ) -> Result<usize, Self::Error> {
+        let mut added = 0;
+        for bundle in bundles {
+            added += self.replace_bundle(bundle)? as usize;
+        }
+        Ok(added)
+    }
+
+    /// Stores all the `witnesses` within a single write, as with [`Self::replace_witness`],
+    /// returning the number of the witnesses which were not known before.
+    fn replace_witnesses(
+        &mut self,
+        witnesses: impl IntoIterator<Item = SealWitness>,
+    ) -> Result<usize, Self::Error> {
+        let mut added = 0;
+        for witness in witnesses {
+            added += self.replace_witness(witness)? as usize;
+        }
+        Ok(added)
+    }
+
     fn replace_lib(&mut self, lib: Lib) -> Result<bool, Self::Error>;
     fn consume_types(&mut self, types: TypeSystem) -> Result<(), Self::Error>;
 
     fn add_secret_seal(&mut self, seal: GraphSeal) -> Result<bool, Self::Error>;
+
+    /// Removes the genesis and all bundles of the contract, together with the witnesses which are
+    /// not referenced by bundles of other contracts. Returns the number of removed witnesses.
+    fn forget_contract(&mut self, contract_id: ContractId) -> Result<usize, Self::Error>;
 }
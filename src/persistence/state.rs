@@ -20,21 +20,29 @@
 // limitations under the License.
 
 use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::fmt::Debug;
 
 use amplify::confinement::{LargeOrdMap, LargeOrdSet};
+use amplify::num::u24;
 use nonasync::persistence::{CloneNoPersistence, Persisting};
+use rgb::bitcoin::OutPoint as Outpoint;
 use rgb::validation::{ResolveWitness, WitnessOrdProvider, WitnessResolverError};
-use rgb::vm::{ContractStateAccess, WitnessOrd};
+use rgb::vm::{ContractStateAccess, UnknownGlobalStateType, WitnessOrd};
 use rgb::{
-    BundleId, ContractId, Genesis, KnownTransition, RevealedData, RevealedValue, Schema, SchemaId,
-    Transition, TransitionBundle, Txid, VoidState,
+    AssignmentType, BundleId, ContractId, FungibleState, Genesis, GlobalStateType, KnownTransition,
+    Opout, RevealedData, RevealedValue, Schema, SchemaId, SecretSeal, Transition, TransitionBundle,
+    Txid, VoidState,
 };
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+use strict_encoding::DefaultBasedStrictDumb;
 
-use crate::containers::{ConsignmentExt, ToWitnessId};
-use crate::contract::OutputAssignment;
-use crate::persistence::StoreTransaction;
+use crate::containers::{ConsignmentExt, ToWitnessId, WitnessBundle};
+use crate::contract::{OutputAssignment, OwnedAllocation};
+use crate::persistence::{IndexReadProvider, StoreTransaction};
+use crate::LIB_NAME_RGB_STORAGE;
 
 #[derive(Debug, Display, Error, From)]
 #[display(inner)]
@@ -53,6 +61,14 @@ pub enum StateError<P: StateProvider> {
     /// state transition bundle.
     AbsentValidWitness,
 
+    /// global state of type {ty} can't hold more than {limit} items.
+    #[display(doc_comments)]
+    GlobalStateOverflow { ty: GlobalStateType, limit: u24 },
+
+    /// global state type {0} is not defined by the contract schema.
+    #[display(doc_comments)]
+    UnknownGlobalType(GlobalStateType),
+
     /// {0}
     ///
     /// It may happen due to RGB ops library bug, or indicate internal
@@ -71,6 +87,79 @@ pub enum StateInconsistency {
     AbsentWitness(Txid),
 }
 
+/// Changes which accepting a consignment would make to the state, computed by
+/// [`State::simulate_consignment`] without modifying the state.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct StateDiff {
+    /// Contract of the consignment.
+    pub contract_id: ContractId,
+    /// Whether the contract is not known to the state yet.
+    pub new_contract: bool,
+    /// Rights allocations which would be added to the contract state.
+    pub added_rights: Vec<OutputAssignment<VoidState>>,
+    /// Fungible allocations which would be added to the contract state.
+    pub added_fungible: Vec<OutputAssignment<RevealedValue>>,
+    /// Structured data allocations which would be added to the contract state.
+    pub added_data: Vec<OutputAssignment<RevealedData>>,
+    /// Witnesses which would become known, with their resolved ordinals.
+    pub new_witnesses: BTreeMap<Txid, WitnessOrd>,
+    /// Bundles which would become invalid.
+    pub invalid_bundles: BTreeSet<BundleId>,
+}
+
+impl StateDiff {
+    /// Detects whether accepting the consignment would leave the state unchanged.
+    pub fn is_empty(&self) -> bool {
+        !self.new_contract
+            && self.added_rights.is_empty()
+            && self.added_fungible.is_empty()
+            && self.added_data.is_empty()
+            && self.new_witnesses.is_empty()
+            && self.invalid_bundles.is_empty()
+    }
+}
+
+/// Reason why a bundle was marked as invalid, see [`StateWriteProvider::update_bundle`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STORAGE, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display(doc_comments)]
+#[repr(u8)]
+pub enum InvalidReason {
+    /// none of the bundle witnesses is valid, since they were archived or ignored.
+    #[default]
+    WitnessInvalid = 0,
+    /// the bundle spends the outputs of an invalid bundle.
+    AncestorInvalid = 1,
+    /// the bundle failed the validation.
+    ValidationFailed = 2,
+    /// the bundle was disabled by the user.
+    ManuallyDisabled = 3,
+}
+
+impl DefaultBasedStrictDumb for InvalidReason {}
+
+/// Progress of a consignment being accepted, reported before each of its bundles is processed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ValidationProgress {
+    /// Number of the consignment bundles which were already processed.
+    pub bundles_processed: usize,
+    /// Total number of the consignment bundles.
+    pub bundles_total: usize,
+    /// Witness of the bundle being processed, which is going to be resolved.
+    pub witness_id: Txid,
+}
+
 #[derive(Debug)]
 pub struct State<P: StateProvider> {
     provider: P,
@@ -113,6 +202,27 @@ impl<P: StateProvider> State<P> {
             .map_err(StateError::ReadProvider)
     }
 
+    /// Returns the provided witnesses together with their ordinals, sorted from the most
+    /// confirmed to the least one (ties are resolved by the witness id).
+    pub fn witnesses_by_ord(
+        &self,
+        witness_ids: impl IntoIterator<Item = impl Borrow<Txid>>,
+    ) -> Result<Vec<(Txid, WitnessOrd)>, StateError<P>> {
+        let witnesses = self.as_provider().witnesses();
+        let mut ords = witness_ids
+            .into_iter()
+            .map(|id| {
+                let id = *id.borrow();
+                witnesses
+                    .get(&id)
+                    .map(|ord| (id, *ord))
+                    .ok_or(StateError::Inconsistency(StateInconsistency::AbsentWitness(id)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        ords.sort_by_key(|(id, ord)| (*ord, *id));
+        Ok(ords)
+    }
+
     pub fn select_valid_witness(
         &self,
         witness_ids: impl IntoIterator<Item = impl Borrow<Txid>>,
@@ -168,15 +278,34 @@ impl<P: StateProvider> State<P> {
         consignment: impl ConsignmentExt,
         resolver: R,
     ) -> Result<(), StateError<P>> {
+        self.update_from_consignment_with_progress(consignment, resolver, None)
+    }
+
+    /// Same as [`Self::update_from_consignment`], but reports the [`ValidationProgress`] to the
+    /// optional `progress` callback before processing each bundle of the consignment.
+    pub fn update_from_consignment_with_progress<R: ResolveWitness>(
+        &mut self,
+        consignment: impl ConsignmentExt,
+        resolver: R,
+        mut progress: Option<&mut dyn FnMut(ValidationProgress)>,
+    ) -> Result<(), StateError<P>> {
+        let bundles_total = consignment.bundled_witnesses().count();
         let mut state = self
             .as_provider_mut()
             .register_contract(consignment.schema(), consignment.genesis())
             .map_err(StateError::WriteProvider)?;
-        for witness_bundle in consignment.bundled_witnesses() {
+        for (bundles_processed, witness_bundle) in consignment.bundled_witnesses().enumerate() {
+            let witness_id = witness_bundle.pub_witness.to_witness_id();
+            if let Some(progress) = progress.as_deref_mut() {
+                progress(ValidationProgress {
+                    bundles_processed,
+                    bundles_total,
+                    witness_id,
+                });
+            }
             let bundle = witness_bundle.bundle();
             let bundle_id = bundle.bundle_id();
             for KnownTransition { transition, .. } in &bundle.known_transitions {
-                let witness_id = witness_bundle.pub_witness.to_witness_id();
                 let witness_ord = resolver
                     .resolve_witness(witness_id)
                     .map_err(|e| StateError::Resolver(witness_id, e))?
@@ -191,6 +320,76 @@ impl<P: StateProvider> State<P> {
         Ok(())
     }
 
+    /// Computes the changes [`Self::update_from_consignment`] would make to the state, applying
+    /// the consignment to a copy of the state and leaving the original one untouched.
+    pub fn simulate_consignment<R: ResolveWitness>(
+        &self,
+        consignment: impl ConsignmentExt,
+        resolver: R,
+    ) -> Result<StateDiff, StateError<P>> {
+        let contract_id = consignment.contract_id();
+        let invalid_bundles = self.provider.invalid_bundles();
+        let mut simulated = self.clone_no_persistence();
+        // the copy may not keep the invalid bundles, while they must be excluded from its state
+        // as they are from the original one
+        for bundle_id in &invalid_bundles {
+            let reason = self.provider.bundle_invalid_reason(*bundle_id);
+            simulated.update_bundle(*bundle_id, Some(reason.unwrap_or_default()))?;
+        }
+        simulated.update_from_consignment(&consignment, resolver)?;
+
+        let witnesses = self.provider.witnesses();
+        let new_witnesses = simulated
+            .provider
+            .witnesses()
+            .into_iter()
+            .filter(|(id, _)| !witnesses.contains_key(id))
+            .collect();
+        let new_invalid_bundles = simulated
+            .provider
+            .invalid_bundles()
+            .into_iter()
+            .filter(|id| !invalid_bundles.contains(id))
+            .collect();
+
+        let new_contract = !self.provider.contract_exists(contract_id);
+        let before = if new_contract { None } else { Some(self.contract_state(contract_id)?) };
+        let rights = before
+            .iter()
+            .flat_map(|s| s.rights_all())
+            .collect::<BTreeSet<_>>();
+        let fungible = before
+            .iter()
+            .flat_map(|s| s.fungible_all())
+            .collect::<BTreeSet<_>>();
+        let data = before
+            .iter()
+            .flat_map(|s| s.data_all())
+            .collect::<BTreeSet<_>>();
+        let after = simulated.contract_state(contract_id)?;
+        Ok(StateDiff {
+            contract_id,
+            new_contract,
+            added_rights: after
+                .rights_all()
+                .filter(|a| !rights.contains(a))
+                .copied()
+                .collect(),
+            added_fungible: after
+                .fungible_all()
+                .filter(|a| !fungible.contains(a))
+                .copied()
+                .collect(),
+            added_data: after
+                .data_all()
+                .filter(|a| !data.contains(a))
+                .cloned()
+                .collect(),
+            new_witnesses,
+            invalid_bundles: new_invalid_bundles,
+        })
+    }
+
     pub fn upsert_witness(
         &mut self,
         witness_id: Txid,
@@ -201,9 +400,29 @@ impl<P: StateProvider> State<P> {
             .map_err(StateError::WriteProvider)
     }
 
-    pub fn update_bundle(&mut self, bundle_id: BundleId, valid: bool) -> Result<(), StateError<P>> {
+    pub fn update_bundle(
+        &mut self,
+        bundle_id: BundleId,
+        invalid: Option<InvalidReason>,
+    ) -> Result<(), StateError<P>> {
         self.provider
-            .update_bundle(bundle_id, valid)
+            .update_bundle(bundle_id, invalid)
+            .map_err(StateError::WriteProvider)
+    }
+
+    pub fn handle_reorg(
+        &mut self,
+        witness_id: Txid,
+        new_ord: WitnessOrd,
+    ) -> Result<(), StateError<P>> {
+        self.provider
+            .handle_reorg(witness_id, new_ord)
+            .map_err(StateError::WriteProvider)
+    }
+
+    pub fn forget_contract(&mut self, contract_id: ContractId) -> Result<usize, StateError<P>> {
+        self.provider
+            .forget_contract(contract_id)
             .map_err(StateError::WriteProvider)
     }
 }
@@ -241,9 +460,62 @@ pub trait StateReadProvider {
         contract_id: ContractId,
     ) -> Result<Self::ContractRead<'_>, Self::Error>;
 
+    /// Iterates over ids of all the contracts known to the state provider.
+    fn contract_ids(&self) -> Result<impl Iterator<Item = ContractId> + '_, Self::Error>;
+
+    /// Checks whether the contract is known to the state provider, without constructing its
+    /// state as [`Self::contract_state`] does.
+    ///
+    /// Default implementation scans [`Self::contract_ids`], treating errors as absence of the
+    /// contract; providers are expected to override it with a direct lookup.
+    fn contract_exists(&self, contract_id: ContractId) -> bool {
+        self.contract_ids()
+            .map(|mut ids| ids.any(|id| id == contract_id))
+            .unwrap_or_default()
+    }
+
     fn witnesses(&self) -> LargeOrdMap<Txid, WitnessOrd>;
 
+    /// Returns only the witnesses whose [`WitnessOrd`] matches the provided `filter`.
+    fn witnesses_with_status(
+        &self,
+        filter: impl Fn(&WitnessOrd) -> bool,
+    ) -> LargeOrdMap<Txid, WitnessOrd> {
+        LargeOrdMap::from_iter_checked(self.witnesses().into_iter().filter(|(_, ord)| filter(ord)))
+    }
+
+    /// Returns the witnesses which are not mined yet, i.e. the ones which should be re-resolved.
+    fn tentative_witnesses(&self) -> LargeOrdMap<Txid, WitnessOrd> {
+        self.witnesses_with_status(|ord| *ord == WitnessOrd::Tentative)
+    }
+
     fn invalid_bundles(&self) -> LargeOrdSet<BundleId>;
+
+    /// Returns the reason why the bundle was marked as invalid, or `None` if the bundle is valid.
+    fn bundle_invalid_reason(&self, bundle_id: BundleId) -> Option<InvalidReason>;
+}
+
+/// Sorts witness bundles chronologically, according to the [`WitnessOrd`] of their witnesses
+/// known to the `state` provider.
+///
+/// Mined witnesses come first, ordered by their position in the blockchain, followed by tentative
+/// witnesses and then by ignored and archived ones. Bundles whose witness is unknown to the
+/// provider are put last. Ties are broken by the witness id, so the resulting order is
+/// deterministic.
+pub fn height_sorted<'a>(
+    bundles: impl Iterator<Item = &'a WitnessBundle>,
+    state: &impl StateReadProvider,
+) -> Vec<&'a WitnessBundle> {
+    let witnesses = state.witnesses();
+    let mut bundles = bundles
+        .map(|bundle| {
+            let witness_id = bundle.witness_id();
+            let ord = witnesses.get(&witness_id).copied();
+            ((ord.is_none(), ord, witness_id), bundle)
+        })
+        .collect::<Vec<_>>();
+    bundles.sort_by_key(|(key, _)| *key);
+    bundles.into_iter().map(|(_, bundle)| bundle).collect()
 }
 
 pub trait StateWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
@@ -268,16 +540,148 @@ pub trait StateWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
         witness_ord: WitnessOrd,
     ) -> Result<(), Self::Error>;
 
-    fn update_bundle(&mut self, bundle_id: BundleId, valid: bool) -> Result<(), Self::Error>;
+    /// Marks the bundle as invalid for the given reason, or as valid if `invalid` is `None`.
+    fn update_bundle(
+        &mut self,
+        bundle_id: BundleId,
+        invalid: Option<InvalidReason>,
+    ) -> Result<(), Self::Error>;
+
+    /// Updates the ordinal of a witness whose status changed due to a blockchain re-organization.
+    ///
+    /// Providers caching data derived from witness ordinals (like balances or witness filters)
+    /// must refresh it here when the new ordinal is [`WitnessOrd::Tentative`] or
+    /// [`WitnessOrd::Archived`]. The default implementation just replaces the ordinal, which is
+    /// enough for providers computing such data on read.
+    ///
+    /// NB: this doesn't change the validity of the bundles: when a witness gets archived the
+    /// caller is responsible for marking the bundles anchored only to it (and their descendants)
+    /// as invalid, as done by `Stock::update_witnesses`.
+    fn handle_reorg(&mut self, witness_id: Txid, new_ord: WitnessOrd) -> Result<(), Self::Error> {
+        self.upsert_witness(witness_id, new_ord)
+    }
+
+    /// Removes the contract state, keeping the witnesses which are still used by other contracts.
+    /// Returns the number of removed witnesses.
+    fn forget_contract(&mut self, contract_id: ContractId) -> Result<usize, Self::Error>;
 }
 
 pub trait ContractStateRead: ContractStateAccess {
     fn contract_id(&self) -> ContractId;
     fn schema_id(&self) -> SchemaId;
     fn witness_ord(&self, witness_id: Txid) -> Option<WitnessOrd>;
+    /// Iterates over all the valid rights allocations of the contract.
+    ///
+    /// The allocations are yielded in ascending [`OutputAssignment`] order (by opout, then by
+    /// seal), independently of the order in which they were added to the state.
     fn rights_all(&self) -> impl Iterator<Item = &OutputAssignment<VoidState>>;
+    /// Iterates over all the valid fungible allocations of the contract, in the same order as
+    /// [`Self::rights_all`].
     fn fungible_all(&self) -> impl Iterator<Item = &OutputAssignment<RevealedValue>>;
+    /// Iterates over all the valid structured data allocations of the contract, in the same order
+    /// as [`Self::rights_all`].
     fn data_all(&self) -> impl Iterator<Item = &OutputAssignment<RevealedData>>;
+    /// Iterates over structured data allocations of the given assignment type whose seal is known
+    /// only in its concealed form, so that the caller may try to reveal them using the seal
+    /// secrets from the stash.
+    fn data_concealed(&self, ty: AssignmentType) -> impl Iterator<Item = (SecretSeal, Opout)>;
+    /// Returns the most recent value of the global state of type `ty`, i.e. the first item which
+    /// would be yielded by [`ContractStateAccess::global`].
+    fn global_latest(
+        &self,
+        ty: GlobalStateType,
+    ) -> Result<Option<&RevealedData>, UnknownGlobalStateType>;
+
+    /// Iterates over rights of the given assignment type, regardless of their outpoint.
+    fn rights_by_type(
+        &self,
+        ty: AssignmentType,
+    ) -> impl Iterator<Item = &OutputAssignment<VoidState>> {
+        self.rights_all()
+            .filter(move |assignment| assignment.opout.ty == ty)
+    }
+
+    /// Iterates over fungible allocations of the given assignment type, regardless of their
+    /// outpoint.
+    fn fungible_by_type(
+        &self,
+        ty: AssignmentType,
+    ) -> impl Iterator<Item = &OutputAssignment<RevealedValue>> {
+        self.fungible_all()
+            .filter(move |assignment| assignment.opout.ty == ty)
+    }
+
+    /// Iterates over structured data allocations of the given assignment type, regardless of their
+    /// outpoint.
+    fn data_by_type(
+        &self,
+        ty: AssignmentType,
+    ) -> impl Iterator<Item = &OutputAssignment<RevealedData>> {
+        self.data_all()
+            .filter(move |assignment| assignment.opout.ty == ty)
+    }
+
+    /// Lists the fungible allocations which were spent by a later operation of the contract.
+    ///
+    /// The contract state doesn't keep track of the spending of its allocations, so the spending
+    /// links are taken from the provided `index`, which must be the one kept together with this
+    /// state (as in [`Stock`](super::Stock)). An allocation is considered spent if the index knows
+    /// any operation using it as an input, regardless of the status of the spending witness.
+    fn fungible_spent<P: IndexReadProvider>(
+        &self,
+        index: &P,
+    ) -> Result<impl Iterator<Item = &OutputAssignment<RevealedValue>>, P::Error> {
+        let mut spent = Vec::new();
+        for assignment in self.fungible_all() {
+            if !index.spenders_of(assignment.opout)?.is_empty() {
+                spent.push(assignment);
+            }
+        }
+        Ok(spent.into_iter())
+    }
+
+    /// Resolves an [`Opout`], e.g. returned by an index query, into the corresponding allocation
+    /// with its state, whichever its kind is.
+    ///
+    /// Returns `None` if the opout is not allocated in the contract state or if it is filtered out
+    /// as the other allocations are.
+    fn assignment_by_opout(&self, opout: Opout) -> Option<OwnedAllocation> {
+        self.rights_by_type(opout.ty)
+            .find(|assignment| assignment.opout == opout)
+            .map(|assignment| assignment.transmute())
+            .or_else(|| {
+                self.fungible_by_type(opout.ty)
+                    .find(|assignment| assignment.opout == opout)
+                    .map(|assignment| assignment.transmute())
+            })
+            .or_else(|| {
+                self.data_by_type(opout.ty)
+                    .find(|assignment| assignment.opout == opout)
+                    .cloned()
+                    .map(OutputAssignment::transmute)
+            })
+    }
+
+    /// Sums the fungible allocations of the given assignment type.
+    ///
+    /// The sum saturates at `u64::MAX` instead of overflowing.
+    fn fungible_balance(&self, ty: AssignmentType) -> u64 {
+        self.fungible_by_type(ty)
+            .map(|assignment| FungibleState::from(assignment.state).as_u64())
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Sums the fungible allocations of the given assignment type for each outpoint.
+    ///
+    /// Each sum saturates at `u64::MAX` instead of overflowing.
+    fn fungible_balance_by_outpoint(&self, ty: AssignmentType) -> BTreeMap<Outpoint, u64> {
+        let mut balances = BTreeMap::<Outpoint, u64>::new();
+        for assignment in self.fungible_by_type(ty) {
+            let balance = balances.entry(assignment.seal.to_outpoint()).or_default();
+            *balance = balance.saturating_add(FungibleState::from(assignment.state).as_u64());
+        }
+        balances
+    }
 }
 
 pub trait ContractStateWrite {
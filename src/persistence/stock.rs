@@ -26,6 +26,7 @@ use std::fmt::Debug;
 use std::num::NonZeroU32;
 
 use amplify::confinement::{Confined, LargeOrdSet};
+use amplify::num::u24;
 use nonasync::persistence::{CloneNoPersistence, PersistenceError, PersistenceProvider};
 use rgb::bitcoin::{OutPoint as Outpoint, Txid};
 use rgb::dbc::{Anchor, Proof};
@@ -35,17 +36,18 @@ use rgb::validation::{
 };
 use rgb::vm::WitnessOrd;
 use rgb::{
-    validation, AssignmentType, BundleId, ChainNet, ContractId, Genesis, GraphSeal, Identity,
-    KnownTransition, OpId, Operation, Opout, OutputSeal, Schema, SchemaId, SecretSeal, Transition,
-    TransitionType, TxoSeal, UnrelatedTransition,
+    validation, AssignmentType, BundleId, ChainNet, ContractId, Genesis, GlobalStateType,
+    GraphSeal, Identity, KnownTransition, OpId, Operation, Opout, OutputSeal, Schema, SchemaId,
+    SecretSeal, Transition, TransitionType, TxoSeal, UnrelatedTransition,
 };
 use strict_types::FieldName;
 
 use super::{
     ContractStateRead, Index, IndexError, IndexInconsistency, IndexProvider, IndexReadProvider,
-    IndexWriteProvider, MemIndex, MemStash, MemState, Stash, StashDataError, StashError,
-    StashInconsistency, StashProvider, StashReadProvider, StashWriteProvider, State, StateError,
-    StateInconsistency, StateProvider, StateReadProvider, StateWriteProvider, StoreTransaction,
+    IndexWriteProvider, InvalidReason, MemIndex, MemStash, MemState, Stash, StashDataError,
+    StashError, StashInconsistency, StashProvider, StashReadProvider, StashWriteProvider, State,
+    StateDiff, StateError, StateInconsistency, StateProvider, StateReadProvider,
+    StateWriteProvider, StoreTransaction, ValidationProgress,
 };
 use crate::containers::{
     Consignment, ContainerVer, Contract, Fascia, Kit, SealWitness, SecretSeals, Transfer,
@@ -118,6 +120,17 @@ pub enum StockError<
     /// Unable to sort bundles because of data inconsistency.
     BundlesInconsistency,
 
+    /// global state of type {ty} can't hold more than {limit} items.
+    #[display(doc_comments)]
+    GlobalStateOverflow {
+        ty: GlobalStateType,
+        limit: u24,
+    },
+
+    /// global state type {0} is not defined by the contract schema.
+    #[display(doc_comments)]
+    UnknownGlobalType(GlobalStateType),
+
     /// witness {0} can't be resolved: {1}
     WitnessUnresolved(Txid, WitnessResolverError),
 
@@ -149,6 +162,10 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider, E: Error> From<StateE
             StateError::Inconsistency(e) => Self::StateInconsistency(e),
             StateError::Resolver(id, e) => Self::WitnessUnresolved(id, e),
             StateError::AbsentValidWitness => Self::AbsentValidWitness,
+            StateError::GlobalStateOverflow { ty, limit } => {
+                Self::GlobalStateOverflow { ty, limit }
+            }
+            StateError::UnknownGlobalType(ty) => Self::UnknownGlobalType(ty),
         }
     }
 }
@@ -304,6 +321,10 @@ macro_rules! stock_err_conv {
                     StockError::StateWrite(e) => StockError::StateWrite(e),
                     StockError::AbsentValidWitness => StockError::AbsentValidWitness,
                     StockError::BundlesInconsistency => StockError::BundlesInconsistency,
+                    StockError::GlobalStateOverflow { ty, limit } => {
+                        StockError::GlobalStateOverflow { ty, limit }
+                    }
+                    StockError::UnknownGlobalType(ty) => StockError::UnknownGlobalType(ty),
                     StockError::StashData(e) => StockError::StashData(e),
                     StockError::StashInconsistency(e) => StockError::StashInconsistency(e),
                     StockError::StateInconsistency(e) => StockError::StateInconsistency(e),
@@ -454,7 +475,9 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
     #[doc(hidden)]
     pub fn as_index_provider_mut(&mut self) -> &mut P { self.index.as_provider_mut() }
 
-    pub fn schemata(&self) -> Result<impl Iterator<Item = SchemaInfo> + '_, StockError<S, H, P>> {
+    pub fn schemata(
+        &self,
+    ) -> Result<impl ExactSizeIterator<Item = SchemaInfo> + '_, StockError<S, H, P>> {
         Ok(self.stash.schemata()?.map(SchemaInfo::with))
     }
     pub fn schema(&self, schema_id: SchemaId) -> Result<&Schema, StockError<S, H, P>> {
@@ -463,7 +486,7 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
 
     pub fn contracts(
         &self,
-    ) -> Result<impl Iterator<Item = ContractInfo> + '_, StockError<S, H, P>> {
+    ) -> Result<impl ExactSizeIterator<Item = ContractInfo> + '_, StockError<S, H, P>> {
         Ok(self.stash.geneses()?.map(ContractInfo::with))
     }
 
@@ -480,6 +503,62 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         Ok(self.index.contracts_assigning(outputs)?)
     }
 
+    /// Returns, for each of the provided outpoints, the operation outputs of all the contracts
+    /// assigning state to it. The outpoints without any allocation are omitted.
+    #[allow(clippy::type_complexity)]
+    pub fn allocations_at(
+        &self,
+        outpoints: impl IntoIterator<Item = impl Into<Outpoint>>,
+    ) -> Result<BTreeMap<Outpoint, BTreeSet<(ContractId, Opout)>>, StockError<S, H, P>> {
+        let outpoints = outpoints
+            .into_iter()
+            .map(|o| o.into())
+            .collect::<BTreeSet<_>>();
+        Ok(self.index.allocations_at(outpoints)?)
+    }
+
+    /// Returns all the outputs the contract has ever assigned state to, allowing a wallet to
+    /// watch exactly the relevant UTXOs.
+    pub fn outpoints_for_contract(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<BTreeSet<OutputSeal>, StockError<S, H, P>> {
+        Ok(self.index.outpoints_for_contract(contract_id)?)
+    }
+
+    /// Returns all the witnesses known for the bundle, sorted from the most confirmed to the
+    /// least one.
+    pub fn bundle_witnesses(
+        &self,
+        bundle_id: BundleId,
+    ) -> Result<Vec<(Txid, WitnessOrd)>, StockError<S, H, P>> {
+        let (witness_ids, _) = self.index.bundle_info(bundle_id)?;
+        Ok(self.state.witnesses_by_ord(witness_ids)?)
+    }
+
+    /// Returns the most confirmed witness of the bundle, if it is not archived.
+    pub fn latest_witness_for_bundle(
+        &self,
+        bundle_id: BundleId,
+    ) -> Result<Option<(Txid, WitnessOrd)>, StockError<S, H, P>> {
+        Ok(self
+            .bundle_witnesses(bundle_id)?
+            .into_iter()
+            .next()
+            .filter(|(_, ord)| *ord != WitnessOrd::Archived))
+    }
+
+    /// Returns ids of all the known bundles downstream of the operation, in the breadth-first
+    /// order.
+    pub fn descendant_bundles(&self, opid: OpId) -> Result<Vec<BundleId>, StockError<S, H, P>> {
+        Ok(self.index.descendant_bundles(opid)?)
+    }
+
+    /// Returns ids of all the known operations spending the provided opout.
+    pub fn spenders_of(&self, opout: Opout) -> Result<BTreeSet<OpId>, StockError<S, H, P>> {
+        Ok(self.index.spenders_of(opout)?)
+    }
+
     #[allow(clippy::type_complexity)]
     fn contract_raw(
         &self,
@@ -1100,7 +1179,7 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         contract: ValidContract,
         resolver: R,
     ) -> Result<validation::Status, StockError<S, H, P>> {
-        self.consume_consignment(contract, resolver)
+        self.consume_consignment(contract, resolver, None)
     }
 
     pub fn accept_transfer<R: ResolveWitness>(
@@ -1108,19 +1187,44 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         contract: ValidTransfer,
         resolver: R,
     ) -> Result<validation::Status, StockError<S, H, P>> {
-        self.consume_consignment(contract, resolver)
+        self.consume_consignment(contract, resolver, None)
+    }
+
+    /// Same as [`Self::accept_transfer`], but reports the [`ValidationProgress`] to the `progress`
+    /// callback before processing each bundle of the transfer.
+    pub fn accept_transfer_with_progress<R: ResolveWitness>(
+        &mut self,
+        contract: ValidTransfer,
+        resolver: R,
+        progress: &mut dyn FnMut(ValidationProgress),
+    ) -> Result<validation::Status, StockError<S, H, P>> {
+        self.consume_consignment(contract, resolver, Some(progress))
+    }
+
+    /// Computes the changes [`Self::accept_transfer`] would make to the contract state, without
+    /// modifying the stock.
+    pub fn simulate_accept<R: ResolveWitness>(
+        &self,
+        transfer: &ValidTransfer,
+        resolver: R,
+    ) -> Result<StateDiff, StockError<S, H, P>> {
+        let consignment = self
+            .stash
+            .resolve_secrets(transfer.clone().into_consignment())?;
+        Ok(self.state.simulate_consignment(&consignment, resolver)?)
     }
 
     fn consume_consignment<R: ResolveWitness, const TRANSFER: bool>(
         &mut self,
         consignment: ValidConsignment<TRANSFER>,
         resolver: R,
+        progress: Option<&mut dyn FnMut(ValidationProgress)>,
     ) -> Result<validation::Status, StockError<S, H, P>> {
         let (mut consignment, status) = consignment.split();
 
         consignment = self.stash.resolve_secrets(consignment)?;
         self.store_transaction(move |stash, state, index| {
-            state.update_from_consignment(&consignment, &resolver)?;
+            state.update_from_consignment_with_progress(&consignment, &resolver, progress)?;
             index.index_consignment(&consignment)?;
             stash.consume_consignment(consignment)?;
             Ok(())
@@ -1211,9 +1315,13 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         Ok(self.stash.store_secret_seal(seal)?)
     }
 
-    fn set_bundles_as_invalid(&mut self, bundle_id: &BundleId) -> Result<(), StockError<S, H, P>> {
+    fn set_bundles_as_invalid(
+        &mut self,
+        bundle_id: &BundleId,
+        reason: InvalidReason,
+    ) -> Result<(), StockError<S, H, P>> {
         // add bundle to set of invalid bundles
-        self.state.update_bundle(*bundle_id, false)?;
+        self.state.update_bundle(*bundle_id, Some(reason))?;
         let bundle = self.stash.bundle(*bundle_id)?.clone();
         // recursively set all bundle descendants as invalid
         for opid in bundle.known_transitions_opids() {
@@ -1227,7 +1335,7 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
             };
 
             for child_bundle_id in children_bundle_ids {
-                self.set_bundles_as_invalid(&child_bundle_id)?;
+                self.set_bundles_as_invalid(&child_bundle_id, InvalidReason::AncestorInvalid)?;
             }
         }
         Ok(())
@@ -1276,7 +1384,7 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
 
         if valid {
             // remove bundle from set of invalid bundles
-            self.state.update_bundle(*bundle_id, true)?;
+            self.state.update_bundle(*bundle_id, None)?;
             invalid_bundles.remove(bundle_id).unwrap();
             // recursively visit bundle descendants to check if they became valid as well
             for KnownTransition { opid, .. } in bundle.known_transitions {
@@ -1386,7 +1494,7 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
                     .all(|id| !witnesses.get(id).unwrap().is_valid())
                 {
                     // set this bundle and all its descendants as invalid
-                    self.set_bundles_as_invalid(bundle_id)?;
+                    self.set_bundles_as_invalid(bundle_id, InvalidReason::WitnessInvalid)?;
                 }
             }
         }
@@ -1424,6 +1532,24 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         })
     }
 
+    /// Stops tracking the contract, removing its state, history and index entries.
+    ///
+    /// Witnesses shared with other contracts are preserved. Returns the number of witnesses
+    /// removed from the stash.
+    pub fn forget_contract(
+        &mut self,
+        contract_id: ContractId,
+    ) -> Result<usize, StockError<S, H, P>> {
+        let mut removed = 0;
+        self.store_transaction(|stash, state, index| {
+            state.forget_contract(contract_id)?;
+            index.forget_contract(contract_id)?;
+            removed = stash.forget_contract(contract_id)?;
+            Ok(())
+        })?;
+        Ok(removed)
+    }
+
     fn _check_bundle_history(
         &self,
         bundle_id: &BundleId,
@@ -0,0 +1,220 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::StoreTransaction;
+
+/// Transaction spanning the stash, state and index providers, driving their [`StoreTransaction`]
+/// implementations in lockstep.
+///
+/// The providers are committed in the same order used by [`Stock`](super::Stock): index first,
+/// then state and stash. If any of the commits fails, the providers not committed yet are rolled
+/// back, so that a failure to persist the index doesn't leave the stash updated. A transaction
+/// which is dropped without being committed is rolled back.
+///
+/// # Partial commits
+///
+/// [`StoreTransaction`] has no prepare phase, so a provider can't be rolled back once its commit
+/// succeeded: if the state or the stash fails to commit, the providers committed before it (the
+/// index, and the state if the stash fails) keep the changes. The commit is thus all or nothing
+/// only if the index commit is the one failing; otherwise the caller should treat the stored data
+/// as inconsistent, e.g. re-importing the affected consignments.
+pub struct CombinedTransaction<'a, S, H, P>
+where
+    S: StoreTransaction,
+    H: StoreTransaction<TransactionErr = S::TransactionErr>,
+    P: StoreTransaction<TransactionErr = S::TransactionErr>,
+{
+    stash: &'a mut S,
+    state: &'a mut H,
+    index: &'a mut P,
+    finished: bool,
+}
+
+impl<'a, S, H, P> CombinedTransaction<'a, S, H, P>
+where
+    S: StoreTransaction,
+    H: StoreTransaction<TransactionErr = S::TransactionErr>,
+    P: StoreTransaction<TransactionErr = S::TransactionErr>,
+{
+    /// Begin a transaction on each of the providers.
+    ///
+    /// If any of the providers fails to begin the transaction, the transactions already begun are
+    /// rolled back.
+    pub fn begin(
+        stash: &'a mut S,
+        state: &'a mut H,
+        index: &'a mut P,
+    ) -> Result<Self, S::TransactionErr> {
+        stash.begin_transaction()?;
+        state
+            .begin_transaction()
+            .inspect_err(|_| stash.rollback_transaction())?;
+        index.begin_transaction().inspect_err(|_| {
+            state.rollback_transaction();
+            stash.rollback_transaction();
+        })?;
+        Ok(Self {
+            stash,
+            state,
+            index,
+            finished: false,
+        })
+    }
+
+    pub fn stash(&mut self) -> &mut S { self.stash }
+
+    pub fn state(&mut self) -> &mut H { self.state }
+
+    pub fn index(&mut self) -> &mut P { self.index }
+
+    /// Commit the transaction on all the providers, rolling back the ones not committed yet on
+    /// failure; see the [partial commits](Self#partial-commits) section.
+    pub fn commit(mut self) -> Result<(), S::TransactionErr> {
+        self.finished = true;
+        self.index
+            .commit_transaction()
+            .and_then(|_| self.state.commit_transaction())
+            .and_then(|_| self.stash.commit_transaction())
+            .inspect_err(|_| self.rollback_all())
+    }
+
+    /// Roll back the transaction on all the providers.
+    pub fn rollback(mut self) {
+        self.finished = true;
+        self.rollback_all();
+    }
+
+    fn rollback_all(&mut self) {
+        self.state.rollback_transaction();
+        self.stash.rollback_transaction();
+        self.index.rollback_transaction();
+    }
+}
+
+impl<S, H, P> Drop for CombinedTransaction<'_, S, H, P>
+where
+    S: StoreTransaction,
+    H: StoreTransaction<TransactionErr = S::TransactionErr>,
+    P: StoreTransaction<TransactionErr = S::TransactionErr>,
+{
+    fn drop(&mut self) {
+        if !self.finished {
+            self.rollback_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::confinement;
+    use rgb::Schema;
+
+    use super::*;
+    use crate::persistence::{
+        IndexWriteProvider, MemError, MemIndex, MemStash, MemState, StashWriteProvider,
+    };
+
+    /// Provider failing to commit any transaction.
+    struct Failing<T>(T);
+
+    impl<T: StoreTransaction<TransactionErr = MemError>> StoreTransaction for Failing<T> {
+        type TransactionErr = MemError;
+
+        fn begin_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+            self.0.begin_transaction()
+        }
+
+        fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+            Err(confinement::Error::Oversize { len: 1, max_len: 0 }.into())
+        }
+
+        fn rollback_transaction(&mut self) { self.0.rollback_transaction() }
+    }
+
+    fn schema() -> Schema {
+        Schema {
+            ffv: Default::default(),
+            name: strict_encoding::TypeName::from_str("Name").unwrap(),
+            meta_types: Default::default(),
+            global_types: Default::default(),
+            owned_types: Default::default(),
+            genesis: Default::default(),
+            transitions: Default::default(),
+            default_assignment: Default::default(),
+        }
+    }
+
+    #[test]
+    fn commit() {
+        let mut stash = MemStash::in_memory();
+        let mut state = MemState::in_memory();
+        let mut index = MemIndex::in_memory();
+
+        let mut tx = CombinedTransaction::begin(&mut stash, &mut state, &mut index).unwrap();
+        tx.stash().replace_schema(schema()).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(stash.debug_schemata().len(), 1);
+    }
+
+    #[test]
+    fn failing_index_commit() {
+        let mut stash = MemStash::in_memory();
+        let mut state = MemState::in_memory();
+        let mut index = Failing(MemIndex::in_memory());
+
+        let mut tx = CombinedTransaction::begin(&mut stash, &mut state, &mut index).unwrap();
+        tx.stash().replace_schema(schema()).unwrap();
+        tx.index().0.register_contract(strict_dumb!()).unwrap();
+        assert!(tx.commit().is_err());
+
+        assert!(stash.debug_schemata().is_empty());
+        assert!(index.0.debug_contract_index().is_empty());
+    }
+
+    #[test]
+    fn failing_stash_commit() {
+        let mut stash = Failing(MemStash::in_memory());
+        let mut state = MemState::in_memory();
+        let mut index = MemIndex::in_memory();
+
+        let mut tx = CombinedTransaction::begin(&mut stash, &mut state, &mut index).unwrap();
+        tx.stash().0.replace_schema(schema()).unwrap();
+        tx.index().register_contract(strict_dumb!()).unwrap();
+        assert!(tx.commit().is_err());
+
+        // the stash is rolled back, while the already committed index keeps the changes
+        assert!(stash.0.debug_schemata().is_empty());
+        assert_eq!(index.debug_contract_index().len(), 1);
+    }
+
+    #[test]
+    fn drop_rolls_back() {
+        let mut stash = MemStash::in_memory();
+        let mut state = MemState::in_memory();
+        let mut index = MemIndex::in_memory();
+
+        let mut tx = CombinedTransaction::begin(&mut stash, &mut state, &mut index).unwrap();
+        tx.stash().replace_schema(schema()).unwrap();
+        drop(tx);
+
+        assert!(stash.debug_schemata().is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Retrieval of the [`Attachment`] data kept outside of the contract.
+
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+
+use amplify::Bytes32;
+use rgb::commit_verify::{DigestExt, Sha256};
+
+use super::Attachment;
+#[cfg(feature = "fs")]
+use super::MediaType;
+
+/// Errors retrieving the data of an [`Attachment`].
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum AttachmentError {
+    /// data of the attachment with digest {0} are not available.
+    NotFound(Bytes32),
+
+    /// attachment data have digest {actual}, while {expected} is expected.
+    DigestMismatch { expected: Bytes32, actual: Bytes32 },
+
+    /// unable to retrieve attachment data: {0}
+    #[from]
+    Io(io::Error),
+}
+
+impl Attachment {
+    /// Computes the digest of the attachment data, to be used in [`Attachment::digest`].
+    pub fn digest_of(data: &[u8]) -> Bytes32 {
+        let mut hasher = Sha256::default();
+        hasher.input_raw(data);
+        Bytes32::from_byte_array(hasher.finish())
+    }
+
+    /// Checks that `data` matches the attachment digest.
+    pub fn verify_bytes(&self, data: &[u8]) -> bool { Self::digest_of(data) == self.digest }
+
+    /// Returns `data` if they match the attachment digest, failing with
+    /// [`AttachmentError::DigestMismatch`] otherwise.
+    ///
+    /// Implementations of [`AttachmentResolver::fetch`] should pass the retrieved data through it.
+    pub fn checked(&self, data: Vec<u8>) -> Result<Vec<u8>, AttachmentError> {
+        let actual = Self::digest_of(&data);
+        if actual != self.digest {
+            return Err(AttachmentError::DigestMismatch {
+                expected: self.digest,
+                actual,
+            });
+        }
+        Ok(data)
+    }
+}
+
+/// Source of the [`Attachment`] data, which are stored out-of-band and addressed by their digest.
+pub trait AttachmentResolver {
+    /// Returns the data of the `attachment`, which must match the attachment digest.
+    fn fetch(&self, attachment: &Attachment) -> Result<Vec<u8>, AttachmentError>;
+}
+
+/// [`AttachmentResolver`] reading the attachments from a directory, where the data of each
+/// attachment are kept in a file named after the hex representation of its digest.
+#[cfg(feature = "fs")]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct FsAttachmentStore {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl FsAttachmentStore {
+    /// Returns a store using the directory at `dir`, which is created if it doesn't exist.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, digest: Bytes32) -> PathBuf { self.dir.join(digest.to_string()) }
+
+    /// Saves `data` into the store, returning the [`Attachment`] referencing them.
+    pub fn store(&self, ty: MediaType, data: &[u8]) -> io::Result<Attachment> {
+        let digest = Attachment::digest_of(data);
+        std::fs::write(self.path(digest), data)?;
+        Ok(Attachment { ty, digest })
+    }
+}
+
+#[cfg(feature = "fs")]
+impl AttachmentResolver for FsAttachmentStore {
+    fn fetch(&self, attachment: &Attachment) -> Result<Vec<u8>, AttachmentError> {
+        let data = match std::fs::read(self.path(attachment.digest)) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(AttachmentError::NotFound(attachment.digest));
+            }
+            res => res?,
+        };
+        attachment.checked(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stl::MediaType;
+
+    #[test]
+    fn verify_bytes() {
+        let data = b"image data".to_vec();
+        let attachment = Attachment {
+            ty: MediaType::with("image/png"),
+            digest: Attachment::digest_of(&data),
+        };
+        assert!(attachment.verify_bytes(&data));
+        assert!(attachment.checked(data.clone()).is_ok());
+
+        let mut tampered = data;
+        tampered[0] ^= 1;
+        assert!(!attachment.verify_bytes(&tampered));
+        assert!(matches!(
+            attachment.checked(tampered),
+            Err(AttachmentError::DigestMismatch { expected, .. }) if expected == attachment.digest
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn fs_store() {
+        let dir = std::env::temp_dir().join(format!("rgb-attachments-{}", std::process::id()));
+        let store = FsAttachmentStore::new(&dir).unwrap();
+        let attachment = store
+            .store(MediaType::with("image/png"), b"image data")
+            .unwrap();
+        assert_eq!(store.fetch(&attachment).unwrap(), b"image data");
+
+        std::fs::write(store.path(attachment.digest), b"tampered").unwrap();
+        assert!(matches!(store.fetch(&attachment), Err(AttachmentError::DigestMismatch { .. })));
+
+        std::fs::remove_file(store.path(attachment.digest)).unwrap();
+        assert!(matches!(store.fetch(&attachment), Err(AttachmentError::NotFound(_))));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
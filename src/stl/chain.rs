@@ -21,12 +21,38 @@
 
 use amplify::confinement::SmallBlob;
 use rgb::bitcoin::hashes::sha256d;
-use rgb::bitcoin::{OutPoint as Outpoint, Txid};
+use rgb::bitcoin::{Amount, OutPoint as Outpoint, TxOut, Txid};
+use rgb::validation::{ResolveWitness, WitnessResolverError, WitnessStatus};
+use rgb::vm::WitnessOrd;
 use strict_encoding::{StrictDeserialize, StrictSerialize};
 use strict_types::StrictVal;
 
 use super::LIB_NAME_RGB_CONTRACT;
 
+/// Errors from the verification of a [`ProofOfReserves`].
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PoRError {
+    /// unable to resolve the reserves transaction: {0}
+    #[from]
+    Resolver(WitnessResolverError),
+
+    /// reserves transaction {0} is unknown.
+    TxUnknown(Txid),
+
+    /// reserves transaction {0} is not valid on chain ({1}).
+    TxInvalid(Txid, WitnessOrd),
+
+    /// resolver returned transaction {1} while transaction {0} was requested.
+    TxMismatch(Txid, Txid),
+
+    /// reserves transaction doesn't have output {0}.
+    NoOutput(Outpoint),
+
+    /// reserves output holds {actual} while at least {expected} is claimed.
+    InsufficientValue { expected: Amount, actual: Amount },
+}
+
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB_CONTRACT, dumb = ProofOfReserves::new(strict_dumb!(), strict_dumb!()))]
@@ -58,4 +84,101 @@ impl ProofOfReserves {
 
         Self { utxo, proof }
     }
+
+    /// Verifies that the reserves UTXO exists, using the `resolver` to retrieve its transaction.
+    ///
+    /// The transaction must be known to the resolver and be either mined or in the mempool, and it
+    /// must contain the referenced output. Since the resolver can't report whether an output has
+    /// been spent, the UTXO is considered unspent as long as its transaction remains valid. The
+    /// `proof` blob has no format defined by the standard library, so its verification is left to
+    /// the caller.
+    pub fn verify(&self, resolver: &impl ResolveWitness) -> Result<(), PoRError> {
+        self.resolve_output(resolver).map(|_| ())
+    }
+
+    /// Verifies the reserves UTXO like [`ProofOfReserves::verify`], additionally checking that
+    /// it holds at least the `expected` value.
+    pub fn verify_value(
+        &self,
+        resolver: &impl ResolveWitness,
+        expected: Amount,
+    ) -> Result<(), PoRError> {
+        let output = self.resolve_output(resolver)?;
+        if output.value < expected {
+            return Err(PoRError::InsufficientValue {
+                expected,
+                actual: output.value,
+            });
+        }
+        Ok(())
+    }
+
+    fn resolve_output(&self, resolver: &impl ResolveWitness) -> Result<TxOut, PoRError> {
+        let txid = self.utxo.txid;
+        let (tx, ord) = match resolver.resolve_witness(txid)? {
+            WitnessStatus::Unresolved => return Err(PoRError::TxUnknown(txid)),
+            WitnessStatus::Resolved(tx, ord) => (tx, ord),
+        };
+        if !matches!(ord, WitnessOrd::Mined(_) | WitnessOrd::Tentative) {
+            return Err(PoRError::TxInvalid(txid, ord));
+        }
+        let resolved_txid = tx.compute_txid();
+        if resolved_txid != txid {
+            return Err(PoRError::TxMismatch(txid, resolved_txid));
+        }
+        tx.output
+            .get(self.utxo.vout as usize)
+            .cloned()
+            .ok_or(PoRError::NoOutput(self.utxo))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rgb::bitcoin::Transaction as Tx;
+
+    use super::*;
+    use crate::indexers::mock::{self, MockResolver};
+
+    fn tx(value: u64) -> Tx { mock::tx(&[], &[value]) }
+
+    #[test]
+    fn verify_matching_tx() {
+        let tx = tx(100_000);
+        let proof = ProofOfReserves::new(Outpoint::new(tx.compute_txid(), 0), none!());
+        let resolver = MockResolver::new(WitnessStatus::Resolved(tx, WitnessOrd::Tentative));
+        assert_eq!(proof.verify(&resolver), Ok(()));
+        assert_eq!(proof.verify_value(&resolver, Amount::from_sat(100_000)), Ok(()));
+        assert_eq!(
+            proof.verify_value(&resolver, Amount::from_sat(100_001)),
+            Err(PoRError::InsufficientValue {
+                expected: Amount::from_sat(100_001),
+                actual: Amount::from_sat(100_000),
+            })
+        );
+
+        let missing = ProofOfReserves::new(Outpoint::new(proof.utxo.txid, 1), none!());
+        assert_eq!(missing.verify(&resolver), Err(PoRError::NoOutput(missing.utxo)));
+    }
+
+    #[test]
+    fn verify_mismatching_tx() {
+        let claimed = tx(100_000).compute_txid();
+        let other = tx(1);
+        let other_txid = other.compute_txid();
+        let proof = ProofOfReserves::new(Outpoint::new(claimed, 0), none!());
+
+        let resolver = MockResolver::new(WitnessStatus::Resolved(other, WitnessOrd::Tentative));
+        assert_eq!(proof.verify(&resolver), Err(PoRError::TxMismatch(claimed, other_txid)));
+
+        let resolver = MockResolver::new(WitnessStatus::Unresolved);
+        assert_eq!(proof.verify(&resolver), Err(PoRError::TxUnknown(claimed)));
+
+        let resolver =
+            MockResolver::new(WitnessStatus::Resolved(tx(100_000), WitnessOrd::Archived));
+        assert_eq!(
+            proof.verify(&resolver),
+            Err(PoRError::TxInvalid(claimed, WitnessOrd::Archived))
+        );
+    }
 }
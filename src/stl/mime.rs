@@ -26,12 +26,64 @@ use std::str::FromStr;
 
 use strict_encoding::stl::AlphaSmall;
 use strict_encoding::{
-    RString, RestrictedCharSet, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize,
+    InvalidRString, RString, RestrictedCharSet, StrictDeserialize, StrictDumb, StrictEncode,
+    StrictSerialize,
 };
 use strict_types::StrictVal;
 
 use super::LIB_NAME_RGB_CONTRACT;
 
+/// Top-level media types registered by IANA.
+pub const MEDIA_TOP_LEVEL_TYPES: [&str; 11] = [
+    "application",
+    "audio",
+    "example",
+    "font",
+    "haptics",
+    "image",
+    "message",
+    "model",
+    "multipart",
+    "text",
+    "video",
+];
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum MediaError {
+    /// media type '{0}' lacks a subtype.
+    NoSubtype(String),
+
+    /// media type '{0}' has an invalid charset parameter.
+    InvalidCharset(String),
+
+    /// invalid media type name: {0}
+    #[from]
+    InvalidName(InvalidRString),
+
+    /// '{0}' is not a top-level media type registered by IANA.
+    UnknownType(String),
+
+    /// media data size {size} exceeds the maximum of {max} bytes.
+    TooLarge { size: usize, max: usize },
+
+    /// media data look like '{detected}', contradicting the declared '{declared}' media type.
+    ContentMismatch {
+        declared: Box<MediaType>,
+        detected: Box<MediaType>,
+    },
+}
+
+/// Leading magic bytes of some well-known media formats, used to sniff the
+/// actual type of the media data.
+const MEDIA_MAGIC_BYTES: [(&[u8], &str); 5] = [
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+];
+
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB_CONTRACT)]
@@ -62,6 +114,43 @@ impl MediaType {
         }
     }
 
+    /// Checks that the top-level type is registered by IANA and that the subtype, unless it is a
+    /// wildcard, is a valid registered name token.
+    pub fn validate(&self) -> Result<(), MediaError> {
+        if !MEDIA_TOP_LEVEL_TYPES.contains(&self.ty.as_str()) {
+            return Err(MediaError::UnknownType(self.ty.to_string()));
+        }
+        if let Some(subtype) = &self.subtype {
+            MediaRegName::from_str(subtype.as_str())?;
+        }
+        Ok(())
+    }
+
+    /// Detects the media type from the leading (magic) bytes of the data, if they belong to one
+    /// of the well-known formats.
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        MEDIA_MAGIC_BYTES
+            .iter()
+            .find(|(magic, _)| data.starts_with(magic))
+            .map(|(_, ty)| MediaType::with(ty))
+    }
+
+    /// Checks whether the media type matches the other one, treating a missing (wildcard) subtype
+    /// as matching any subtype and ignoring the charset.
+    pub fn matches(&self, other: &MediaType) -> bool {
+        self.ty == other.ty
+            && match (&self.subtype, &other.subtype) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+    }
+
+    pub fn is_image(&self) -> bool { self.ty.as_str() == "image" }
+
+    pub fn is_video(&self) -> bool { self.ty.as_str() == "video" }
+
+    pub fn is_text(&self) -> bool { self.ty.as_str() == "text" }
+
     pub fn from_strict_val_unchecked(value: &StrictVal) -> Self {
         let ty = MediaRegName::from_strict_val_unchecked(value.unwrap_struct("type"));
         let subtype = value
@@ -91,6 +180,36 @@ impl fmt::Display for MediaType {
     }
 }
 
+impl FromStr for MediaType {
+    type Err = MediaError;
+
+    /// Parses media types in the `type/subtype[; charset=name]` form, where the subtype may be a
+    /// `*` wildcard. Names are case-insensitive and are converted to lowercase. The parsed media
+    /// type is not [validated](MediaType::validate).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        let (media, params) = s.split_once(';').unwrap_or((&s, ""));
+        let (ty, subty) = media
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| MediaError::NoSubtype(s.clone()))?;
+        let charset = match params.trim() {
+            "" => None,
+            param => {
+                let name = param
+                    .strip_prefix("charset=")
+                    .ok_or_else(|| MediaError::InvalidCharset(s.clone()))?;
+                Some(MediaRegName::from_str(name.trim_matches('"'))?)
+            }
+        };
+        Ok(MediaType {
+            ty: MediaRegName::from_str(ty)?,
+            subtype: if subty == "*" { None } else { Some(MediaRegName::from_str(subty)?) },
+            charset,
+        })
+    }
+}
+
 #[derive(Wrapper, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, From)]
 #[wrapper(Deref, Display, FromStr)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -212,3 +331,54 @@ pub enum MimeChar {
 }
 
 impl RestrictedCharSet for MimeChar {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_valid() {
+        let media = MediaType::from_str("image/png").unwrap();
+        assert_eq!(media, MediaType::with("image/png"));
+        assert!(media.validate().is_ok());
+        assert!(media.is_image());
+        assert!(!media.is_text());
+
+        let media = MediaType::from_str("Text/Plain; charset=\"UTF-8\"").unwrap();
+        assert_eq!(media.charset, Some(MediaRegName::from("utf-8")));
+        assert!(media.validate().is_ok());
+        assert!(media.is_text());
+
+        let media = MediaType::from_str("video/*").unwrap();
+        assert_eq!(media.subtype, None);
+        assert!(media.validate().is_ok());
+        assert!(media.is_video());
+
+        assert!(MediaType::from_str("application/vnd.api+json")
+            .unwrap()
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn parse_malformed() {
+        assert!(matches!(MediaType::from_str("image"), Err(MediaError::NoSubtype(_))));
+        assert!(matches!(MediaType::from_str("image/"), Err(MediaError::InvalidName(_))));
+        assert!(matches!(MediaType::from_str("/png"), Err(MediaError::InvalidName(_))));
+        assert!(matches!(MediaType::from_str("image/p g"), Err(MediaError::InvalidName(_))));
+        assert!(matches!(
+            MediaType::from_str("text/plain; format=flowed"),
+            Err(MediaError::InvalidCharset(_))
+        ));
+    }
+
+    #[test]
+    fn validate() {
+        assert!(matches!(
+            MediaType::with("notreal/png").validate(),
+            Err(MediaError::UnknownType(_))
+        ));
+        assert!(MediaType::with("font/woff2").validate().is_ok());
+        assert!(MediaType::with("model/*").validate().is_ok());
+    }
+}
@@ -25,15 +25,21 @@ mod stl;
 mod error;
 mod mime;
 mod chain;
+mod attachment;
+mod reject;
 
-pub use chain::ProofOfReserves;
+#[cfg(feature = "fs")]
+pub use attachment::FsAttachmentStore;
+pub use attachment::{AttachmentError, AttachmentResolver};
+pub use chain::{PoRError, ProofOfReserves};
 use error::Error;
 pub use invoice::LIB_NAME_RGB_CONTRACT;
-pub use mime::{MediaRegName, MediaType};
+pub use mime::{MediaError, MediaRegName, MediaType, MEDIA_TOP_LEVEL_TYPES};
+pub use reject::{RejectList, RejectListError, RejectListSource};
 pub use specs::{
-    Article, AssetSpec, Attachment, AttachmentName, AttachmentType, BurnMeta, ContractSpec,
-    ContractTerms, Details, EmbeddedMedia, IssueMeta, Name, RejectListUrl, RicardianContract,
-    Ticker, TokenData,
+    Article, AssetSpec, AssetSpecBuilder, Attachment, AttachmentName, AttachmentType, BurnMeta,
+    ContractSpec, ContractTerms, Details, EmbeddedMedia, IssueMeta, Name, RejectListUrl,
+    RicardianContract, SpecError, Ticker, TokenData, TokenError, MAX_NAME_LEN, MAX_TICKER_LEN,
 };
 pub use stl::{
     aluvm_stl, bitcoin_stl, commit_verify_stl, rgb_commit_stl, rgb_contract_stl, rgb_logic_stl,
@@ -0,0 +1,203 @@
+// RGB ops library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Copyright (C) 2026 RGB-Tools developers. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lists of operations rejected by the contract issuer, published at a [`RejectListUrl`].
+//!
+//! A list can be published in one of two formats:
+//! - text, with the hex id of a rejected operation on each line; empty lines and lines starting
+//!   with `#` are ignored;
+//! - binary, with the strict encoding of a set of operation ids, i.e. the number of the ids as a
+//!   24-bit little-endian integer followed by the 32-byte ids.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use amplify::confinement::U24;
+use rgb::OpId;
+
+use super::RejectListUrl;
+
+/// Errors retrieving or parsing a [`RejectList`].
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum RejectListError {
+    /// unable to fetch the reject list from {0}: {1}
+    Fetch(String, String),
+
+    /// line {0} of the reject list is not a valid operation id.
+    InvalidLine(usize),
+
+    /// reject list is neither a valid text nor a valid binary list.
+    InvalidFormat,
+
+    /// reject list has {0} operations, which exceeds the maximum of 16777215 ids supported by the
+    /// binary format.
+    TooLarge(usize),
+}
+
+/// Source of the data published at a [`RejectListUrl`], e.g. an HTTP client.
+pub trait RejectListSource {
+    /// Returns the data published at `url`, or a description of the failure.
+    fn fetch_data(&self, url: &str) -> Result<Vec<u8>, String>;
+}
+
+impl<F: Fn(&str) -> Result<Vec<u8>, String>> RejectListSource for F {
+    fn fetch_data(&self, url: &str) -> Result<Vec<u8>, String> { self(url) }
+}
+
+/// Set of the operations rejected by a contract issuer.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RejectList(BTreeSet<OpId>);
+
+impl RejectList {
+    /// Fetches the list published at `url` using the provided `source` and parses it.
+    pub fn fetch(
+        url: &RejectListUrl,
+        source: &impl RejectListSource,
+    ) -> Result<Self, RejectListError> {
+        let url = url.to_string();
+        let data = source
+            .fetch_data(&url)
+            .map_err(|e| RejectListError::Fetch(url, e))?;
+        Self::parse(&data)
+    }
+
+    /// Parses a list in any of the supported formats, see the module documentation.
+    pub fn parse(data: &[u8]) -> Result<Self, RejectListError> {
+        if let Some(list) = Self::parse_binary(data) {
+            return Ok(list);
+        }
+        let text = std::str::from_utf8(data).map_err(|_| RejectListError::InvalidFormat)?;
+        Self::parse_text(text)
+    }
+
+    /// Parses a list in the text format, see the module documentation.
+    pub fn parse_text(text: &str) -> Result<Self, RejectListError> {
+        text.lines()
+            .enumerate()
+            .map(|(no, line)| (no + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(no, line)| OpId::from_str(line).map_err(|_| RejectListError::InvalidLine(no)))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    fn parse_binary(data: &[u8]) -> Option<Self> {
+        let (len, ids) = data.split_first_chunk::<3>()?;
+        let len = decode_len(*len);
+        if ids.len() != len * 32 {
+            return None;
+        }
+        ids.chunks_exact(32)
+            .map(|id| OpId::copy_from_slice(id).ok())
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+
+    /// Serializes the list in the binary format.
+    ///
+    /// # Errors
+    ///
+    /// If the list has more ids than the 24-bit length prefix of the binary format can hold.
+    pub fn to_binary(&self) -> Result<Vec<u8>, RejectListError> {
+        let mut data = encode_len(self.0.len())?.to_vec();
+        for opid in &self.0 {
+            data.extend_from_slice(opid.as_slice());
+        }
+        Ok(data)
+    }
+
+    /// Checks whether the operation with the given `opid` is rejected.
+    pub fn contains(&self, opid: OpId) -> bool { self.0.contains(&opid) }
+
+    /// Returns the number of the rejected operations.
+    pub fn len(&self) -> usize { self.0.len() }
+
+    /// Checks whether the list doesn't reject any operation.
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+impl FromIterator<OpId> for RejectList {
+    fn from_iter<T: IntoIterator<Item = OpId>>(iter: T) -> Self { Self(iter.into_iter().collect()) }
+}
+
+fn encode_len(len: usize) -> Result<[u8; 3], RejectListError> {
+    if len > U24 {
+        return Err(RejectListError::TooLarge(len));
+    }
+    let [a, b, c, _] = (len as u32).to_le_bytes();
+    Ok([a, b, c])
+}
+
+fn decode_len(len: [u8; 3]) -> usize { u32::from_le_bytes([len[0], len[1], len[2], 0]) as usize }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn opid(byte: u8) -> OpId { OpId::from_str(&format!("{byte:02x}").repeat(32)).unwrap() }
+
+    #[test]
+    fn parse_text() {
+        let text = format!("# rejected operations\n{}\n\n  {}  \n", opid(1), opid(2));
+        let list = RejectList::parse(text.as_bytes()).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list.contains(opid(1)));
+        assert!(list.contains(opid(2)));
+        assert!(!list.contains(opid(3)));
+
+        let text = format!("{}\nnot an opid\n", opid(1));
+        assert_eq!(RejectList::parse(text.as_bytes()), Err(RejectListError::InvalidLine(2)));
+        assert_eq!(RejectList::parse(&[0xff, 0xfe]), Err(RejectListError::InvalidFormat));
+        assert!(RejectList::parse(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_binary() {
+        let list = RejectList::from_iter([opid(1), opid(2)]);
+        let data = list.to_binary().unwrap();
+        assert_eq!(data.len(), 3 + 2 * 32);
+        assert_eq!(RejectList::parse(&data).unwrap(), list);
+    }
+
+    #[test]
+    fn binary_len_boundary() {
+        for len in [0, 1, 0xFFFF, U24] {
+            assert_eq!(decode_len(encode_len(len).unwrap()), len);
+        }
+        assert_eq!(encode_len(U24), Ok([0xFF; 3]));
+        assert_eq!(encode_len(U24 + 1), Err(RejectListError::TooLarge(U24 + 1)));
+    }
+
+    #[test]
+    fn fetch() {
+        let url = RejectListUrl::from_str("https://example.com/rejected").unwrap();
+        let source = |url: &str| {
+            if url == "https://example.com/rejected" {
+                Ok(opid(7).to_string().into_bytes())
+            } else {
+                Err(s!("not found"))
+            }
+        };
+        let list = RejectList::fetch(&url, &source).unwrap();
+        assert!(list.contains(opid(7)));
+
+        let url = RejectListUrl::from_str("https://example.com/other").unwrap();
+        assert!(matches!(RejectList::fetch(&url, &source), Err(RejectListError::Fetch(..))));
+    }
+}
@@ -21,7 +21,7 @@
 
 #![allow(unused_braces)] // caused by rustc unable to understand strict_dumb
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
 use std::hash::{Hash, Hasher};
 use std::str::FromStr;
@@ -32,6 +32,10 @@ use amplify::confinement::{
 };
 use amplify::Bytes32;
 use invoice::{Precision, TokenIndex};
+use rgb::bitcoin::hashes::{sha256, Hash as _};
+use rgb::commit_verify::{DigestExt, Sha256};
+use rgb::secp256k1::rand::{thread_rng, RngCore};
+use rgb::secp256k1::{schnorr, Keypair, Message, XOnlyPublicKey, SECP256K1};
 use strict_encoding::stl::{Alpha, AlphaNum, AsciiPrintable};
 use strict_encoding::{
     DefaultBasedStrictDumb, InvalidRString, RString, StrictDecode, StrictDeserialize, StrictDumb,
@@ -39,7 +43,7 @@ use strict_encoding::{
 };
 use strict_types::StrictVal;
 
-use super::{MediaType, ProofOfReserves, LIB_NAME_RGB_CONTRACT};
+use super::{MediaError, MediaType, ProofOfReserves, LIB_NAME_RGB_CONTRACT};
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 #[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
@@ -83,6 +87,52 @@ pub struct Article(RString<Alpha, AlphaNum, 1, 32>);
 impl_ident_type!(Article);
 impl_ident_subtype!(Article);
 
+/// Errors from the checked construction of asset specification fields.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SpecError {
+    /// ticker must contain at least one character.
+    TickerEmpty,
+
+    /// ticker has {0} characters, while at most 8 are allowed.
+    TickerTooLong(usize),
+
+    /// ticker contains character '{0}', while only uppercase letters A-Z are allowed.
+    TickerInvalidChar(char),
+
+    /// name must contain at least one non-whitespace character.
+    NameEmpty,
+
+    /// name has {0} characters, while at most 40 are allowed.
+    NameTooLong(usize),
+
+    /// name contains non-printable character {0:?}.
+    NameInvalidChar(char),
+
+    /// name must not start or end with whitespace.
+    NameUntrimmed,
+
+    /// asset specification lacks a ticker.
+    NoTicker,
+
+    /// asset specification lacks a name.
+    NoName,
+
+    /// asset details can't be provided without an asset name.
+    DetailsWithoutName,
+
+    /// invalid asset details: {0}
+    InvalidDetails(InvalidRString),
+
+    /// precision of {0} decimal digits exceeds the maximum of 18.
+    PrecisionTooHigh(u8),
+}
+
+/// Maximal length of an asset ticker.
+pub const MAX_TICKER_LEN: usize = 8;
+/// Maximal length of an asset name.
+pub const MAX_NAME_LEN: usize = 40;
+
 #[derive(Wrapper, Clone, Ord, PartialOrd, Eq, From)]
 #[wrapper(Deref, Display, FromStr)]
 #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
@@ -109,6 +159,26 @@ impl Hash for Ticker {
 impl_ident_type!(Ticker);
 impl_ident_subtype!(Ticker);
 
+impl Ticker {
+    /// Constructs a ticker enforcing the RGB contract rules: from 1 to [`MAX_TICKER_LEN`]
+    /// uppercase letters A-Z.
+    ///
+    /// Unlike the [`FromStr`] implementation, which only checks the string against the ticker
+    /// type encoding, this reports the exact rule being violated.
+    pub fn try_with(s: &str) -> Result<Self, SpecError> {
+        if s.is_empty() {
+            return Err(SpecError::TickerEmpty);
+        }
+        if let Some(c) = s.chars().find(|c| !c.is_ascii_uppercase()) {
+            return Err(SpecError::TickerInvalidChar(c));
+        }
+        if s.len() > MAX_TICKER_LEN {
+            return Err(SpecError::TickerTooLong(s.len()));
+        }
+        Ok(Ticker::from_str(s).expect("ticker rules are stricter than its type"))
+    }
+}
+
 #[derive(Wrapper, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, From)]
 #[wrapper(Deref, Display, FromStr)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -127,6 +197,27 @@ impl_ident_type!(Name);
 impl_ident_subtype!(Name);
 
 impl Name {
+    /// Constructs a name enforcing the RGB contract rules: from 1 to [`MAX_NAME_LEN`] printable
+    /// ASCII characters, without leading or trailing whitespace.
+    ///
+    /// Unlike the [`FromStr`] implementation, which only checks the string against the name type
+    /// encoding, this reports the exact rule being violated.
+    pub fn try_with(s: &str) -> Result<Self, SpecError> {
+        if s.trim().is_empty() {
+            return Err(SpecError::NameEmpty);
+        }
+        if let Some(c) = s.chars().find(|c| !(c.is_ascii_graphic() || *c == ' ')) {
+            return Err(SpecError::NameInvalidChar(c));
+        }
+        if s.trim() != s {
+            return Err(SpecError::NameUntrimmed);
+        }
+        if s.len() > MAX_NAME_LEN {
+            return Err(SpecError::NameTooLong(s.len()));
+        }
+        Ok(Name::from_str(s).expect("name rules are stricter than its type"))
+    }
+
     pub fn from_strict_val_unchecked(value: &StrictVal) -> Self {
         Name::from_str(&value.unwrap_string()).unwrap()
     }
@@ -210,6 +301,9 @@ impl StrictSerialize for AssetSpec {}
 impl StrictDeserialize for AssetSpec {}
 
 impl AssetSpec {
+    /// Starts building an [`AssetSpec`] with checks of each field and across fields.
+    pub fn builder() -> AssetSpecBuilder { AssetSpecBuilder::default() }
+
     pub fn new(ticker: &'static str, name: &'static str, precision: Precision) -> AssetSpec {
         AssetSpec {
             ticker: Ticker::from(ticker),
@@ -260,6 +354,67 @@ impl AssetSpec {
     pub fn details(&self) -> Option<&str> { self.details.as_ref().map(|d| d.as_str()) }
 }
 
+/// Builder of [`AssetSpec`] validating the fields with the RGB contract rules (see
+/// [`Ticker::try_with`] and [`Name::try_with`]) and checking their consistency.
+///
+/// When no precision is given, the [default](Precision::default) one is used.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct AssetSpecBuilder {
+    ticker: Option<String>,
+    name: Option<String>,
+    details: Option<String>,
+    precision: Option<u8>,
+}
+
+impl AssetSpecBuilder {
+    pub fn ticker(mut self, ticker: impl Into<String>) -> Self {
+        self.ticker = Some(ticker.into());
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    /// Sets the precision as the number of decimal digits.
+    pub fn precision(mut self, decimals: u8) -> Self {
+        self.precision = Some(decimals);
+        self
+    }
+
+    pub fn build(self) -> Result<AssetSpec, SpecError> {
+        if self.details.is_some() && self.name.is_none() {
+            return Err(SpecError::DetailsWithoutName);
+        }
+        let ticker = Ticker::try_with(&self.ticker.ok_or(SpecError::NoTicker)?)?;
+        let name = Name::try_with(&self.name.ok_or(SpecError::NoName)?)?;
+        let details = self
+            .details
+            .as_deref()
+            .map(Details::from_str)
+            .transpose()
+            .map_err(SpecError::InvalidDetails)?;
+        let precision = match self.precision {
+            Some(decimals) => {
+                Precision::try_from(decimals).map_err(|_| SpecError::PrecisionTooHigh(decimals))?
+            }
+            None => Precision::default(),
+        };
+        Ok(AssetSpec {
+            ticker,
+            name,
+            details,
+            precision,
+        })
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB_CONTRACT)]
@@ -349,6 +504,52 @@ impl AsRef<str> for RicardianContract {
     fn as_ref(&self) -> &str { self.0.as_str() }
 }
 
+impl RicardianContract {
+    /// Tag of the hash returned by [`Self::commitment`].
+    ///
+    /// The tag follows the `urn:lnp-bp:rgb:<name>#<date>` scheme of the other RGB tagged hashes,
+    /// like the kit and consignment ids. It is a part of the protocol: changing it changes the
+    /// commitment of every contract text, invalidating all the signatures made before.
+    pub const COMMITMENT_TAG: &'static str = "urn:lnp-bp:rgb:ricardian-contract#2026-10-16";
+
+    /// Returns the contract text with all the line endings (CRLF, CR or LF) normalized to LF.
+    pub fn normalized(&self) -> String { self.0.as_str().replace("\r\n", "\n").replace('\r', "\n") }
+
+    /// Computes a tagged SHA256 hash of the [`Self::normalized`] contract text, which is the same
+    /// for all copies of the contract text regardless of the line endings used by the platform.
+    ///
+    /// The parties sign this hash to confirm they agreed to the same terms.
+    pub fn commitment(&self) -> sha256::Hash {
+        let mut hasher = Sha256::from_tag(Self::COMMITMENT_TAG);
+        hasher.input_raw(self.normalized().as_bytes());
+        sha256::Hash::from_byte_array(hasher.finish())
+    }
+
+    /// Signs the contract [`Self::commitment`] with a BIP-340 signature.
+    ///
+    /// The signature is detached, i.e. it is not a part of the contract data and must be kept or
+    /// transferred by the parties next to the contract. The nonce is derived with fresh auxiliary
+    /// randomness, as recommended by BIP-340, so signing the same contract twice produces different
+    /// (but equally valid) signatures.
+    pub fn sign(&self, keypair: &Keypair) -> schnorr::Signature {
+        let msg = Message::from_digest(self.commitment().to_byte_array());
+        let mut aux_rand = [0u8; 32];
+        thread_rng().fill_bytes(&mut aux_rand);
+        SECP256K1.sign_schnorr_with_aux_rand(&msg, keypair, &aux_rand)
+    }
+
+    /// Verifies a detached signature of the contract [`Self::commitment`] made with
+    /// [`Self::sign`].
+    pub fn verify_signature(
+        &self,
+        signature: &schnorr::Signature,
+        pubkey: &XOnlyPublicKey,
+    ) -> bool {
+        let msg = Message::from_digest(self.commitment().to_byte_array());
+        SECP256K1.verify_schnorr(signature, &msg, pubkey).is_ok()
+    }
+}
+
 impl FromStr for RicardianContract {
     type Err = InvalidRString;
 
@@ -430,6 +631,38 @@ pub struct EmbeddedMedia {
 }
 
 impl EmbeddedMedia {
+    /// Constructs embedded media, checking that the data do not exceed `max_size` bytes and that
+    /// their leading bytes do not contradict the declared media type.
+    ///
+    /// Constructing the structure directly skips these checks.
+    pub fn new_checked(
+        ty: MediaType,
+        data: impl Into<Vec<u8>>,
+        max_size: usize,
+    ) -> Result<Self, MediaError> {
+        ty.validate()?;
+        let data = data.into();
+        let max = max_size.min(u16::MAX as usize);
+        if data.len() > max {
+            return Err(MediaError::TooLarge {
+                size: data.len(),
+                max,
+            });
+        }
+        if let Some(detected) = MediaType::sniff(&data) {
+            if !ty.matches(&detected) {
+                return Err(MediaError::ContentMismatch {
+                    declared: Box::new(ty),
+                    detected: Box::new(detected),
+                });
+            }
+        }
+        Ok(Self {
+            ty,
+            data: SmallBlob::try_from(data).expect("data size checked above"),
+        })
+    }
+
     pub fn from_strict_val_unchecked(value: &StrictVal) -> Self {
         let ty = MediaType::from_strict_val_unchecked(value.unwrap_struct("type"));
         let data = SmallBlob::from_iter_checked(
@@ -440,7 +673,7 @@ impl EmbeddedMedia {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB_CONTRACT, dumb = { AttachmentType::with(0, "dumb") })]
 #[cfg_attr(
@@ -517,6 +750,18 @@ impl Debug for AttachmentName {
     }
 }
 
+/// Errors from the validation of [`TokenData`].
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TokenError {
+    /// token {token} references attachment type {attachment} which is not defined by the
+    /// contract.
+    UnknownAttachment { token: TokenIndex, attachment: u8 },
+
+    /// token index {0} is used by more than one token of the collection.
+    DuplicateIndex(TokenIndex),
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB_CONTRACT)]
@@ -541,6 +786,38 @@ impl StrictSerialize for TokenData {}
 impl StrictDeserialize for TokenData {}
 
 impl TokenData {
+    /// Checks that every attachment of the token refers to one of the `known_attachments` types
+    /// defined by the contract.
+    pub fn validate(&self, known_attachments: &BTreeSet<AttachmentType>) -> Result<(), TokenError> {
+        match self
+            .attachments
+            .keys()
+            .find(|id| !known_attachments.iter().any(|known| known.id == **id))
+        {
+            Some(id) => Err(TokenError::UnknownAttachment {
+                token: self.index,
+                attachment: *id,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates each token of a collection with [`TokenData::validate`], checking also that no
+    /// token index is used more than once.
+    pub fn validate_collection<'a>(
+        tokens: impl IntoIterator<Item = &'a TokenData>,
+        known_attachments: &BTreeSet<AttachmentType>,
+    ) -> Result<(), TokenError> {
+        let mut indexes = BTreeSet::new();
+        for token in tokens {
+            if !indexes.insert(token.index) {
+                return Err(TokenError::DuplicateIndex(token.index));
+            }
+            token.validate(known_attachments)?;
+        }
+        Ok(())
+    }
+
     pub fn from_strict_val_unchecked(value: &StrictVal) -> Self {
         let index = TokenIndex::from(
             value
@@ -620,3 +897,163 @@ impl RejectListUrl {
         RejectListUrl::from_str(&value.unwrap_string()).unwrap()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ricardian_commitment() {
+        let lf = RicardianContract::from_str("Terms\nof the\ncontract\n").unwrap();
+        let crlf = RicardianContract::from_str("Terms\r\nof the\r\ncontract\r\n").unwrap();
+        let cr = RicardianContract::from_str("Terms\rof the\rcontract\r").unwrap();
+        let other = RicardianContract::from_str("Other terms\n").unwrap();
+        assert_eq!(lf.normalized(), crlf.normalized());
+        assert_eq!(lf.commitment(), crlf.commitment());
+        assert_eq!(lf.commitment(), cr.commitment());
+        assert_ne!(lf.commitment(), other.commitment());
+        // the commitment is a part of the protocol and must never change
+        assert_eq!(
+            lf.commitment().to_string(),
+            "463b4f131312eae3204bdf0cfd30d0cbb7bafd2c0fa0266f9d0a2e4d25e29dab"
+        );
+
+        let keypair = Keypair::from_seckey_slice(SECP256K1, &[1; 32]).unwrap();
+        let (pubkey, _) = keypair.x_only_public_key();
+        let signature = lf.sign(&keypair);
+        assert!(lf.verify_signature(&signature, &pubkey));
+        // signatures use auxiliary randomness, yet all of them are valid
+        let another = lf.sign(&keypair);
+        assert_ne!(signature, another);
+        assert!(lf.verify_signature(&another, &pubkey));
+        assert!(crlf.verify_signature(&signature, &pubkey));
+        assert!(!other.verify_signature(&signature, &pubkey));
+        let stranger = Keypair::from_seckey_slice(SECP256K1, &[2; 32]).unwrap();
+        assert!(!lf.verify_signature(&signature, &stranger.x_only_public_key().0));
+    }
+
+    #[test]
+    fn embedded_media_checked() {
+        let png = b"\x89PNG\r\n\x1a\nimage data".to_vec();
+        let media =
+            EmbeddedMedia::new_checked(MediaType::with("image/png"), png.clone(), 1024).unwrap();
+        assert_eq!(media.data.as_slice(), png.as_slice());
+        EmbeddedMedia::new_checked(MediaType::with("image/*"), png.clone(), 1024).unwrap();
+        EmbeddedMedia::new_checked(MediaType::with("text/plain"), b"plain text".to_vec(), 1024)
+            .unwrap();
+
+        assert_eq!(
+            EmbeddedMedia::new_checked(MediaType::with("image/jpeg"), png.clone(), 1024),
+            Err(MediaError::ContentMismatch {
+                declared: Box::new(MediaType::with("image/jpeg")),
+                detected: Box::new(MediaType::with("image/png")),
+            })
+        );
+        assert_eq!(
+            EmbeddedMedia::new_checked(MediaType::with("image/png"), vec![0u8; 2048], 1024),
+            Err(MediaError::TooLarge {
+                size: 2048,
+                max: 1024
+            })
+        );
+        assert_eq!(
+            EmbeddedMedia::new_checked(MediaType::with("image/png"), vec![0u8; 70_000], usize::MAX),
+            Err(MediaError::TooLarge {
+                size: 70_000,
+                max: u16::MAX as usize
+            })
+        );
+    }
+
+    #[test]
+    fn token_data_validation() {
+        let attachment = Attachment {
+            ty: MediaType::with("image/png"),
+            digest: Bytes32::from_array([1; 32]),
+        };
+        let known = bset! { AttachmentType::with(0, "image"), AttachmentType::with(1, "video") };
+        let token = |index: u32, attachments: &[u8]| TokenData {
+            index: TokenIndex::from(index),
+            attachments: Confined::from_iter_checked(
+                attachments.iter().map(|id| (*id, attachment.clone())),
+            ),
+            ..default!()
+        };
+
+        assert_eq!(token(1, &[0, 1]).validate(&known), Ok(()));
+        assert_eq!(
+            token(2, &[0, 2]).validate(&known),
+            Err(TokenError::UnknownAttachment {
+                token: TokenIndex::from(2),
+                attachment: 2
+            })
+        );
+
+        let tokens = [token(1, &[0]), token(2, &[]), token(1, &[1])];
+        assert_eq!(TokenData::validate_collection(&tokens[..2], &known), Ok(()));
+        assert_eq!(
+            TokenData::validate_collection(&tokens, &known),
+            Err(TokenError::DuplicateIndex(TokenIndex::from(1)))
+        );
+    }
+
+    #[test]
+    fn ticker_rules() {
+        assert_eq!(Ticker::try_with("USDT").unwrap().as_str(), "USDT");
+        assert_eq!(Ticker::try_with(""), Err(SpecError::TickerEmpty));
+        assert_eq!(Ticker::try_with("usdt"), Err(SpecError::TickerInvalidChar('u')));
+        assert_eq!(Ticker::try_with("BTC2"), Err(SpecError::TickerInvalidChar('2')));
+        assert_eq!(Ticker::try_with("ABCDEFGHI"), Err(SpecError::TickerTooLong(9)));
+    }
+
+    #[test]
+    fn asset_spec_builder() {
+        let spec = AssetSpec::builder()
+            .ticker("USDT")
+            .name("Tether USD")
+            .details("Stablecoin")
+            .precision(6)
+            .build()
+            .unwrap();
+        assert_eq!(spec, AssetSpec {
+            ticker: Ticker::from("USDT"),
+            name: Name::from("Tether USD"),
+            details: Some(Details::from("Stablecoin")),
+            precision: Precision::Micro,
+        });
+
+        let builder = AssetSpec::builder().ticker("USDT").name("Tether USD");
+        assert_eq!(builder.clone().build().unwrap().precision, Precision::default());
+        assert_eq!(builder.clone().precision(18).build().unwrap().precision, Precision::Atto);
+        assert_eq!(builder.precision(19).build(), Err(SpecError::PrecisionTooHigh(19)));
+
+        assert_eq!(
+            AssetSpec::builder()
+                .ticker("USDT")
+                .details("Stablecoin")
+                .build(),
+            Err(SpecError::DetailsWithoutName)
+        );
+        assert_eq!(AssetSpec::builder().name("Tether USD").build(), Err(SpecError::NoTicker));
+        assert_eq!(
+            AssetSpec::builder()
+                .ticker("usdt")
+                .name("Tether USD")
+                .build(),
+            Err(SpecError::TickerInvalidChar('u'))
+        );
+    }
+
+    #[test]
+    fn name_rules() {
+        assert_eq!(Name::try_with("Tether USD").unwrap().as_str(), "Tether USD");
+        assert_eq!(Name::try_with(""), Err(SpecError::NameEmpty));
+        assert_eq!(Name::try_with("   "), Err(SpecError::NameEmpty));
+        assert_eq!(Name::try_with(" Tether"), Err(SpecError::NameUntrimmed));
+        assert_eq!(Name::try_with("Tether\nUSD"), Err(SpecError::NameInvalidChar('\n')));
+        assert_eq!(Name::try_with("Tether\u{7}"), Err(SpecError::NameInvalidChar('\u{7}')));
+        assert_eq!(Name::try_with("Tëther"), Err(SpecError::NameInvalidChar('ë')));
+        assert_eq!(Name::try_with(&"N".repeat(41)), Err(SpecError::NameTooLong(41)));
+        assert!(Name::try_with(&"N".repeat(40)).is_ok());
+    }
+}
@@ -42,7 +42,7 @@ use crate::LIB_NAME_RGB_OPS;
 /// Strict types id for the library providing standard data types which may be
 /// used in RGB smart contracts.
 pub const LIB_ID_RGB_STORAGE: &str =
-    "stl:F7wSSbxs-BSP1l1q-QGgnevO-yidno7Q-_~UaHEp-auTjKaw#miller-product-retro";
+    "stl:Erpj6Hic-BNOecL5-3AEoKN3-HkymgRv-ioRlw4I-8OrE~bI#portal-laura-office";
 
 /// Strict types id for the library providing standard data types which may be
 /// used in RGB smart contracts.
@@ -51,7 +51,7 @@ pub const LIB_ID_RGB_CONTRACT: &str =
 
 /// Strict types id for the library representing of RGB Ops data types.
 pub const LIB_ID_RGB_OPS: &str =
-    "stl:QqYi2ogb-bIP5EMk-YaIQeXj-L7lgzOg-gN4Ab6k-6NzZRAI#valery-annual-beach";
+    "stl:aIi8o1vL-NqOVUrz-ZK1FV~1-~R~Jp8n-apbHe4_-BdVrCEA#finance-mercury-maximum";
 
 /// Generates strict type library representation of RGB Ops data types.
 pub fn rgb_ops_stl() -> TypeLib {